@@ -1,3 +1,4 @@
+use num_complex::Complex;
 use qsim::Gate;
 use std::fmt;
 use std::str::FromStr;
@@ -62,11 +63,35 @@ impl Default for PauliTerm {
 #[derive(Debug, PartialEq, Eq)]
 pub struct PauliTermParseError;
 
+/// A term acts on `qubit`, which does not exist in a `num_qubits`-qubit register.
+#[derive(Debug, PartialEq, Eq)]
+pub struct QubitOutOfRange {
+    pub qubit: usize,
+    pub num_qubits: usize,
+}
+
+impl fmt::Display for QubitOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "term acts on qubit {} of a {}-qubit register",
+            self.qubit, self.num_qubits
+        )
+    }
+}
+
+impl std::error::Error for QubitOutOfRange {}
+
 impl FromStr for PauliTerm {
     type Err = PauliTermParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parts: Vec<&str> = s.split('*').map(|p| p.trim()).collect();
+        // A bare coefficient is an identity term, which is how `Display` writes one.
+        if parts.len() == 1 {
+            let coefficient = parts[0].parse::<f64>().map_err(|_| PauliTermParseError)?;
+            return Ok(PauliTerm::new().with_coefficient(coefficient));
+        }
         if parts.len() != 2 {
             return Err(PauliTermParseError);
         }
@@ -99,16 +124,64 @@ impl FromStr for PauliTerm {
     }
 }
 
-impl fmt::Display for PauliTerm {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:.8}", self.coefficient)?;
+/// Rounds to `digits` decimal places, then drops trailing zeros (and a bare trailing '.').
+fn format_coefficient(coefficient: f64, digits: usize) -> String {
+    let formatted = format!("{:.*}", digits, coefficient);
+    if formatted.contains('.') {
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        formatted
+    }
+}
+
+impl PauliTerm {
+    /// Like `Display`, but with the coefficient rounded to `digits` decimal places.
+    pub fn to_string_with_precision(&self, digits: usize) -> String {
+        self.render(&format_coefficient(self.coefficient, digits))
+    }
+
+    fn render(&self, coefficient: &str) -> String {
+        let mut out = coefficient.to_string();
         if !self.operators.is_empty() {
-            write!(f, " *")?;
+            out.push_str(" *");
             for (pauli, qubit_index) in &self.operators {
-                write!(f, " {}{}", pauli, qubit_index)?;
+                out.push_str(&format!(" {}{}", pauli, qubit_index));
             }
         }
-        Ok(())
+        out
+    }
+
+    /// The term applied to the basis state |basis⟩. Every Pauli maps a basis state to a
+    /// single basis state times a phase, so the result is one `(basis, amplitude)` pair.
+    /// Callers must have checked that every operator fits the register.
+    fn apply_to_basis_state(&self, basis: usize) -> (usize, Complex<f64>) {
+        let mut state = basis;
+        let mut amplitude = Complex::new(self.coefficient, 0.0);
+        for &(pauli, qubit) in &self.operators {
+            let sign = if (state >> qubit) & 1 == 0 { 1.0 } else { -1.0 };
+            match pauli {
+                Pauli::I => {}
+                Pauli::X => state ^= 1 << qubit,
+                Pauli::Y => {
+                    // Y|0⟩ = i|1⟩, Y|1⟩ = -i|0⟩.
+                    amplitude *= Complex::new(0.0, sign);
+                    state ^= 1 << qubit;
+                }
+                Pauli::Z => amplitude *= sign,
+            }
+        }
+        (state, amplitude)
+    }
+}
+
+/// The coefficient is written in its shortest exact form (`0.5`, not `0.50000000`), so the
+/// output parses back to the same term with `from_str`.
+impl fmt::Display for PauliTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(&self.coefficient.to_string()))
     }
 }
 
@@ -131,6 +204,47 @@ impl Hamiltonian {
         self.add_term(term);
         self
     }
+
+    /// Like `Display`, but with every coefficient rounded to `digits` decimal places and
+    /// trailing zeros trimmed.
+    pub fn to_string_with_precision(&self, digits: usize) -> String {
+        self.terms
+            .iter()
+            .map(|term| term.to_string_with_precision(digits))
+            .collect::<Vec<_>>()
+            .join("\n+ ")
+    }
+
+    /// The dense 2^n x 2^n matrix of the Hamiltonian on `num_qubits` qubits. Row and column
+    /// `j` correspond to the basis state |j⟩, with qubit 0 as the least significant bit.
+    ///
+    /// Fails if a term acts on a qubit outside the register.
+    pub fn to_matrix(&self, num_qubits: usize) -> Result<Vec<Vec<Complex<f64>>>, QubitOutOfRange> {
+        let out_of_range = self
+            .terms
+            .iter()
+            .flat_map(|term| &term.operators)
+            .find(|&&(_, qubit)| qubit >= num_qubits);
+        if let Some(&(_, qubit)) = out_of_range {
+            return Err(QubitOutOfRange { qubit, num_qubits });
+        }
+
+        let dim = 1 << num_qubits;
+        let mut matrix = vec![vec![Complex::new(0.0, 0.0); dim]; dim];
+        for term in &self.terms {
+            let images = (0..dim).map(|column| (column, term.apply_to_basis_state(column)));
+            for (column, (row, amplitude)) in images {
+                matrix[row][column] += amplitude;
+            }
+        }
+        Ok(matrix)
+    }
+
+    /// The smallest eigenvalue of the Hamiltonian on `num_qubits` qubits, found by exact
+    /// diagonalization. Use it as a reference for variational results on small systems.
+    pub fn exact_ground_state_energy(&self, num_qubits: usize) -> Result<f64, QubitOutOfRange> {
+        Ok(qsim::hermitian_eigenvalues(&self.to_matrix(num_qubits)?)[0])
+    }
 }
 
 /// Display trait for the entire Hamiltonian.
@@ -172,4 +286,64 @@ mod tests {
         assert!(display_str.contains("-0.8126"));
         assert!(display_str.contains("X0 X1"));
     }
+
+    #[test]
+    fn test_h2_exact_ground_state_energy() {
+        let h2_hamiltonian = Hamiltonian::new()
+            .with_term(PauliTerm::from_str("-0.8126 * I0").unwrap())
+            .with_term(PauliTerm::from_str("0.1712 * Z0").unwrap())
+            .with_term(PauliTerm::from_str("-0.2228 * Z1").unwrap())
+            .with_term(PauliTerm::from_str("0.1686 * Z0 Z1").unwrap())
+            .with_term(PauliTerm::from_str("0.0453 * X0 X1").unwrap());
+
+        // X0 X1 only couples |01⟩ with |10⟩ (and |00⟩ with |11⟩). The ground state lives in
+        // the first block, with diagonal -1.3752 and -0.5872.
+        let (a, d, b) = (-1.3752_f64, -0.5872_f64, 0.0453_f64);
+        let expected = (a + d) / 2.0 - (((a - d) / 2.0).powi(2) + b * b).sqrt();
+        let energy = h2_hamiltonian.exact_ground_state_energy(2).unwrap();
+        assert!(
+            (energy - expected).abs() < 1e-9,
+            "{} != {}",
+            energy,
+            expected
+        );
+        assert!((energy - -1.3778).abs() < 1e-4);
+
+        // Y terms make the matrix complex: Y0 has eigenvalues ±1.
+        let y = Hamiltonian::new().with_term(PauliTerm::new().with_pauli(0, Pauli::Y));
+        assert_eq!(y.to_matrix(1).unwrap()[1][0], Complex::new(0.0, 1.0));
+        assert!((y.exact_ground_state_energy(1).unwrap() + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_to_matrix_rejects_qubit_outside_register() {
+        let h = Hamiltonian::new().with_term(PauliTerm::from_str("0.5 * Z0 X2").unwrap());
+        assert_eq!(
+            h.to_matrix(2),
+            Err(QubitOutOfRange {
+                qubit: 2,
+                num_qubits: 2
+            })
+        );
+        assert!(h.exact_ground_state_energy(2).is_err());
+        assert!(h.exact_ground_state_energy(3).is_ok());
+    }
+
+    #[test]
+    fn test_coefficient_display_round_trips() {
+        let term = PauliTerm::from_str("0.5 * Z0").unwrap();
+        assert_eq!(term.to_string(), "0.5 * Z0");
+        assert_eq!(PauliTerm::from_str(&term.to_string()).unwrap(), term);
+
+        // Tiny coefficients keep every digit by default.
+        let tiny = PauliTerm::new().with_coefficient(1.25e-9);
+        assert_eq!(PauliTerm::from_str(&tiny.to_string()).unwrap(), tiny);
+
+        let h = Hamiltonian::new()
+            .with_term(PauliTerm::new().with_coefficient(-0.8126))
+            .with_term(PauliTerm::from_str("0.123456 * X0 X1").unwrap());
+        assert_eq!(h.to_string_with_precision(3), "-0.813\n+ 0.123 * X0 X1");
+        assert_eq!(h.to_string_with_precision(8), "-0.8126\n+ 0.123456 * X0 X1");
+        assert_eq!(PauliTerm::from_str("-0.8126").unwrap(), h.terms[0]);
+    }
 }
@@ -1,27 +1,40 @@
-use ndarray::ArrayView1;
-use numpy::PyReadonlyArray1;
+use ndarray::{Array2, ArrayView1, ArrayView2};
+use numpy::{IntoPyArray, PyArray2, PyReadonlyArray1, PyReadonlyArray2};
 use pyo3::prelude::*;
-use qsim::QuantumSimulator;
 use qsim::simulator::Simulator;
+use qsim::{QuantumSimulator, StateVector};
 
-fn compute_kernel_value(v1: ArrayView1<f64>, v2: ArrayView1<f64>) -> f64 {
-    let num_qubits = v1.len();
-    let mut sim1 = QuantumSimulator::new(num_qubits);
-    let mut sim2 = QuantumSimulator::new(num_qubits);
+/// Prepares the feature-map state for one data point.
+fn encode(v: ArrayView1<f64>) -> StateVector {
+    let mut sim = QuantumSimulator::new(v.len());
 
-    // Example encoding: apply Ry rotations with angles from v1 and v2
-    for (i, &theta) in v1.iter().enumerate() {
-        sim1.apply_gate(&qsim::Gate::RY { qubit: i, theta });
-    }
-    for (i, &theta) in v2.iter().enumerate() {
-        sim2.apply_gate(&qsim::Gate::RY { qubit: i, theta });
+    // Example encoding: apply Ry rotations with angles from the data point
+    for (i, &theta) in v.iter().enumerate() {
+        sim.apply_gate(&qsim::Gate::RY { qubit: i, theta });
     }
+    sim.get_statevector().clone()
+}
 
+fn compute_kernel_value(v1: ArrayView1<f64>, v2: ArrayView1<f64>) -> f64 {
     // Compute fidelity between the two statevectors as the kernel value
-    let state1 = sim1.get_statevector();
-    let state2 = sim2.get_statevector();
-    let fidelity = state1.fidelity(state2);
-    fidelity
+    encode(v1).fidelity(&encode(v2))
+}
+
+/// Computes the Gram matrix of the kernel over the rows of `x`. Each row is encoded once and
+/// only the upper triangle is evaluated, the lower one is filled in by symmetry.
+fn compute_kernel_matrix(x: ArrayView2<f64>) -> Array2<f64> {
+    let states: Vec<StateVector> = x.rows().into_iter().map(encode).collect();
+    let n = states.len();
+    let mut gram = Array2::zeros((n, n));
+    for i in 0..n {
+        gram[[i, i]] = 1.0;
+        for j in i + 1..n {
+            let fidelity = states[i].fidelity(&states[j]);
+            gram[[i, j]] = fidelity;
+            gram[[j, i]] = fidelity;
+        }
+    }
+    gram
 }
 
 #[pyfunction]
@@ -31,8 +44,38 @@ fn quantum_kernel(x1: PyReadonlyArray1<f64>, x2: PyReadonlyArray1<f64>) -> PyRes
     Ok(compute_kernel_value(x1, x2))
 }
 
+#[pyfunction]
+fn quantum_kernel_matrix(py: Python<'_>, x: PyReadonlyArray2<f64>) -> Py<PyArray2<f64>> {
+    compute_kernel_matrix(x.as_array())
+        .into_pyarray(py)
+        .unbind()
+}
+
 #[pymodule]
 fn quantum_kernel_lib(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(quantum_kernel, m)?)?;
+    m.add_function(wrap_pyfunction!(quantum_kernel_matrix, m)?)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_kernel_matrix_is_symmetric_with_unit_diagonal() {
+        let x = array![[0.1, 0.5], [1.2, -0.4], [2.0, 0.3], [0.15, 0.45]];
+        let gram = compute_kernel_matrix(x.view());
+
+        assert_eq!(gram.dim(), (4, 4));
+        for i in 0..4 {
+            assert!((gram[[i, i]] - 1.0).abs() < 1e-12);
+            for j in 0..4 {
+                assert_eq!(gram[[i, j]], gram[[j, i]]);
+                let pair = compute_kernel_value(x.row(i), x.row(j));
+                assert!((gram[[i, j]] - pair).abs() < 1e-12);
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ mod integrations;
 
 use num_complex::Complex;
 use qsim::simulator::Simulator;
-use qsim::{Gate, QuantumSimulator};
+use qsim::{Gate, QuantumSimulator, StateVector};
 use std::str::FromStr;
 
 /// Parses a circuit string into a vector of Gate objects.
@@ -63,55 +63,58 @@ fn parse_circuit(circuit_str: &str) -> Result<Vec<qsim::Gate>, String> {
         };
 
         // --- Create Gate Struct(s) ---
+        let expect_qubits = |count: usize| {
+            if qubit_indices.len() == count {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{} gate requires {} qubit(s), found {}: '{}'",
+                    name.to_uppercase(),
+                    count,
+                    qubit_indices.len(),
+                    clean_line
+                ))
+            }
+        };
         match name.as_str() {
             "cx" => {
-                if qubit_indices.len() != 2 {
-                    return Err(format!(
-                        "CX gate requires 2 qubits, found {}: '{}'",
-                        qubit_indices.len(),
-                        clean_line
-                    ));
-                }
+                expect_qubits(2)?;
                 gates.push(Gate::CX {
-                    control: 0,
-                    target: 1,
+                    control: qubit_indices[0],
+                    target: qubit_indices[1],
                 });
             }
-            "rz" => {
-                if qubit_indices.len() != 1 {
-                    return Err(format!(
-                        "Rz gate requires 1 qubit, found {}: '{}'",
-                        qubit_indices.len(),
-                        clean_line
-                    ));
-                }
-                if let Some(param) = parameter {
-                    gates.push(Gate::RZ {
-                        qubit: qubit_indices[0],
-                        theta: param,
-                    });
-                } else {
-                    return Err(format!(
-                        "Rz gate requires a parameter, found none in '{}'",
-                        clean_line
-                    ));
-                }
+            "h" | "x" | "y" | "z" => {
+                expect_qubits(1)?;
+                let qubit = qubit_indices[0];
+                gates.push(match name.as_str() {
+                    "h" => Gate::H { qubit },
+                    "x" => Gate::X { qubit },
+                    "y" => Gate::Y { qubit },
+                    _ => Gate::Z { qubit },
+                });
             }
-            "h" => {
-                if qubit_indices.len() != 1 {
-                    return Err(format!(
-                        "Hadamard gate requires 1 qubit, found {}: '{}'",
-                        qubit_indices.len(),
+            "rx" | "ry" | "rz" => {
+                expect_qubits(1)?;
+                let qubit = qubit_indices[0];
+                let theta = parameter.ok_or_else(|| {
+                    format!(
+                        "{} gate requires a parameter, found none in '{}'",
+                        name.to_uppercase(),
                         clean_line
-                    ));
-                }
-                gates.push(Gate::H {
-                    qubit: qubit_indices[0],
+                    )
+                })?;
+                gates.push(match name.as_str() {
+                    "rx" => Gate::RX { qubit, theta },
+                    "ry" => Gate::RY { qubit, theta },
+                    _ => Gate::RZ { qubit, theta },
                 });
             }
             _ => {
-                // For single-qubit gates like h, rz, etc.
-                panic!("Invalid gate specified: '{}'", name);
+                return Err(format!(
+                    "Unsupported gate '{}' in line: '{}'",
+                    name, clean_line
+                ));
             }
         }
     }
@@ -129,10 +132,11 @@ fn parse_circuit(circuit_str: &str) -> Result<Vec<qsim::Gate>, String> {
 /// # Arguments
 /// * `point_a` - A slice of f64 representing the first classical data point.
 /// * `point_b` - A slice of f64 representing the second classical data point.
+/// * `reps` - The number of feature-map layers, see `create_encoding_circuit`.
 ///
 /// # Returns
 /// A single f64 value between 0.0 and 1.0 representing the similarity.
-pub fn compute_kernel_value(point_a: &[f64], point_b: &[f64]) -> f64 {
+pub fn compute_kernel_value(point_a: &[f64], point_b: &[f64], reps: usize) -> f64 {
     // For this example, we assume the number of qubits is determined by the data dimension.
     // A more robust implementation would handle mismatched dimensions.
     assert_eq!(
@@ -147,7 +151,7 @@ pub fn compute_kernel_value(point_a: &[f64], point_b: &[f64]) -> f64 {
     let mut simulator = QuantumSimulator::new(num_qubits);
 
     // --- Step 1: Simulate the circuit for point_a ---
-    let circuit_str_a = create_encoding_circuit(point_a);
+    let circuit_str_a = create_encoding_circuit(point_a, reps);
     // Parse the string into executable gates. Handle potential errors.
     let circuit_a = parse_circuit(&circuit_str_a).expect("Failed to parse circuit A");
 
@@ -158,7 +162,7 @@ pub fn compute_kernel_value(point_a: &[f64], point_b: &[f64]) -> f64 {
     let statevector_a = simulator.get_statevector().clone();
 
     // --- Step 2: Simulate the circuit for point_b ---
-    let circuit_str_b = create_encoding_circuit(point_b);
+    let circuit_str_b = create_encoding_circuit(point_b, reps);
     let circuit_b = parse_circuit(&circuit_str_b).expect("Failed to parse circuit B");
 
     simulator.reset(); // Reset for the second simulation
@@ -179,55 +183,103 @@ pub fn compute_kernel_value(point_a: &[f64], point_b: &[f64]) -> f64 {
     inner_product.norm_sqr()
 }
 
+/// Encodes `point` with the ZZ feature map and returns the resulting statevector.
+fn prepare_statevector(point: &[f64], reps: usize) -> StateVector {
+    let mut simulator = QuantumSimulator::new(point.len());
+    let circuit =
+        parse_circuit(&create_encoding_circuit(point, reps)).expect("Failed to parse circuit");
+    for gate in &circuit {
+        simulator.apply_gate(gate);
+    }
+    simulator.get_statevector().clone()
+}
+
+/// A quantum kernel over a fixed set of training points.
+///
+/// Each point's feature-map statevector is simulated once up front, so evaluating the
+/// kernel for a pair is a single inner product instead of two circuit simulations.
+pub struct QuantumKernel {
+    states: Vec<StateVector>,
+}
+
+impl QuantumKernel {
+    /// Precomputes the statevector for every point, encoded with `reps` feature-map layers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the points do not all have the same dimension.
+    pub fn new(points: &[Vec<f64>], reps: usize) -> Self {
+        if let Some(first) = points.first() {
+            assert!(
+                points.iter().all(|p| p.len() == first.len()),
+                "Data points must have the same dimension."
+            );
+        }
+        QuantumKernel {
+            states: points
+                .iter()
+                .map(|p| prepare_statevector(p, reps))
+                .collect(),
+        }
+    }
+
+    /// The number of cached points.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Whether the kernel was built from no points.
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// The fidelity |<state_i|state_j>|^2 between the cached states of points `i` and `j`.
+    pub fn kernel(&self, i: usize, j: usize) -> f64 {
+        self.states[i].fidelity(&self.states[j])
+    }
+
+    /// The full Gram matrix, evaluating each symmetric pair once.
+    pub fn gram_matrix(&self) -> Vec<Vec<f64>> {
+        let n = self.len();
+        let mut gram = vec![vec![1.0; n]; n];
+        for (i, j) in (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))) {
+            let value = self.kernel(i, j);
+            gram[i][j] = value;
+            gram[j][i] = value;
+        }
+        gram
+    }
+}
+
 /// This module contains the functionality to create a quantum circuit
 /// for encoding classical data using a ZZ Feature Map.
 
 /// Creates an OpenQASM 2.0 string representing a quantum circuit that
-/// encodes a 2D classical data point using a ZZ Feature Map.
+/// encodes a classical data point using a ZZ Feature Map.
 ///
 /// The ZZ Feature Map is a common technique in Quantum Machine Learning
 /// to encode classical data into the quantum state of a circuit.
-/// This implementation uses a 2-qubit circuit.
+/// This implementation uses one qubit per feature.
 ///
 /// The encoding process is as follows:
-/// 1. Start with a 2-qubit register.
+/// 1. Start with a `d`-qubit register for a `d`-dimensional point.
 /// 2. Apply a Hadamard gate to each qubit to create a superposition.
 /// 3. Apply parameterized single-qubit rotations (Rz) based on the input data.
-/// 4. Apply an entangling gate (CNOT) between the qubits.
-/// 5. Apply another parameterized single-qubit rotation (Rz) that is a
-///    function of the product of the two data features. This captures the
-///    "ZZ" interaction.
-/// 6. Apply another layer of Hadamard gates.
+/// 4. For each adjacent pair of qubits, apply a CNOT, an Rz on the second qubit
+///    that is a function of the product of the two data features, and another CNOT.
+///    This captures the "ZZ" interaction.
+/// 5. Repeat steps 2-4 `reps` times in total, like Qiskit's `ZZFeatureMap(reps=...)`.
 ///
 /// # Arguments
 ///
-/// * `data_point` - A slice of f64 with two elements, e.g., `&[x, y]`.
+/// * `data_point` - A slice of f64 with one element per feature, e.g., `&[x, y]`.
+/// * `reps` - The number of times the encoding layer is applied.
 ///
 /// # Returns
 ///
 /// A `String` containing the OpenQASM 2.0 representation of the circuit.
-///
-/// # Panics
-///
-/// This function will panic if the input `data_point` does not contain exactly 2 elements.
-fn create_encoding_circuit(data_point: &[f64]) -> String {
-    // Ensure the data point is 2-dimensional for our 2-qubit feature map.
-    if data_point.len() != 2 {
-        panic!("This function requires a 2D data point, e.g., [x, y].");
-    }
-
-    let x = data_point[0];
-    let y = data_point[1];
-
-    // The angles for the Rz gates are derived from the input data.
-    // A common practice is to scale the data, here we use it directly for simplicity.
-    let angle_x = x * std::f64::consts::PI;
-    let angle_y = y * std::f64::consts::PI;
-
-    // The angle for the ZZ interaction term.
-    // This is often a non-linear combination of the inputs.
-    // A simple choice is (pi - x) * (pi - y)
-    let angle_zz = (std::f64::consts::PI - x) * (std::f64::consts::PI - y);
+fn create_encoding_circuit(data_point: &[f64], reps: usize) -> String {
+    let num_qubits = data_point.len();
 
     // Using a mutable string to build the OpenQASM code.
     let mut qasm_string = String::new();
@@ -237,44 +289,49 @@ fn create_encoding_circuit(data_point: &[f64]) -> String {
     qasm_string.push_str("include \"qelib1.inc\";\n\n");
 
     // --- Qubit and Classical Register Declaration ---
-    qasm_string.push_str("// Declare a 2-qubit register for the feature map\n");
-    qasm_string.push_str("qreg q[2];\n");
-    qasm_string.push_str(
-        "// Declare a 2-bit classical register for measurement (optional, for simulation)\n",
-    );
-    qasm_string.push_str("creg c[2];\n\n");
+    qasm_string.push_str("// Declare one qubit per feature for the feature map\n");
+    qasm_string.push_str(&format!("qreg q[{}];\n", num_qubits));
+    qasm_string
+        .push_str("// Declare a classical register for measurement (optional, for simulation)\n");
+    qasm_string.push_str(&format!("creg c[{}];\n\n", num_qubits));
 
     // --- Circuit Implementation ---
 
-    // 1. Apply Hadamard gates to all qubits to create superposition
-    qasm_string.push_str("// Step 1: Create superposition\n");
-    qasm_string.push_str("h q[0];\n");
-    qasm_string.push_str("h q[1];\n\n");
-
-    // 2. Apply first layer of parameterized rotations
-    qasm_string.push_str("// Step 2: Encode data features with Rz gates\n");
-    qasm_string.push_str(&format!("rz({}) q[0];\n", angle_x));
-    qasm_string.push_str(&format!("rz({}) q[1];\n", angle_y));
-    qasm_string.push_str("\n");
+    // Steps 1-3 form one layer, repeated `reps` times.
+    for rep in 0..reps {
+        qasm_string.push_str(&format!("// Layer {}\n", rep + 1));
 
-    // 3. Apply entangling gates (linear entanglement)
-    qasm_string.push_str("// Step 3: Entangle qubits with CNOT gates\n");
-    qasm_string.push_str("cx q[0], q[1];\n\n");
-
-    // 4. Apply the ZZ interaction term
-    qasm_string.push_str("// Step 4: Apply the ZZ interaction term\n");
-    qasm_string.push_str(&format!("rz({}) q[1];\n\n", angle_zz));
+        // 1. Apply Hadamard gates to all qubits to create superposition
+        qasm_string.push_str("// Step 1: Create superposition\n");
+        for q in 0..num_qubits {
+            qasm_string.push_str(&format!("h q[{}];\n", q));
+        }
+        qasm_string.push('\n');
 
-    // 5. Un-entangle with another CNOT
-    qasm_string.push_str("// Step 5: Un-entangle\n");
-    qasm_string.push_str("cx q[0], q[1];\n\n");
+        // 2. Apply first layer of parameterized rotations.
+        // A common practice is to scale the data, here we use it directly for simplicity.
+        qasm_string.push_str("// Step 2: Encode data features with Rz gates\n");
+        for (q, x) in data_point.iter().enumerate() {
+            qasm_string.push_str(&format!("rz({}) q[{}];\n", x * std::f64::consts::PI, q));
+        }
+        qasm_string.push('\n');
+
+        // 3. Apply the ZZ interaction between adjacent qubits, sandwiched between CNOTs.
+        // The angle is a non-linear combination of the two features: (pi - x) * (pi - y).
+        qasm_string.push_str("// Step 3: Apply the ZZ interaction terms\n");
+        for q in 1..num_qubits {
+            let angle_zz =
+                (std::f64::consts::PI - data_point[q - 1]) * (std::f64::consts::PI - data_point[q]);
+            qasm_string.push_str(&format!("cx q[{}], q[{}];\n", q - 1, q));
+            qasm_string.push_str(&format!("rz({}) q[{}];\n", angle_zz, q));
+            qasm_string.push_str(&format!("cx q[{}], q[{}];\n\n", q - 1, q));
+        }
+    }
 
     // --- Optional: Add measurements to observe the final state ---
     qasm_string.push_str("// Optional: Measure qubits\n");
     qasm_string.push_str("measure q -> c;\n");
 
-    // strip the
-
     qasm_string
 }
 
@@ -284,7 +341,7 @@ fn main() {
     let data_point = [0.5, 0.8];
 
     // Generate the OpenQASM string for the data point.
-    let qasm_circuit = create_encoding_circuit(&data_point);
+    let qasm_circuit = create_encoding_circuit(&data_point, 1);
 
     // Print the generated circuit.
     println!("--- Generated OpenQASM 2.0 Circuit ---");
@@ -303,24 +360,118 @@ fn main() {
 
     // Calculate the kernel value (similarity) between point 1 and itself.
     // This should be 1.0, as a state is perfectly similar to itself.
-    let similarity_1_1 = compute_kernel_value(&data_point_1, &data_point_1);
+    let similarity_1_1 = compute_kernel_value(&data_point_1, &data_point_1, 1);
     println!("Similarity(Point 1, Point 1): {:.6}", similarity_1_1);
 
     // Calculate the similarity between two nearby points.
     // This should result in a high value, close to 1.0.
-    let similarity_1_2 = compute_kernel_value(&data_point_1, &data_point_2);
+    let similarity_1_2 = compute_kernel_value(&data_point_1, &data_point_2, 1);
     println!("Similarity(Point 1, Point 2): {:.6}", similarity_1_2);
 
     // Calculate the similarity between two distant points.
     // This should result in a lower value.
-    let similarity_1_3 = compute_kernel_value(&data_point_1, &data_point_3);
+    let similarity_1_3 = compute_kernel_value(&data_point_1, &data_point_3, 1);
     println!("Similarity(Point 1, Point 3): {:.6}", similarity_1_3);
+
+    // The same similarities from cached statevectors, as used for a training set.
+    let kernel = QuantumKernel::new(&[data_point_1, data_point_2, data_point_3], 1);
+    println!("--- Gram Matrix ---");
+    for row in kernel.gram_matrix() {
+        println!("{:.6?}", row);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_two_dimensional_encoding_gates() {
+        let (x, y) = (0.5, 0.8);
+        let gates = parse_circuit(&create_encoding_circuit(&[x, y], 1)).unwrap();
+        let pi = std::f64::consts::PI;
+        assert_eq!(
+            gates,
+            vec![
+                Gate::H { qubit: 0 },
+                Gate::H { qubit: 1 },
+                Gate::RZ {
+                    qubit: 0,
+                    theta: x * pi
+                },
+                Gate::RZ {
+                    qubit: 1,
+                    theta: y * pi
+                },
+                Gate::CX {
+                    control: 0,
+                    target: 1
+                },
+                Gate::RZ {
+                    qubit: 1,
+                    theta: (pi - x) * (pi - y)
+                },
+                Gate::CX {
+                    control: 0,
+                    target: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encoding_repetitions_stack_layers() {
+        let point = [0.3, -0.6, 1.1];
+        let one_layer = parse_circuit(&create_encoding_circuit(&point, 1)).unwrap();
+        let two_layers = parse_circuit(&create_encoding_circuit(&point, 2)).unwrap();
+
+        // 3 H, 3 Rz and 2 × (CX, Rz, CX) per layer.
+        assert_eq!(one_layer.len(), 12);
+        assert_eq!(two_layers.len(), 2 * one_layer.len());
+        assert_eq!(&two_layers[..12], &one_layer[..]);
+        assert_eq!(&two_layers[12..], &one_layer[..]);
+        assert!(compute_kernel_value(&point, &[0.2, -0.5, 1.0], 2) < 1.0);
+    }
+
+    #[test]
+    fn test_kernel_on_four_dimensional_points() {
+        let point = [0.1, 0.4, -0.3, 0.7];
+        assert!((compute_kernel_value(&point, &point, 1) - 1.0).abs() < 1e-10);
+
+        let near = [0.12, 0.38, -0.28, 0.71];
+        let far = [-0.8, 0.9, 0.6, -0.5];
+        let near_fidelity = compute_kernel_value(&point, &near, 1);
+        let far_fidelity = compute_kernel_value(&point, &far, 1);
+        assert!(near_fidelity > 0.9, "{}", near_fidelity);
+        assert!(
+            far_fidelity < near_fidelity,
+            "{} {}",
+            far_fidelity,
+            near_fidelity
+        );
+    }
+
+    #[test]
+    fn test_cached_kernel_matches_naive_kernel() {
+        let points = vec![
+            vec![0.5, 0.2],
+            vec![0.55, 0.25],
+            vec![-0.8, 0.9],
+            vec![0.0, 1.3],
+        ];
+        let kernel = QuantumKernel::new(&points, 2);
+        let gram = kernel.gram_matrix();
+
+        assert_eq!(kernel.len(), 4);
+        for i in 0..points.len() {
+            for j in 0..points.len() {
+                let naive = compute_kernel_value(&points[i], &points[j], 2);
+                assert!((kernel.kernel(i, j) - naive).abs() < 1e-10);
+                assert!((gram[i][j] - naive).abs() < 1e-10);
+            }
+        }
+    }
+
     #[test]
     fn test_parse_simple_gate() {
         let qasm = "h q[0];";
@@ -357,6 +508,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_cx_uses_parsed_qubits() {
+        let gates = parse_circuit("cx q[1], q[0];").unwrap();
+        assert_eq!(
+            gates,
+            vec![Gate::CX {
+                control: 1,
+                target: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_pauli_and_rotation_gates() {
+        let qasm = "x q[0];\ny q[1];\nz q[0];\nrx(0.5) q[1];\nry(-1.25) q[0];";
+        let gates = parse_circuit(qasm).unwrap();
+        assert_eq!(
+            gates,
+            vec![
+                Gate::X { qubit: 0 },
+                Gate::Y { qubit: 1 },
+                Gate::Z { qubit: 0 },
+                Gate::RX {
+                    qubit: 1,
+                    theta: 0.5
+                },
+                Gate::RY {
+                    qubit: 0,
+                    theta: -1.25
+                },
+            ]
+        );
+        assert!(parse_circuit("ry q[0];").is_err());
+    }
+
+    #[test]
+    fn test_unknown_gate_is_an_error() {
+        let err = parse_circuit("swap q[0], q[1];").unwrap_err();
+        assert!(err.contains("swap"), "{}", err);
+    }
+
     #[test]
     fn test_empty_and_comment_only_input() {
         assert!(parse_circuit("").unwrap().is_empty());
@@ -218,13 +218,18 @@ mod tests {
         let mut workflow = Workflow::new();
         workflow.run(ast).expect("Workflow execution failed");
 
-        let updated_angle_content = fs::read_to_string(angle_file).unwrap();
+        let updated_angle: f64 = fs::read_to_string(angle_file).unwrap().parse().unwrap();
+        let energy: f64 = fs::read_to_string(energy_file).unwrap().parse().unwrap();
 
-        // 0.5 - (0.1 * 1.0) = 0.5 - 0.05 = 0.4
-        assert_eq!(updated_angle_content, "0.4");
-
-        let energy_content = fs::read_to_string(energy_file).unwrap();
-        assert_eq!(energy_content, "1");
+        // H then RY(0.5) on |0> gives <Z> = -sin(0.5), estimated here from 1024 shots.
+        assert!(
+            (energy + 0.5f64.sin()).abs() < 0.15,
+            "sampled energy {} is far from {}",
+            energy,
+            -0.5f64.sin()
+        );
+        // new_angle = current_angle - learning_rate * energy
+        assert!((updated_angle - (0.5 - 0.1 * energy)).abs() < 1e-12);
 
         fs::remove_file(angle_file).unwrap();
         fs::remove_file(energy_file).unwrap();
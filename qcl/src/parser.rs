@@ -11,8 +11,10 @@ pub enum Value {
     List(Vec<(Value, SimpleSpan)>),
 }
 
+/// A gate call as written in QCL source: a name plus unevaluated arguments.
+/// The workflow lowers these into concrete `qsim::Gate`s.
 #[derive(Debug, Clone, PartialEq)]
-pub struct Gate {
+pub struct SymbolicGate {
     pub name: String,
     pub args: Vec<Value>,
 }
@@ -34,7 +36,7 @@ pub enum Declaration {
     DefCircuit {
         name: String,
         qubits: u64,
-        body: Vec<Gate>,
+        body: Vec<SymbolicGate>,
     },
     DefObs {
         name: String,
@@ -43,7 +45,7 @@ pub enum Declaration {
     DefMacro {
         name: String,
         params: Vec<String>,
-        body: Vec<Gate>,
+        body: Vec<SymbolicGate>,
     },
     Run(HashMap<String, Value>),
     Loop {
@@ -112,7 +114,7 @@ pub fn validate_ast(raw_s_exprs: &[(Value, SimpleSpan)]) -> Result<Vec<Declarati
         .collect()
 }
 
-fn try_gate_from_value(gate_val: &(Value, SimpleSpan)) -> Result<Gate, String> {
+fn try_gate_from_value(gate_val: &(Value, SimpleSpan)) -> Result<SymbolicGate, String> {
     if let Value::List(gate_items) = &gate_val.0 {
         if gate_items.is_empty() {
             return Err("Gate definition cannot be an empty list".to_string());
@@ -123,7 +125,7 @@ fn try_gate_from_value(gate_val: &(Value, SimpleSpan)) -> Result<Gate, String> {
             _ => return Err("Expected gate name as a string or symbol".to_string()),
         };
         let args = gate_items[1..].iter().map(|(arg, _)| arg.clone()).collect();
-        Ok(Gate {
+        Ok(SymbolicGate {
             name: gate_name,
             args,
         })
@@ -1,7 +1,6 @@
-use crate::parser::{Declaration, Gate as SymbolicGate, Value};
+use crate::parser::{Declaration, SymbolicGate, Value};
 use chumsky::span::SimpleSpan;
 use qsim::circuit::Circuit;
-use qsim::simulator::Simulator;
 use qsim::{Gate as ConcreteGate, QuantumSimulator};
 use std::collections::HashMap;
 use std::fs;
@@ -107,8 +106,13 @@ impl Workflow {
                 }
                 Declaration::Run(run_args) => {
                     println!("[Workflow] --- Triggering Run (fire and forget) ---");
-                    // For a top-level run, we ignore the result.
-                    self.run_simulation(run_args)?;
+                    // For a top-level run, we ignore the result. Without an observable
+                    // to measure, report the sampled bitstring histogram instead.
+                    if run_args.contains_key("measure") {
+                        self.run_simulation(run_args)?;
+                    } else {
+                        self.run_histogram(run_args)?;
+                    }
                 }
                 Declaration::Loop { times, body } => {
                     println!("[Workflow] >>> Entering Loop ({} iterations)", times);
@@ -227,6 +231,57 @@ impl Workflow {
 
     /// This function now returns a f64 result, representing the expectation value.
     fn run_simulation(&mut self, args: &HashMap<String, Value>) -> Result<f64, String> {
+        let obs_name = match args.get("measure") {
+            Some(Value::Symbol(s)) => s,
+            None => return Err("A 'run' expression that returns a value must have a (measure: 'obs_name') argument.".to_string()),
+            _ => return Err("Expected a symbol for the 'measure' argument.".to_string()),
+        };
+        let operator = self
+            .observables
+            .get(obs_name)
+            .map(|obs_def| obs_def.operator.clone())
+            .ok_or_else(|| format!("Observable '{}' not found.", obs_name))?;
+
+        let shots = self.prepare_run(args)?;
+
+        println!("[Workflow] Measuring expectation of '{}'.", operator);
+
+        let expectation_value = self
+            .simulator
+            .measure_hamiltonian_expectation(&operator, shots as usize)
+            .map_err(|e| e.to_string())?;
+
+        println!(
+            "[Workflow] Simulation complete. Measured <{}> = {}",
+            obs_name, expectation_value
+        );
+
+        Ok(expectation_value)
+    }
+
+    /// Runs the circuit and samples it in the computational basis, printing a histogram of
+    /// the outcomes. This is what a top-level `run` without a `measure:` argument does.
+    fn run_histogram(
+        &mut self,
+        args: &HashMap<String, Value>,
+    ) -> Result<HashMap<String, u32>, String> {
+        let shots = self.prepare_run(args)?;
+        let counts = self.simulator.sample_counts(shots as u32);
+
+        println!("[Workflow] Measurement histogram ({} shots):", shots);
+        let mut outcomes: Vec<_> = counts.iter().collect();
+        outcomes.sort();
+        for (bitstring, count) in outcomes {
+            let bar_len = (*count as u64 * 40).div_ceil(shots.max(1)) as usize;
+            println!("  {} | {:<40} {}", bitstring, "#".repeat(bar_len), count);
+        }
+
+        Ok(counts)
+    }
+
+    /// Builds the circuit named by a run's `circuit:`/`with:` arguments, resets the simulator
+    /// to its width and applies it. Returns the requested number of shots.
+    fn prepare_run(&mut self, args: &HashMap<String, Value>) -> Result<u64, String> {
         let circuit_name = match args.get("circuit") {
             Some(Value::Symbol(s)) => s,
             _ => {
@@ -258,50 +313,25 @@ impl Workflow {
             _ => return Err("Expected 'shots:' argument to be a number.".to_string()),
         };
 
-        let obs_name = match args.get("measure") {
-            Some(Value::Symbol(s)) => s,
-            None => return Err("A 'run' expression that returns a value must have a (measure: 'obs_name') argument.".to_string()),
-            _ => return Err("Expected a symbol for the 'measure' argument.".to_string()),
-        };
-        let obs_def = self
-            .observables
-            .get(obs_name)
-            .ok_or_else(|| format!("Observable '{}' not found.", obs_name))?;
-
         println!(
             "[Workflow] Building concrete circuit for '{}' with {} shots.",
             circuit_def.name, shots
         );
 
         let concrete_circuit = self.build_concrete_circuit(circuit_def, &run_params)?;
+        let num_qubits = circuit_def.qubits as usize;
 
         self.run_counter += 1;
 
-        println!(
-            "[Workflow] Resetting simulator for {} qubits.",
-            circuit_def.qubits
-        );
-        self.simulator.reset();
+        println!("[Workflow] Resetting simulator for {} qubits.", num_qubits);
+        self.simulator = QuantumSimulator::new(num_qubits);
 
         println!("[Workflow] Running circuit on simulator.");
-        self.simulator.apply_circuit(&concrete_circuit);
-
-        println!(
-            "[Workflow] Measuring expectation of '{}'.",
-            obs_def.operator
-        );
-
-        let expectation_value = self
-            .simulator
-            .measure_expectation(&obs_def.operator, shots as usize)
+        self.simulator
+            .apply_circuit(&concrete_circuit)
             .map_err(|e| e.to_string())?;
 
-        println!(
-            "[Workflow] Simulation complete. Measured <{}> = {}",
-            obs_name, expectation_value
-        );
-
-        Ok(expectation_value)
+        Ok(shots)
     }
 
     fn parse_run_params(
@@ -533,6 +563,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bell_state_from_qsim_shorthand_gates() {
+        let mut circuit = Circuit::with_qubits(2);
+        circuit.add_gate(ConcreteGate::h(0));
+        circuit.add_gate(ConcreteGate::cx(0, 1));
+
+        let mut sim = QuantumSimulator::new(2);
+        sim.apply_circuit(&circuit).unwrap();
+
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        let amplitudes = &sim.state.amplitudes;
+        assert!((amplitudes[0].re - s).abs() < 1e-9);
+        assert!((amplitudes[3].re - s).abs() < 1e-9);
+        assert!(amplitudes[1].norm() < 1e-9);
+        assert!(amplitudes[2].norm() < 1e-9);
+    }
+
     #[test]
     fn test_undefined_parameter_error() {
         let workflow = Workflow::new();
@@ -846,4 +893,36 @@ mod tests {
 
         fs::remove_file(test_file).unwrap();
     }
+
+    #[test]
+    fn test_run_without_observable_samples_histogram() {
+        let declarations = vec![Declaration::DefCircuit {
+            name: "bell".to_string(),
+            qubits: 2,
+            body: vec![
+                SymbolicGate {
+                    name: "H".to_string(),
+                    args: vec![Value::Num(0.0)],
+                },
+                SymbolicGate {
+                    name: "CX".to_string(),
+                    args: vec![Value::Num(0.0), Value::Num(1.0)],
+                },
+            ],
+        }];
+
+        let mut workflow = Workflow::new();
+        workflow.run(declarations).unwrap();
+
+        let run_args: HashMap<String, Value> = [
+            ("circuit".to_string(), Value::Symbol("bell".to_string())),
+            ("shots".to_string(), Value::Num(500.0)),
+        ]
+        .into_iter()
+        .collect();
+        let counts = workflow.run_histogram(&run_args).unwrap();
+
+        assert_eq!(counts.values().sum::<u32>(), 500);
+        assert!(counts.keys().all(|k| k == "00" || k == "11"));
+    }
 }
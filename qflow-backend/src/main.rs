@@ -1,6 +1,9 @@
+use axum::body::Body;
 use axum::extract::Multipart;
 use axum::extract::Request;
-use axum::routing::post;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, post};
 use axum::{
     Form, Json, Router,
     extract::{Path, Query, State},
@@ -8,17 +11,26 @@ use axum::{
     routing::get,
 };
 
-use k8s_openapi::api::{batch::v1::Job, core::v1::Pod};
+use futures_util::{AsyncBufReadExt, TryStreamExt};
+use k8s_openapi::api::{
+    batch::v1::Job,
+    core::v1::{ConfigMap, Pod},
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
 use kube::{
-    Client,
-    api::{Api, ListParams, LogParams, PostParams},
+    Client, Resource,
+    api::{Api, DeleteParams, ListParams, LogParams, Patch, PatchParams, PostParams},
 };
-use qflow_types::{QFlowTaskSpec, QuantumWorkflow, QuantumWorkflowSpec};
+use qflow_types::{QFlowTask, QFlowTaskSpec, QcbmResult, QuantumWorkflow, QuantumWorkflowSpec};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::io::Write;
-use std::{collections::HashMap, sync::Arc};
-use tempfile::NamedTempFile;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
@@ -92,6 +104,13 @@ pub struct FetchWorkflowParams {
     pub namespace: String,
 }
 
+#[derive(Deserialize, Default)]
+pub struct SubmitParams {
+    /// Patch the spec of an existing workflow with the same name instead of failing.
+    #[serde(default)]
+    pub upsert: bool,
+}
+
 #[tokio::main]
 async fn main() {
     let client = Client::try_default()
@@ -109,7 +128,20 @@ async fn main() {
             "/api/workflows/{namespace}/{name}/tasks/{task_name}/results",
             get(fetch_task_results),
         )
-        .route("/api/workflows/{namespace}/new", post(submit_workflow))
+        .route("/api/workflows/{namespace}/{name}/tasks", post(submit_task))
+        .route(
+            "/api/workflows/{namespace}/{name}/status",
+            get(fetch_workflow_status),
+        )
+        .route(
+            "/api/workflows/{namespace}/{name}/tasks/{task_name}/logs",
+            get(fetch_task_logs),
+        )
+        .route(
+            "/api/workflows/{namespace}/{name}/new",
+            post(submit_workflow),
+        )
+        .route("/api/workflows/{namespace}/{name}", delete(delete_workflow))
         .route("/api/ml/svm", post(run_ml_svm))
         .layer(
             TraceLayer::new_for_http()
@@ -155,25 +187,7 @@ async fn fetch_workflow(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    let mut job_status_map: HashMap<String, String> = HashMap::new();
-    for job in all_jobs.items {
-        if let Some(owner_refs) = job.metadata.owner_references.as_ref() {
-            if owner_refs.iter().any(|owner| owner.name == workflow_name) {
-                if let Some(labels) = job.metadata.labels {
-                    if let Some(task_name) = labels.get("qflow.io/task-name") {
-                        let status_str = match job.status {
-                            Some(s) if s.succeeded.map_or(false, |c| c > 0) => "Succeeded",
-                            Some(s) if s.failed.map_or(false, |c| c > 0) => "Failed",
-                            Some(s) if s.active.map_or(false, |c| c > 0) => "Running",
-                            _ => "Pending",
-                        }
-                        .to_string();
-                        job_status_map.insert(task_name.clone(), status_str);
-                    }
-                }
-            }
-        }
-    }
+    let job_status_map = job_task_statuses(&workflow_name, all_jobs.items);
 
     let mut tasks = Vec::new();
     let mut task_status_map = HashMap::new();
@@ -182,7 +196,7 @@ async fn fetch_workflow(
         let task_name = task_from_cr.name.clone();
 
         let (quantum, classical, qcbm) = match task_from_cr.spec {
-            QFlowTaskSpec::Classical { image } => {
+            QFlowTaskSpec::Classical { image, .. } => {
                 (None, Some(serde_json::json!({ "image": image })), None)
             }
             QFlowTaskSpec::Quantum {
@@ -236,30 +250,90 @@ async fn fetch_workflow(
 
 async fn fetch_task_results(
     State(state): State<Arc<AppState>>,
-    Path((namespace, _workflow_name, task_name)): Path<(String, String, String)>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+    Path((namespace, workflow_name, task_name)): Path<(String, String, String)>,
+) -> Result<Json<TaskResult>, StatusCode> {
     let pods: Api<Pod> = Api::namespaced(state.client.clone(), &namespace);
     let jobs: Api<Job> = Api::namespaced(state.client.clone(), &namespace);
+    let wf_api: Api<QuantumWorkflow> = Api::namespaced(state.client.clone(), &namespace);
 
-    let job_list = jobs.list(&ListParams::default()).await.map_err(|e| {
-        eprintln!("Error listing jobs: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    let is_qcbm_task = wf_api.get(&workflow_name).await.is_ok_and(|wf| {
+        wf.spec
+            .tasks
+            .iter()
+            .any(|t| t.name == task_name && matches!(t.spec, QFlowTaskSpec::Qcbm(_)))
+    });
+
+    let task_pods = list_task_pods(&jobs, &pods, &workflow_name, &task_name).await?;
 
-    let job_name = job_list
-        .items
+    if let Some(pod) = task_pods
         .into_iter()
-        .find(|job| {
-            job.metadata.labels.as_ref().map_or(false, |labels| {
-                labels.get("qflow.io/task-name") == Some(&task_name)
-            })
-        })
-        .and_then(|job| job.metadata.name);
+        .find(|p| pod_phase(p) == Some(POD_SUCCEEDED))
+    {
+        if let Some(pod_name) = &pod.metadata.name {
+            let logs = pods
+                .logs(pod_name, &LogParams::default())
+                .await
+                .map_err(|e| {
+                    eprintln!("Error fetching logs for pod '{}': {}", pod_name, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+
+            let output = if is_qcbm_task {
+                let result = parse_qcbm_result(&logs).ok_or_else(|| {
+                    eprintln!("Task '{}' did not emit a valid QCBM result", task_name);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+                serde_json::to_value(result)
+                    .map(TaskOutput::Json)
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            } else {
+                TaskOutput::from_logs(logs)
+            };
 
-    let job_name = match job_name {
+            Ok(Json(TaskResult::from_pod(&task_name, &pod, output)))
+        } else {
+            Err(StatusCode::NOT_FOUND)
+        }
+    } else {
+        eprintln!("No succeeded pod found for task '{}'", task_name);
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+const POD_SUCCEEDED: &str = "Succeeded";
+const POD_RUNNING: &str = "Running";
+
+fn pod_phase(pod: &Pod) -> Option<&str> {
+    pod.status.as_ref()?.phase.as_deref()
+}
+
+/// Lists the pods of the Job that runs `task_name` in `workflow_name`. Task names are only
+/// unique within a workflow, so the Job is selected by both labels.
+async fn list_task_pods(
+    jobs: &Api<Job>,
+    pods: &Api<Pod>,
+    workflow_name: &str,
+    task_name: &str,
+) -> Result<Vec<Pod>, StatusCode> {
+    let job_label = format!(
+        "qflow.io/workflow-name={},qflow.io/task-name={}",
+        workflow_name, task_name
+    );
+    let job_list = jobs
+        .list(&ListParams::default().labels(&job_label))
+        .await
+        .map_err(|e| {
+            eprintln!("Error listing jobs: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let job_name = match job_list.items.into_iter().find_map(|job| job.metadata.name) {
         Some(name) => name,
         None => {
-            eprintln!("No job found for task '{}'", task_name);
+            eprintln!(
+                "No job found for task '{}' of workflow '{}'",
+                task_name, workflow_name
+            );
             return Err(StatusCode::NOT_FOUND);
         }
     };
@@ -271,41 +345,234 @@ async fn fetch_task_results(
         eprintln!("Error listing pods: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
+    Ok(pod_list.items)
+}
 
-    if let Some(pod) = pod_list.items.into_iter().find(|p| {
-        p.status
-            .as_ref()
-            .map_or(false, |s| s.phase == Some("Succeeded".to_string()))
-    }) {
-        if let Some(pod_name) = &pod.metadata.name {
-            let logs = pods
-                .logs(pod_name, &LogParams::default())
+/// Picks the pod whose logs to serve for a task: a succeeded pod if the Job has one,
+/// otherwise one that is still running.
+fn select_log_pod(pods: &[Pod]) -> Option<&Pod> {
+    pods.iter()
+        .find(|p| pod_phase(p) == Some(POD_SUCCEEDED))
+        .or_else(|| pods.iter().find(|p| pod_phase(p) == Some(POD_RUNNING)))
+}
+
+#[derive(Deserialize, Default)]
+pub struct TaskLogsParams {
+    /// Keep the response open and stream new log lines as the pod writes them.
+    #[serde(default)]
+    pub follow: bool,
+}
+
+/// Returns the raw logs of a task's pod as plain text, including while it is still
+/// running. With `follow=true` the body is streamed line by line until the pod exits.
+async fn fetch_task_logs(
+    State(state): State<Arc<AppState>>,
+    Path((namespace, workflow_name, task_name)): Path<(String, String, String)>,
+    Query(params): Query<TaskLogsParams>,
+) -> Result<Response, StatusCode> {
+    let pods: Api<Pod> = Api::namespaced(state.client.clone(), &namespace);
+    let jobs: Api<Job> = Api::namespaced(state.client.clone(), &namespace);
+
+    let task_pods = list_task_pods(&jobs, &pods, &workflow_name, &task_name).await?;
+    let pod_name = select_log_pod(&task_pods)
+        .and_then(|pod| pod.metadata.name.clone())
+        .ok_or_else(|| {
+            eprintln!("No running or succeeded pod found for task '{}'", task_name);
+            StatusCode::NOT_FOUND
+        })?;
+    let log_error = |e: kube::Error| {
+        eprintln!("Error fetching logs for pod '{}': {}", pod_name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let body = if params.follow {
+        let lp = LogParams {
+            follow: true,
+            ..LogParams::default()
+        };
+        let lines = pods
+            .log_stream(&pod_name, &lp)
+            .await
+            .map_err(log_error)?
+            .lines();
+        Body::from_stream(lines.map_ok(|line| line + "\n"))
+    } else {
+        Body::from(
+            pods.logs(&pod_name, &LogParams::default())
                 .await
-                .map_err(|e| {
-                    eprintln!("Error fetching logs for pod '{}': {}", pod_name, e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
+                .map_err(log_error)?,
+        )
+    };
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response())
+}
 
-            match serde_json::from_str::<serde_json::Value>(&logs) {
-                Ok(json_value) => Ok(Json(json_value)),
-                Err(_) => Ok(Json(serde_json::json!({ "raw_logs": logs }))),
+/// Maps each task of `workflow_name` to the status of its Job.
+fn job_task_statuses(workflow_name: &str, jobs: Vec<Job>) -> HashMap<String, String> {
+    let mut job_status_map: HashMap<String, String> = HashMap::new();
+    for job in jobs {
+        if let Some(owner_refs) = job.metadata.owner_references.as_ref() {
+            if owner_refs.iter().any(|owner| owner.name == workflow_name) {
+                if let Some(labels) = job.metadata.labels {
+                    if let Some(task_name) = labels.get("qflow.io/task-name") {
+                        let status_str = match job.status {
+                            Some(s) if s.succeeded.map_or(false, |c| c > 0) => "Succeeded",
+                            Some(s) if s.failed.map_or(false, |c| c > 0) => "Failed",
+                            Some(s) if s.active.map_or(false, |c| c > 0) => "Running",
+                            _ => "Pending",
+                        }
+                        .to_string();
+                        job_status_map.insert(task_name.clone(), status_str);
+                    }
+                }
             }
-        } else {
-            Err(StatusCode::NOT_FOUND)
         }
-    } else {
-        eprintln!("No succeeded pod found with label '{}'", pod_label);
-        Err(StatusCode::NOT_FOUND)
     }
+    job_status_map
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct WorkflowStatusSummary {
+    phase: String,
+    task_statuses: BTreeMap<String, String>,
+}
+
+impl WorkflowStatusSummary {
+    /// Takes each task's status from its Job when one exists, falling back to the status
+    /// recorded on the CR and then to `Pending`. The phase is the one the operator recorded
+    /// on the CR; before it has recorded one, a workflow without tasks is pending and the
+    /// operator's rule applies otherwise: a failed task fails the workflow, it succeeds once
+    /// every task has, and it is running otherwise.
+    fn new(workflow: &QuantumWorkflow, job_statuses: &HashMap<String, String>) -> Self {
+        let recorded = workflow
+            .status
+            .as_ref()
+            .and_then(|s| s.task_statuses.as_ref());
+        let recorded_phase = workflow.status.as_ref().and_then(|s| s.phase.clone());
+        let task_statuses: BTreeMap<String, String> = workflow
+            .spec
+            .tasks
+            .iter()
+            .map(|task| {
+                let status = job_statuses
+                    .get(&task.name)
+                    .or_else(|| recorded.and_then(|r| r.get(&task.name)))
+                    .cloned()
+                    .unwrap_or_else(|| "Pending".to_string());
+                (task.name.clone(), status)
+            })
+            .collect();
+
+        let phase = recorded_phase.unwrap_or_else(|| {
+            if task_statuses.is_empty() {
+                "Pending"
+            } else if task_statuses.values().any(|s| s == "Failed") {
+                "Failed"
+            } else if task_statuses.values().all(|s| s == "Succeeded") {
+                "Succeeded"
+            } else {
+                "Running"
+            }
+            .to_string()
+        });
+        WorkflowStatusSummary {
+            phase,
+            task_statuses,
+        }
+    }
+}
+
+/// A cheap status poll: the workflow phase and per-task statuses, without the task specs.
+async fn fetch_workflow_status(
+    State(state): State<Arc<AppState>>,
+    Path((namespace, workflow_name)): Path<(String, String)>,
+) -> Result<Json<WorkflowStatusSummary>, StatusCode> {
+    let wf_api: Api<QuantumWorkflow> = Api::namespaced(state.client.clone(), &namespace);
+    let job_api: Api<Job> = Api::namespaced(state.client.clone(), &namespace);
+
+    let workflow_cr = wf_api.get(&workflow_name).await.map_err(|e| {
+        eprintln!("Error fetching QuantumWorkflow '{}': {}", workflow_name, e);
+        StatusCode::NOT_FOUND
+    })?;
+    let all_jobs = job_api.list(&ListParams::default()).await.map_err(|e| {
+        eprintln!("Error listing jobs: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let job_statuses = job_task_statuses(&workflow_name, all_jobs.items);
+    Ok(Json(WorkflowStatusSummary::new(
+        &workflow_cr,
+        &job_statuses,
+    )))
+}
+
+/// The result of a finished task, as returned by `fetch_task_results`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TaskResult {
+    task_name: String,
+    phase: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    started_at: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finished_at: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+    output: TaskOutput,
+}
+
+/// What a task printed: `{ "json": value }` if its logs parse as JSON, else `{ "raw": logs }`.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum TaskOutput {
+    Json(serde_json::Value),
+    Raw(String),
+}
+
+impl TaskOutput {
+    fn from_logs(logs: String) -> Self {
+        match serde_json::from_str(&logs) {
+            Ok(value) => TaskOutput::Json(value),
+            Err(_) => TaskOutput::Raw(logs),
+        }
+    }
+}
+
+impl TaskResult {
+    /// Takes the phase and start time from the pod status, and the finish time and exit
+    /// code from the first container that has terminated.
+    fn from_pod(task_name: &str, pod: &Pod, output: TaskOutput) -> Self {
+        let status = pod.status.as_ref();
+        let terminated = status
+            .and_then(|s| s.container_statuses.as_ref())
+            .into_iter()
+            .flatten()
+            .find_map(|c| c.state.as_ref()?.terminated.as_ref());
+        TaskResult {
+            task_name: task_name.to_string(),
+            phase: pod_phase(pod).unwrap_or("Unknown").to_string(),
+            started_at: status.and_then(|s| s.start_time.clone()),
+            finished_at: terminated.and_then(|t| t.finished_at.clone()),
+            exit_code: terminated.map(|t| t.exit_code),
+            output,
+        }
+    }
+}
+
+/// The QCBM runner prints progress before its result, so only the last non-empty line is parsed.
+fn parse_qcbm_result(logs: &str) -> Option<QcbmResult> {
+    let last_line = logs.lines().rev().find(|l| !l.trim().is_empty())?;
+    serde_json::from_str(last_line).ok()
 }
 
 async fn submit_workflow(
     State(state): State<Arc<AppState>>,
-    Path((namespace)): Path<(String)>,
+    Path((namespace, workflow_name)): Path<(String, String)>,
+    Query(params): Query<SubmitParams>,
     Json(workflow): Json<QuantumWorkflowSpec>,
-) -> Result<StatusCode, StatusCode> {
-    // check the workflow
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
     println!("Submitting workflow '{:?}'", workflow);
+    validate_workflow(&workflow)?;
 
     let wf_api: Api<QuantumWorkflow> = Api::namespaced(state.client.clone(), &namespace);
 
@@ -315,7 +582,7 @@ async fn submit_workflow(
     // Convert the SyntheticWorkflow to a QuantumWorkflow CR
     let quantum_workflow = QuantumWorkflow {
         metadata: kube::api::ObjectMeta {
-            name: Some("workflow_name".parse().unwrap()),
+            name: Some(workflow_name),
             namespace: Some(namespace),
             ..Default::default()
         },
@@ -323,21 +590,100 @@ async fn submit_workflow(
         status: Default::default(),
     };
 
-    match wf_api
-        .create(&PostParams::default(), &quantum_workflow)
+    create_workflow(&wf_api, &quantum_workflow, params.upsert)
         .await
-    {
-        Ok(_) => Ok(StatusCode::CREATED),
-        Err(e) => {
+        .map_err(|e| {
             eprintln!("Error submitting workflow: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            (
+                submit_error_status(&e),
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+        })
+}
+
+/// Adds a task of any kind (`classical`, `quantum` or `qcbm`, given next to `name` and
+/// `dependsOn` as in the CR) to a workflow, creating the workflow if it doesn't exist yet.
+/// Returns 201 when a workflow was created, 200 when the task was appended, 400 if the
+/// resulting workflow is invalid, e.g. because the task name is already taken, and 409 if
+/// another request changed or created the workflow concurrently, so the client can retry.
+async fn submit_task(
+    State(state): State<Arc<AppState>>,
+    Path((namespace, workflow_name)): Path<(String, String)>,
+    Json(task): Json<QFlowTask>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let wf_api: Api<QuantumWorkflow> = Api::namespaced(state.client.clone(), &namespace);
+    let api_error = |e: kube::Error| {
+        eprintln!("Error adding task to workflow '{}': {}", workflow_name, e);
+        (
+            submit_error_status(&e),
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+    };
+
+    match wf_api.get_opt(&workflow_name).await.map_err(api_error)? {
+        Some(existing) => {
+            let mut spec = existing.spec;
+            spec.tasks.push(task);
+            validate_workflow(&spec)?;
+            // The patch replaces the whole task list, so it carries the resourceVersion we
+            // read: if another append landed in between, the API server rejects this one
+            // with a conflict instead of letting it drop the other task.
+            let patch = serde_json::json!({
+                "metadata": { "resourceVersion": existing.metadata.resource_version },
+                "spec": { "tasks": spec.tasks }
+            });
+            wf_api
+                .patch(
+                    &workflow_name,
+                    &PatchParams::default(),
+                    &Patch::Merge(&patch),
+                )
+                .await
+                .map_err(api_error)?;
+            Ok(StatusCode::OK)
+        }
+        None => {
+            let spec = QuantumWorkflowSpec {
+                volume: None,
+                tasks: vec![task],
+            };
+            validate_workflow(&spec)?;
+            let quantum_workflow = QuantumWorkflow {
+                metadata: kube::api::ObjectMeta {
+                    name: Some(workflow_name.clone()),
+                    namespace: Some(namespace.clone()),
+                    ..Default::default()
+                },
+                spec,
+                status: Default::default(),
+            };
+            wf_api
+                .create(&PostParams::default(), &quantum_workflow)
+                .await
+                .map_err(api_error)?;
+            Ok(StatusCode::CREATED)
         }
     }
 }
 
+/// Rejects workflows the operator could never schedule (dangling `dependsOn` entries,
+/// duplicate task names or dependency cycles) with `400 Bad Request` and an
+/// `{ "error": message }` body, instead of letting them fail after creation.
+fn validate_workflow(
+    workflow: &QuantumWorkflowSpec,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    workflow.topological_order().map(|_| ()).map_err(|message| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": message })),
+        )
+    })
+}
+
 async fn submit_qasm(
     State(state): State<Arc<AppState>>,
     Path((namespace, workflow_name)): Path<(String, String)>,
+    Query(params): Query<SubmitParams>,
     Form(form): Form<HashMap<String, String>>,
 ) -> Result<StatusCode, StatusCode> {
     let qasm_data = form.get("qasm_data").cloned().unwrap_or_default();
@@ -349,6 +695,9 @@ async fn submit_qasm(
     let quantum_task = qflow_types::QFlowTask {
         name: "qasm-task".to_string(),
         depends_on: None,
+        retries: None,
+        resources: None,
+        image_pull_policy: None,
         spec: QFlowTaskSpec::Quantum {
             image: "your-quantum-image:latest".to_string(),
             circuit: qasm_data.clone(),
@@ -373,15 +722,71 @@ async fn submit_qasm(
 
     let wf_api: Api<QuantumWorkflow> = Api::namespaced(state.client.clone(), &namespace);
 
-    match wf_api
-        .create(&PostParams::default(), &quantum_workflow)
+    create_workflow(&wf_api, &quantum_workflow, params.upsert)
         .await
-    {
-        Ok(_) => Ok(StatusCode::CREATED),
-        Err(e) => {
+        .map_err(|e| {
             eprintln!("Error submitting QASM workflow: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            submit_error_status(&e)
+        })
+}
+
+/// Creates `workflow`, or with `upsert` patches the spec of an existing workflow of the same
+/// name, so that clients can safely retry a submission.
+async fn create_workflow(
+    wf_api: &Api<QuantumWorkflow>,
+    workflow: &QuantumWorkflow,
+    upsert: bool,
+) -> Result<StatusCode, kube::Error> {
+    match wf_api.create(&PostParams::default(), workflow).await {
+        Ok(_) => Ok(StatusCode::CREATED),
+        Err(e) if upsert && submit_error_status(&e) == StatusCode::CONFLICT => {
+            let name = workflow.metadata.name.as_deref().unwrap_or_default();
+            let patch = serde_json::json!({ "spec": workflow.spec });
+            wf_api
+                .patch(name, &PatchParams::default(), &Patch::Merge(&patch))
+                .await?;
+            Ok(StatusCode::OK)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Maps a failed submission to a response status: 409 if a workflow with the same name
+/// already exists or was modified since it was read, 500 for anything else.
+fn submit_error_status(error: &kube::Error) -> StatusCode {
+    match error {
+        kube::Error::Api(response)
+            if response.reason == "AlreadyExists" || response.reason == "Conflict" =>
+        {
+            StatusCode::CONFLICT
         }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Deletes a workflow. Its Jobs, ConfigMaps and volume claim carry owner references to the
+/// CR, so Kubernetes garbage-collects them once the workflow is gone.
+async fn delete_workflow(
+    State(state): State<Arc<AppState>>,
+    Path((namespace, workflow_name)): Path<(String, String)>,
+) -> Result<StatusCode, StatusCode> {
+    let wf_api: Api<QuantumWorkflow> = Api::namespaced(state.client.clone(), &namespace);
+    wf_api
+        .delete(&workflow_name, &DeleteParams::background())
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(|e| {
+            eprintln!("Error deleting workflow '{}': {}", workflow_name, e);
+            delete_error_status(&e)
+        })
+}
+
+/// Maps a failed deletion to a response status: 404 if the workflow doesn't exist, 500 for
+/// anything else.
+fn delete_error_status(error: &kube::Error) -> StatusCode {
+    match error {
+        kube::Error::Api(response) if response.reason == "NotFound" => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
 
@@ -399,25 +804,27 @@ struct MlSvmResult {
 
 async fn run_ml_svm(
     State(state): State<Arc<AppState>>,
+    Query(params): Query<MlSvmQuery>,
     mut multipart: Multipart,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let mut csv_path = None;
+    let mut csv_data = None;
     let mut target_column = None;
     let mut test_size = None;
 
-    while let Some(field) = multipart.next_field().await.unwrap() {
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?
+    {
         let name = field.name().unwrap_or("");
         match name {
             "data_file" => {
-                let mut file =
-                    NamedTempFile::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-                let data = field
-                    .bytes()
-                    .await
-                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-                file.write_all(&data)
-                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-                csv_path = Some(file.into_temp_path());
+                let data = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+                if data.len() > MAX_SVM_CSV_BYTES {
+                    return Err(StatusCode::PAYLOAD_TOO_LARGE);
+                }
+                csv_data =
+                    Some(String::from_utf8(data.to_vec()).map_err(|_| StatusCode::BAD_REQUEST)?);
             }
             "target_column" => {
                 target_column = Some(field.text().await.unwrap_or_default());
@@ -429,14 +836,12 @@ async fn run_ml_svm(
         }
     }
 
-    let csv_path = csv_path.ok_or(StatusCode::BAD_REQUEST)?;
+    let csv_data = csv_data.ok_or(StatusCode::BAD_REQUEST)?;
     let target_column = target_column.ok_or(StatusCode::BAD_REQUEST)?;
     let test_size = test_size.ok_or(StatusCode::BAD_REQUEST)?;
 
-    let job_name = format!("ml-svm-job-{}", "job-12345");
-    let namespace = "default";
-    let image = "qsim:latest";
-    let csv_file_name = "input.csv";
+    let job_name = unique_job_name("ml-svm-job");
+    let namespace = params.namespace.as_str();
 
     // TODO: refactor this to create a QFlowTaskSpec for SVM
     // let quantum_task = qflow_types::QFlowTask {
@@ -449,12 +854,114 @@ async fn run_ml_svm(
     //     },
     // };
 
-    // Save the uploaded CSV to a location accessible by the Job (e.g., a PVC or object storage)
-    // For now, this is a placeholder. You may need to implement PVC upload or use a shared volume.
-    // Here, we assume the Job can access the file at /data/input.csv
+    let job_spec = svm_job_spec(
+        &job_name,
+        namespace,
+        &params.claim_name,
+        &target_column,
+        &test_size,
+    );
+    let job: Job =
+        serde_json::from_value(job_spec).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // The ConfigMap goes first so the Job never starts against missing data.
+    let config_map = svm_data_config_map(&job_name, csv_data);
+    let config_map_name = config_map.metadata.name.clone().unwrap_or_default();
+    let cm_api: Api<ConfigMap> = Api::namespaced(state.client.clone(), namespace);
+    cm_api
+        .create(&PostParams::default(), &config_map)
+        .await
+        .map_err(|e| {
+            eprintln!("Error uploading SVM data for Job '{}': {}", job_name, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let job_api: Api<Job> = Api::namespaced(state.client.clone(), namespace);
+    let job = match job_api.create(&PostParams::default(), &job).await {
+        Ok(job) => job,
+        Err(e) => {
+            eprintln!("Error submitting SVM Job: {}", e);
+            let _ = cm_api
+                .delete(&config_map_name, &DeleteParams::background())
+                .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
 
-    // Build Job spec
-    let job_spec = serde_json::json!({
+    // Owning the ConfigMap by the Job lets Kubernetes delete the uploaded data along with
+    // the Job. The Job is already running by now, so failing here only leaks the ConfigMap.
+    if let Some(owner) = job.controller_owner_ref(&()) {
+        let patch = serde_json::json!({ "metadata": { "ownerReferences": [owner] } });
+        if let Err(e) = cm_api
+            .patch(
+                &config_map_name,
+                &PatchParams::default(),
+                &Patch::Merge(&patch),
+            )
+            .await
+        {
+            eprintln!("Error handing SVM data to Job '{}': {}", job_name, e);
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "SVM Job submitted",
+        "job_name": job_name,
+        "namespace": namespace
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct MlSvmQuery {
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// An existing PersistentVolumeClaim in `namespace` that receives the plot and metrics.
+    pub claim_name: String,
+}
+
+fn default_namespace() -> String {
+    "default".to_string()
+}
+
+/// Returns `{prefix}-{millis}-{n}`, where `n` counts the names handed out by this process,
+/// so submissions landing in the same millisecond still get distinct Jobs.
+fn unique_job_name(prefix: &str) -> String {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    format!("{}-{}-{}", prefix, millis, id)
+}
+
+/// ConfigMaps are capped at 1 MiB by the API server, so larger uploads are rejected up front.
+const MAX_SVM_CSV_BYTES: usize = 1024 * 1024;
+const SVM_CSV_FILE_NAME: &str = "input.csv";
+
+/// The ConfigMap that carries the uploaded CSV into the SVM Job.
+fn svm_data_config_map(job_name: &str, csv_data: String) -> ConfigMap {
+    ConfigMap {
+        metadata: kube::api::ObjectMeta {
+            name: Some(format!("{}-data", job_name)),
+            ..Default::default()
+        },
+        data: Some(BTreeMap::from([(SVM_CSV_FILE_NAME.to_string(), csv_data)])),
+        ..Default::default()
+    }
+}
+
+/// Builds the SVM Job. The uploaded CSV is mounted read-only from the ConfigMap made by
+/// `svm_data_config_map`, and the plot and metrics are written to the claim `claim_name`
+/// so they outlive the pod.
+fn svm_job_spec(
+    job_name: &str,
+    namespace: &str,
+    claim_name: &str,
+    target_column: &str,
+    test_size: &str,
+) -> serde_json::Value {
+    serde_json::json!({
         "apiVersion": "batch/v1",
         "kind": "Job",
         "metadata": {
@@ -469,41 +976,674 @@ async fn run_ml_svm(
                 "spec": {
                     "containers": [{
                         "name": "ml-svm",
-                        "image": image,
+                        "image": "qsim:latest",
                         "args": [
-                            "--data_path", format!("/data/{}", csv_file_name),
+                            "--data_path", format!("/input/{}", SVM_CSV_FILE_NAME),
                             "--target-column", target_column,
                             "--output-plot", "/data/plot.png",
                             "--output-metrics", "/data/metrics.txt",
                             "--test-size", test_size
                         ],
-                        "volumeMounts": [{
-                            "name": "data-volume",
-                            "mountPath": "/data"
-                        }]
+                        "volumeMounts": [
+                            {
+                                "name": "input-volume",
+                                "mountPath": "/input",
+                                "readOnly": true
+                            },
+                            {
+                                "name": "data-volume",
+                                "mountPath": "/data"
+                            }
+                        ]
                     }],
                     "restartPolicy": "Never",
-                    "volumes": [{
-                        "name": "data-volume",
-                        // Define your PVC here
-                        "persistentVolumeClaim": { "claimName": "your-pvc" }
-                    }]
+                    "volumes": [
+                        {
+                            "name": "input-volume",
+                            "configMap": { "name": format!("{}-data", job_name) }
+                        },
+                        {
+                            "name": "data-volume",
+                            "persistentVolumeClaim": { "claimName": claim_name }
+                        }
+                    ]
                 }
             }
         }
-    });
+    })
+}
 
-    let job_api: Api<Job> = Api::namespaced(state.client.clone(), namespace);
-    let job: Job =
-        serde_json::from_value(job_spec).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    match job_api.create(&PostParams::default(), &job).await {
-        Ok(_) => Ok(Json(serde_json::json!({
-            "message": "SVM Job submitted",
-            "job_name": job_name
-        }))),
-        Err(e) => {
-            eprintln!("Error submitting SVM Job: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::batch::v1::JobStatus;
+    use k8s_openapi::api::core::v1::{
+        ContainerState, ContainerStateTerminated, ContainerStatus, PodStatus,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+    use kube::core::ErrorResponse;
+    use tower::ServiceExt;
+
+    fn api_error(reason: &str, code: u16) -> kube::Error {
+        kube::Error::Api(ErrorResponse {
+            status: "Failure".to_string(),
+            message: format!("{} error", reason),
+            reason: reason.to_string(),
+            code,
+        })
+    }
+
+    /// App state whose client hands every request to `handler` instead of an API server.
+    fn fake_state<F, Fut, E>(handler: F) -> Arc<AppState>
+    where
+        F: FnMut(axum::http::Request<kube::client::Body>) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<axum::http::Response<kube::client::Body>, E>>
+            + Send
+            + 'static,
+        E: Into<tower::BoxError>,
+    {
+        Arc::new(AppState {
+            client: Client::new(tower::service_fn(handler), "default"),
+        })
+    }
+
+    /// A fake API server response with the given status and body.
+    fn api_response(
+        status: u16,
+        body: impl Into<Vec<u8>>,
+    ) -> Result<axum::http::Response<kube::client::Body>, kube::Error> {
+        axum::http::Response::builder()
+            .status(status)
+            .body(kube::client::Body::from(body.into()))
+            .map_err(kube::Error::HttpError)
+    }
+
+    /// The `Status` the API server returns with `code` for a failed request.
+    fn api_failure(
+        reason: &str,
+        code: u16,
+    ) -> Result<axum::http::Response<kube::client::Body>, kube::Error> {
+        let status = serde_json::json!({
+            "kind": "Status",
+            "apiVersion": "v1",
+            "status": "Failure",
+            "message": format!("{} error", reason),
+            "reason": reason,
+            "code": code
+        });
+        api_response(code, status.to_string())
+    }
+
+    #[test]
+    fn test_submit_error_status_distinguishes_already_exists() {
+        assert_eq!(
+            submit_error_status(&api_error("AlreadyExists", 409)),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            submit_error_status(&api_error("Conflict", 409)),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            submit_error_status(&api_error("Forbidden", 403)),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            submit_error_status(&kube::Error::LinesCodecMaxLineLengthExceeded),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[tokio::test]
+    async fn test_submit_workflow_rejects_missing_dependency() {
+        // Any request reaching the API server fails, so a 400 can only come from validation.
+        let state = fake_state(|_| async {
+            Err::<axum::http::Response<kube::client::Body>, _>(std::io::Error::other("unreachable"))
+        });
+        let app = Router::new()
+            .route(
+                "/api/workflows/{namespace}/{name}/new",
+                post(submit_workflow),
+            )
+            .with_state(state);
+
+        let workflow = serde_json::json!({
+            "tasks": [{
+                "name": "train",
+                "dependsOn": ["prepare"],
+                "quantum": { "image": "qsim:latest", "circuit": "", "params": "" }
+            }]
+        });
+        let request = axum::http::Request::post("/api/workflows/default/train-flow/new")
+            .header("content-type", "application/json")
+            .body(Body::from(workflow.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(body["error"].as_str().unwrap().contains("'prepare'"));
+    }
+
+    #[tokio::test]
+    async fn test_submit_workflow_uses_name_from_path() {
+        // The fake API server records the submitted CR and echoes it back as created.
+        let created = Arc::new(std::sync::Mutex::new(None));
+        let recorder = created.clone();
+        let state = fake_state(move |request: axum::http::Request<kube::client::Body>| {
+            let recorder = recorder.clone();
+            async move {
+                let body = request.into_body().collect_bytes().await?;
+                let workflow: QuantumWorkflow = serde_json::from_slice(&body).unwrap();
+                *recorder.lock().unwrap() = Some(workflow);
+                api_response(201, body.to_vec())
+            }
+        });
+        let app = Router::new()
+            .route(
+                "/api/workflows/{namespace}/{name}/new",
+                post(submit_workflow),
+            )
+            .with_state(state);
+
+        let request = axum::http::Request::post("/api/workflows/default/bell-flow/new")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"tasks": []}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let workflow = created.lock().unwrap().take().unwrap();
+        assert_eq!(workflow.metadata.name.as_deref(), Some("bell-flow"));
+        assert_eq!(workflow.metadata.namespace.as_deref(), Some("default"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_workflow() {
+        // The fake API server knows a single workflow, "bell-flow".
+        let state = fake_state(
+            |request: axum::http::Request<kube::client::Body>| async move {
+                assert_eq!(request.method(), axum::http::Method::DELETE);
+                if request
+                    .uri()
+                    .path()
+                    .ends_with("/quantumworkflows/bell-flow")
+                {
+                    let success = serde_json::json!({ "kind": "Status", "apiVersion": "v1", "status": "Success" });
+                    api_response(200, success.to_string())
+                } else {
+                    api_failure("NotFound", 404)
+                }
+            },
+        );
+        let app = Router::new()
+            .route("/api/workflows/{namespace}/{name}", delete(delete_workflow))
+            .with_state(state);
+
+        let delete_status = |name: &str| {
+            let request = axum::http::Request::delete(format!("/api/workflows/default/{}", name))
+                .body(Body::empty())
+                .unwrap();
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+        assert_eq!(delete_status("bell-flow").await, StatusCode::NO_CONTENT);
+        assert_eq!(delete_status("ghost").await, StatusCode::NOT_FOUND);
+    }
+
+    fn pod(name: &str, phase: &str) -> Pod {
+        Pod {
+            metadata: kube::api::ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            status: Some(PodStatus {
+                phase: Some(phase.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_select_log_pod() {
+        let selected = |pods: &[Pod]| select_log_pod(pods).and_then(|p| p.metadata.name.clone());
+
+        let running = [pod("failed", "Failed"), pod("running", "Running")];
+        assert_eq!(selected(&running).as_deref(), Some("running"));
+
+        let finished = [pod("running", "Running"), pod("done", "Succeeded")];
+        assert_eq!(selected(&finished).as_deref(), Some("done"));
+
+        assert_eq!(selected(&[pod("pending", "Pending")]), None);
+    }
+
+    #[test]
+    fn test_task_result_from_succeeded_pod() {
+        let start_time: Time = serde_json::from_str(r#""2025-01-01T10:00:00Z""#).unwrap();
+        let finished_at: Time = serde_json::from_str(r#""2025-01-01T10:05:00Z""#).unwrap();
+        let mut succeeded = pod("task-pod", "Succeeded");
+        let status = succeeded.status.as_mut().unwrap();
+        status.start_time = Some(start_time.clone());
+        status.container_statuses = Some(vec![ContainerStatus {
+            state: Some(ContainerState {
+                terminated: Some(ContainerStateTerminated {
+                    exit_code: 0,
+                    finished_at: Some(finished_at.clone()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }]);
+
+        let logs = r#"{"counts": {"00": 512, "11": 512}}"#.to_string();
+        let result = TaskResult::from_pod("bell", &succeeded, TaskOutput::from_logs(logs));
+        assert_eq!(result.task_name, "bell");
+        assert_eq!(result.phase, "Succeeded");
+        assert_eq!(result.started_at, Some(start_time));
+        assert_eq!(result.finished_at, Some(finished_at));
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(
+            result.output,
+            TaskOutput::Json(serde_json::json!({ "counts": { "00": 512, "11": 512 } }))
+        );
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["phase"], "Succeeded");
+        assert_eq!(json["output"]["json"]["counts"]["11"], 512);
+        assert_eq!(
+            TaskOutput::from_logs("done".to_string()),
+            TaskOutput::Raw("done".to_string())
+        );
+    }
+
+    #[test]
+    fn test_svm_job_mounts_uploaded_csv() {
+        let csv = "x,y,label\n0.1,0.2,0\n0.9,0.8,1\n".to_string();
+        let config_map = svm_data_config_map("ml-svm-job-1", csv.clone());
+        let job: Job = serde_json::from_value(svm_job_spec(
+            "ml-svm-job-1",
+            "default",
+            "svm-results",
+            "label",
+            "0.2",
+        ))
+        .unwrap();
+
+        let pod_spec = job.spec.unwrap().template.spec.unwrap();
+        let container = &pod_spec.containers[0];
+        let args = container.args.as_ref().unwrap();
+        let data_path = &args[args.iter().position(|a| a == "--data_path").unwrap() + 1];
+
+        // Find the volume mounted over the data path and check it serves the CSV.
+        let mount = container
+            .volume_mounts
+            .iter()
+            .flatten()
+            .find(|m| data_path.starts_with(&format!("{}/", m.mount_path)))
+            .unwrap();
+        let file_name = &data_path[mount.mount_path.len() + 1..];
+        let volume = pod_spec
+            .volumes
+            .iter()
+            .flatten()
+            .find(|v| v.name == mount.name)
+            .unwrap();
+        let source = volume.config_map.as_ref().unwrap();
+        assert_eq!(Some(&source.name), config_map.metadata.name.as_ref());
+        assert_eq!(config_map.data.unwrap()[file_name], csv);
+    }
+
+    #[test]
+    fn test_svm_job_writes_outputs_to_requested_claim() {
+        let job: Job = serde_json::from_value(svm_job_spec(
+            "ml-svm-job-1",
+            "default",
+            "svm-results",
+            "label",
+            "0.2",
+        ))
+        .unwrap();
+
+        let pod_spec = job.spec.unwrap().template.spec.unwrap();
+        let container = &pod_spec.containers[0];
+        let args = container.args.as_ref().unwrap();
+        for flag in ["--output-plot", "--output-metrics"] {
+            let path = &args[args.iter().position(|a| a == flag).unwrap() + 1];
+            let mount = container
+                .volume_mounts
+                .iter()
+                .flatten()
+                .find(|m| path.starts_with(&format!("{}/", m.mount_path)))
+                .unwrap();
+            let volume = pod_spec
+                .volumes
+                .iter()
+                .flatten()
+                .find(|v| v.name == mount.name)
+                .unwrap();
+            let claim = volume.persistent_volume_claim.as_ref().unwrap();
+            assert_eq!(claim.claim_name, "svm-results");
+        }
+    }
+
+    #[test]
+    fn test_unique_job_names_differ() {
+        let first = unique_job_name("ml-svm-job");
+        let second = unique_job_name("ml-svm-job");
+        assert_ne!(first, second);
+        assert!(first.starts_with("ml-svm-job-"));
+    }
+
+    fn job(workflow_name: &str, task_name: &str, status: JobStatus) -> Job {
+        Job {
+            metadata: kube::api::ObjectMeta {
+                labels: Some(BTreeMap::from([(
+                    "qflow.io/task-name".to_string(),
+                    task_name.to_string(),
+                )])),
+                owner_references: Some(vec![OwnerReference {
+                    name: workflow_name.to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+            status: Some(status),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_workflow_status_summary() {
+        let workflow = QuantumWorkflow::new(
+            "bell-flow",
+            QuantumWorkflowSpec {
+                volume: None,
+                tasks: vec![
+                    qflow_types::QFlowTask {
+                        name: "prepare".to_string(),
+                        ..Default::default()
+                    },
+                    qflow_types::QFlowTask {
+                        name: "measure".to_string(),
+                        depends_on: Some(vec!["prepare".to_string()]),
+                        ..Default::default()
+                    },
+                ],
+            },
+        );
+        let jobs = vec![
+            job(
+                "bell-flow",
+                "prepare",
+                JobStatus {
+                    succeeded: Some(1),
+                    ..Default::default()
+                },
+            ),
+            job(
+                "bell-flow",
+                "measure",
+                JobStatus {
+                    active: Some(1),
+                    ..Default::default()
+                },
+            ),
+            // Jobs of other workflows are ignored.
+            job(
+                "other-flow",
+                "measure",
+                JobStatus {
+                    failed: Some(1),
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        let summary = WorkflowStatusSummary::new(&workflow, &job_task_statuses("bell-flow", jobs));
+        assert_eq!(summary.phase, "Running");
+        assert_eq!(summary.task_statuses["prepare"], "Succeeded");
+        assert_eq!(summary.task_statuses["measure"], "Running");
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["taskStatuses"]["measure"], "Running");
+    }
+
+    #[test]
+    fn test_workflow_status_summary_prefers_recorded_phase() {
+        let mut workflow = QuantumWorkflow::new(
+            "bell-flow",
+            QuantumWorkflowSpec {
+                volume: None,
+                tasks: vec![qflow_types::QFlowTask {
+                    name: "prepare".to_string(),
+                    ..Default::default()
+                }],
+            },
+        );
+        workflow.status = Some(qflow_types::QuantumWorkflowStatus {
+            phase: Some("Failed".to_string()),
+            ..Default::default()
+        });
+        let jobs = vec![job(
+            "bell-flow",
+            "prepare",
+            JobStatus {
+                succeeded: Some(1),
+                ..Default::default()
+            },
+        )];
+
+        let summary = WorkflowStatusSummary::new(&workflow, &job_task_statuses("bell-flow", jobs));
+        assert_eq!(summary.phase, "Failed");
+        assert_eq!(summary.task_statuses["prepare"], "Succeeded");
+
+        let empty = QuantumWorkflow::new(
+            "empty-flow",
+            QuantumWorkflowSpec {
+                volume: None,
+                tasks: vec![],
+            },
+        );
+        let summary = WorkflowStatusSummary::new(&empty, &HashMap::new());
+        assert_eq!(summary.phase, "Pending");
+        assert!(summary.task_statuses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_submit_task_creates_workflow_with_qcbm_task() {
+        // The fake API server has no workflows yet, records the created CR and echoes it.
+        let created = Arc::new(std::sync::Mutex::new(None));
+        let recorder = created.clone();
+        let state = fake_state(move |request: axum::http::Request<kube::client::Body>| {
+            let recorder = recorder.clone();
+            async move {
+                if request.method() == axum::http::Method::GET {
+                    return api_failure("NotFound", 404);
+                }
+                let body = request.into_body().collect_bytes().await?;
+                let workflow: QuantumWorkflow = serde_json::from_slice(&body).unwrap();
+                *recorder.lock().unwrap() = Some(workflow);
+                api_response(201, body.to_vec())
+            }
+        });
+        let app = Router::new()
+            .route("/api/workflows/{namespace}/{name}/tasks", post(submit_task))
+            .with_state(state);
+
+        let task = serde_json::json!({
+            "name": "born-machine",
+            "qcbm": {
+                "image": "qcbm:latest",
+                "ansatz": "hardware-efficient",
+                "trainingData": ["00", "11"]
+            }
+        });
+        let request = axum::http::Request::post("/api/workflows/default/qcbm-flow/tasks")
+            .header("content-type", "application/json")
+            .body(Body::from(task.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let workflow = created.lock().unwrap().take().unwrap();
+        assert_eq!(workflow.metadata.name.as_deref(), Some("qcbm-flow"));
+        assert_eq!(workflow.spec.tasks.len(), 1);
+        assert_eq!(workflow.spec.tasks[0].name, "born-machine");
+        match &workflow.spec.tasks[0].spec {
+            QFlowTaskSpec::Qcbm(spec) => assert_eq!(spec.training_data, vec!["00", "11"]),
+            other => panic!("expected a Qcbm task, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_task_append_is_rejected_on_concurrent_change() {
+        // The fake API server holds "pipeline" at resourceVersion 7, records the patch and
+        // answers it as if another append had already bumped the version.
+        let patched = Arc::new(std::sync::Mutex::new(None));
+        let recorder = patched.clone();
+        let state = fake_state(move |request: axum::http::Request<kube::client::Body>| {
+            let recorder = recorder.clone();
+            async move {
+                if request.method() == axum::http::Method::GET {
+                    let existing = serde_json::json!({
+                        "apiVersion": "qflow.io/v1alpha1",
+                        "kind": "QuantumWorkflow",
+                        "metadata": {
+                            "name": "pipeline",
+                            "namespace": "default",
+                            "resourceVersion": "7"
+                        },
+                        "spec": {
+                            "tasks": [{ "name": "prepare", "classical": { "image": "alpine" } }]
+                        }
+                    });
+                    return api_response(200, existing.to_string());
+                }
+                let body = request.into_body().collect_bytes().await?;
+                let patch: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                *recorder.lock().unwrap() = Some(patch);
+                api_failure("Conflict", 409)
+            }
+        });
+        let app = Router::new()
+            .route("/api/workflows/{namespace}/{name}/tasks", post(submit_task))
+            .with_state(state);
+
+        let task = serde_json::json!({
+            "name": "train",
+            "dependsOn": ["prepare"],
+            "classical": { "image": "python:3" }
+        });
+        let request = axum::http::Request::post("/api/workflows/default/pipeline/tasks")
+            .header("content-type", "application/json")
+            .body(Body::from(task.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let patch = patched.lock().unwrap().take().unwrap();
+        assert_eq!(patch["metadata"]["resourceVersion"], "7");
+        let names: Vec<&str> = patch["spec"]["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["prepare", "train"]);
+    }
+
+    #[tokio::test]
+    async fn test_task_logs_are_scoped_to_the_workflow() {
+        // Both workflows have a "train" task. The fake API server filters Jobs by label
+        // selector like the real one and serves each pod's name as its log.
+        let state = fake_state(
+            |request: axum::http::Request<kube::client::Body>| async move {
+                let path = request.uri().path().to_string();
+                let selector = request
+                    .uri()
+                    .query()
+                    .and_then(|q| q.split('&').find_map(|p| p.strip_prefix("labelSelector=")))
+                    .unwrap_or_default()
+                    .replace("%3D", "=")
+                    .replace("%2C", ",")
+                    .replace("%2F", "/");
+                let selected = |labels: &[(&str, &str)]| {
+                    selector
+                        .split(',')
+                        .all(|term| labels.iter().any(|(k, v)| term == format!("{}={}", k, v)))
+                };
+                if path.ends_with("/jobs") {
+                    let items: Vec<_> = ["flow-a", "flow-b"]
+                        .into_iter()
+                        .filter(|wf| {
+                            selected(&[
+                                ("qflow.io/workflow-name", *wf),
+                                ("qflow.io/task-name", "train"),
+                            ])
+                        })
+                        .map(|wf| {
+                            serde_json::json!({ "metadata": {
+                                "name": format!("{}-train", wf),
+                                "labels": {
+                                    "qflow.io/workflow-name": wf,
+                                    "qflow.io/task-name": "train"
+                                }
+                            } })
+                        })
+                        .collect();
+                    let list = serde_json::json!({
+                        "apiVersion": "batch/v1",
+                        "kind": "JobList",
+                        "metadata": {},
+                        "items": items
+                    });
+                    api_response(200, list.to_string())
+                } else if path.ends_with("/pods") {
+                    let job_name = selector.strip_prefix("job-name=").unwrap_or_default();
+                    let list = serde_json::json!({
+                        "apiVersion": "v1",
+                        "kind": "PodList",
+                        "metadata": {},
+                        "items": [{
+                            "metadata": { "name": format!("{}-pod", job_name) },
+                            "status": { "phase": "Succeeded" }
+                        }]
+                    });
+                    api_response(200, list.to_string())
+                } else if let Some(pod) =
+                    path.strip_suffix("/log").and_then(|p| p.rsplit('/').next())
+                {
+                    api_response(200, pod.to_string())
+                } else {
+                    api_failure("NotFound", 404)
+                }
+            },
+        );
+        let app = Router::new()
+            .route(
+                "/api/workflows/{namespace}/{name}/tasks/{task_name}/logs",
+                get(fetch_task_logs),
+            )
+            .with_state(state);
+
+        for workflow in ["flow-a", "flow-b"] {
+            let request = axum::http::Request::get(format!(
+                "/api/workflows/default/{}/tasks/train/logs",
+                workflow
+            ))
+            .body(Body::empty())
+            .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(body, format!("{}-train-pod", workflow));
         }
     }
 }
@@ -1,15 +1,13 @@
-use std::collections::{BTreeMap, HashMap};
-use std::hash::RandomState;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use futures_util::StreamExt;
 use kube::{
     Resource,
-    api::{Api, Patch, PatchParams, PostParams},
+    api::{Api, ListParams, LogParams, Patch, PatchParams, PostParams},
     client::Client,
     runtime::{Controller, controller::Action},
 };
-use petgraph::{graphmap::DiGraphMap, visit::Topo};
 use thiserror::Error;
 use tokio::time::Duration;
 use tracing::{error, info, warn};
@@ -17,13 +15,13 @@ use tracing::{error, info, warn};
 use k8s_openapi::api::batch::v1::{Job, JobSpec};
 use k8s_openapi::api::core::v1::{
     ConfigMap, ConfigMapVolumeSource, Container, PersistentVolumeClaim, PersistentVolumeClaimSpec,
-    PersistentVolumeClaimVolumeSource, PodSpec, PodTemplateSpec, Volume, VolumeMount,
-    VolumeResourceRequirements,
+    PersistentVolumeClaimVolumeSource, Pod, PodSpec, PodTemplateSpec, ResourceRequirements, Volume,
+    VolumeMount, VolumeResourceRequirements,
 };
 use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
-use qflow_types::{QFlowTask, QFlowTaskSpec, QcbmOptimizerSpec, QuantumWorkflow};
+use qflow_types::{QFlowTask, QFlowTaskSpec, QuantumWorkflow, TaskProgress};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -40,6 +38,8 @@ pub struct QuantumWorkflowStatus {
     pub phase: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub task_statuses: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_messages: Option<BTreeMap<String, String>>,
 }
 
 #[derive(Error, Debug)]
@@ -59,7 +59,23 @@ const TASK_PENDING: &str = "Pending";
 const TASK_RUNNING: &str = "Running";
 const TASK_SUCCEEDED: &str = "Succeeded";
 const TASK_FAILED: &str = "Failed";
+
+/// The entrypoint of classical tasks that don't set `command`.
+const DEFAULT_CLASSICAL_COMMAND: &str = "/qsim";
+
+/// The pull policy of tasks that don't set `imagePullPolicy`.
+const DEFAULT_IMAGE_PULL_POLICY: &str = "IfNotPresent";
+
+/// The Job `backoffLimit` for tasks that don't set `retries`.
+const DEFAULT_TASK_RETRIES: i32 = 4;
 const QFLOW_TASK_NAME_LABEL: &str = "qflow.io/task-name";
+/// Task names are only unique within a workflow, so Jobs carry the workflow's name too.
+const QFLOW_WORKFLOW_NAME_LABEL: &str = "qflow.io/workflow-name";
+/// Only the end of a running task's output is fetched when looking for progress.
+const PROGRESS_LOG_TAIL_LINES: i64 = 20;
+/// Registry prepended to task images that don't already name a registry host.
+const IMAGE_REGISTRY_ENV: &str = "QFLOW_IMAGE_REGISTRY";
+const DEFAULT_QSIM_IMAGE: &str = "qsim:latest";
 
 async fn create_pvc_if_not_exists(client: &Client, wf: &QuantumWorkflow) -> Result<(), Error> {
     let ns = wf
@@ -99,12 +115,45 @@ async fn create_pvc_if_not_exists(client: &Client, wf: &QuantumWorkflow) -> Resu
     Ok(())
 }
 
+/// Prefixes `image` with `registry` unless the image already names a registry host,
+/// i.e. its first path segment contains a `.` or `:` or is `localhost`.
+fn qualify_image(image: &str, registry: Option<&str>) -> String {
+    let registry = match registry.map(|r| r.trim_end_matches('/')) {
+        Some(r) if !r.is_empty() => r,
+        _ => return image.to_string(),
+    };
+    let has_host = image.split_once('/').is_some_and(|(first, _)| {
+        first.contains('.') || first.contains(':') || first == "localhost"
+    });
+    if has_host {
+        image.to_string()
+    } else {
+        format!("{}/{}", registry, image)
+    }
+}
+
+/// Converts a task's resources into the container's, leaving out unset quantities.
+fn container_resources(resources: &qflow_types::ResourceRequirements) -> ResourceRequirements {
+    let quantities = |q: &qflow_types::ResourceQuantities| -> BTreeMap<String, Quantity> {
+        [("cpu", &q.cpu), ("memory", &q.memory)]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), Quantity(value.clone()?))))
+            .collect()
+    };
+    ResourceRequirements {
+        requests: resources.requests.as_ref().map(quantities),
+        limits: resources.limits.as_ref().map(quantities),
+        ..Default::default()
+    }
+}
+
 /// Creates a Kubernetes Job for a given task spec.
 /// This function has been refactored to handle Classical, Quantum, and the new QCBM task types.
 fn create_job_for_task(
     wf: &QuantumWorkflow,
     task: &QFlowTask,
     cm_name: Option<String>,
+    registry: Option<&str>,
 ) -> Result<Job, Error> {
     let pvc_name = format!("{}-{}", wf.metadata.name.clone().unwrap(), PVC_NAME);
 
@@ -122,13 +171,21 @@ fn create_job_for_task(
         ..Default::default()
     }];
 
-    let container = match &task.spec {
-        QFlowTaskSpec::Classical { image } => Container {
+    let mut container = match &task.spec {
+        QFlowTaskSpec::Classical {
+            image,
+            command,
+            args,
+        } => Container {
             name: "task-runner".to_string(),
-            image: Some(image.clone()),
-            command: Some(vec!["/qsim".to_string()]),
+            image: Some(qualify_image(image, registry)),
+            command: Some(
+                command
+                    .clone()
+                    .unwrap_or_else(|| vec![DEFAULT_CLASSICAL_COMMAND.to_string()]),
+            ),
+            args: args.clone(),
             volume_mounts: Some(volume_mounts),
-            image_pull_policy: Some("Never".to_string()),
             ..Default::default()
         },
         QFlowTaskSpec::Quantum { image, .. } => {
@@ -150,7 +207,7 @@ fn create_job_for_task(
                     ..Default::default()
                 });
             }
-            let default_image = "qsim:latest".to_string();
+            let default_image = qualify_image(DEFAULT_QSIM_IMAGE, registry);
             let input_file_path = "/workspace/input/circuit.qasm";
             Container {
                 name: "task-runner".to_string(),
@@ -161,7 +218,6 @@ fn create_job_for_task(
                     input_file_path.to_string(),
                 ]),
                 volume_mounts: Some(volume_mounts),
-                image_pull_policy: Some("Never".to_string()),
                 ..Default::default()
             }
         }
@@ -169,15 +225,7 @@ fn create_job_for_task(
             let training_data_json = serde_json::to_string(&qcbm_spec.training_data)
                 .map_err(|e| Error::Anyhow(anyhow::Error::from(e)))?;
 
-            let optimizer_spec = qcbm_spec
-                .optimizer
-                .clone()
-                .unwrap_or_else(|| QcbmOptimizerSpec {
-                    name: "Adam".to_string(),
-                    epochs: 100,
-                    learning_rate: 0.01,
-                    initial_params: None,
-                });
+            let optimizer_spec = qcbm_spec.optimizer.clone().unwrap_or_default();
 
             let mut args = vec![
                 "--ansatz".to_string(),
@@ -196,21 +244,36 @@ fn create_job_for_task(
 
             Container {
                 name: "task-runner".to_string(),
-                image: Some(qcbm_spec.image.clone()),
+                image: Some(qualify_image(&qcbm_spec.image, registry)),
                 args: Some(args),
                 volume_mounts: Some(volume_mounts),
-                image_pull_policy: Some("Never".to_string()),
                 ..Default::default()
             }
         }
     };
 
+    container.resources = task.resources.as_ref().map(container_resources);
+    container.image_pull_policy = Some(
+        task.image_pull_policy
+            .clone()
+            .unwrap_or_else(|| DEFAULT_IMAGE_PULL_POLICY.to_string()),
+    );
+
     let job_name = format!("{}-{}", wf.metadata.name.clone().unwrap(), task.name);
     Ok(Job {
         metadata: ObjectMeta {
             name: Some(job_name),
             owner_references: Some(vec![wf.controller_owner_ref(&()).unwrap()]),
-            labels: Some([(QFLOW_TASK_NAME_LABEL.to_string(), task.name.clone())].into()),
+            labels: Some(
+                [
+                    (QFLOW_TASK_NAME_LABEL.to_string(), task.name.clone()),
+                    (
+                        QFLOW_WORKFLOW_NAME_LABEL.to_string(),
+                        wf.metadata.name.clone().unwrap(),
+                    ),
+                ]
+                .into(),
+            ),
             ..Default::default()
         },
         spec: Some(JobSpec {
@@ -223,7 +286,7 @@ fn create_job_for_task(
                 }),
                 ..Default::default()
             },
-            backoff_limit: Some(4),
+            backoff_limit: Some(task.retries.unwrap_or(DEFAULT_TASK_RETRIES)),
             ..Default::default()
         }),
         ..Default::default()
@@ -241,6 +304,61 @@ async fn update_status(
     Ok(())
 }
 
+/// Returns the pending tasks whose dependencies have all succeeded, in the order given by
+/// `QuantumWorkflowSpec::topological_order`.
+///
+/// The result is a pure function of `order` and `statuses`. The topological order depends
+/// only on the spec, and `statuses` is a `BTreeMap`, so it serializes back into the
+/// workflow status ordered by task name. Reconciling the same state twice therefore
+/// schedules the same tasks in the same order.
+fn schedulable_tasks<'a>(
+    order: &[&'a QFlowTask],
+    statuses: &BTreeMap<String, String>,
+) -> Vec<&'a QFlowTask> {
+    let has_status =
+        |name: &str, status: &str| statuses.get(name).is_some_and(|s| s.as_str() == status);
+
+    order
+        .iter()
+        .copied()
+        .filter(|task| has_status(&task.name, TASK_PENDING))
+        .filter(|task| {
+            task.depends_on
+                .iter()
+                .flatten()
+                .all(|dep_name| has_status(dep_name, TASK_SUCCEEDED))
+        })
+        .collect()
+}
+
+/// The status message for the latest progress line in a task's output, e.g. `epoch 30/100`.
+fn progress_message(logs: &str) -> Option<String> {
+    TaskProgress::parse_last(logs).map(|progress| progress.to_string())
+}
+
+/// Reads the latest progress a running Job's pod has reported in its output.
+/// The pod log is the source of truth rather than the results PVC: tasks only write their
+/// results to the claim when they finish, and the operator would need a pod mounting the
+/// claim to read it, whereas the API server serves a running pod's log directly.
+async fn fetch_progress_message(pod_api: &Api<Pod>, job_name: &str) -> Option<String> {
+    let pods = pod_api
+        .list(&ListParams::default().labels(&format!("job-name={}", job_name)))
+        .await
+        .map_err(|e| warn!("Failed to list pods for job {}: {}", job_name, e))
+        .ok()?;
+    let pod_name = pods.items.first()?.metadata.name.clone()?;
+    let params = LogParams {
+        tail_lines: Some(PROGRESS_LOG_TAIL_LINES),
+        ..Default::default()
+    };
+    let logs = pod_api
+        .logs(&pod_name, &params)
+        .await
+        .map_err(|e| warn!("Failed to read logs of pod {}: {}", pod_name, e))
+        .ok()?;
+    progress_message(&logs)
+}
+
 async fn reconcile(wf: Arc<QuantumWorkflow>, ctx: Arc<Context>) -> Result<Action, Error> {
     let client = &ctx.client;
     let ns = wf
@@ -251,6 +369,7 @@ async fn reconcile(wf: Arc<QuantumWorkflow>, ctx: Arc<Context>) -> Result<Action
     let wf_api = Api::<QuantumWorkflow>::namespaced(client.clone(), &ns);
     let job_api = Api::<Job>::namespaced(client.clone(), &ns);
     let cm_api = Api::<ConfigMap>::namespaced(client.clone(), &ns);
+    let pod_api = Api::<Pod>::namespaced(client.clone(), &ns);
 
     if wf.status.is_none() {
         info!(
@@ -265,34 +384,16 @@ async fn reconcile(wf: Arc<QuantumWorkflow>, ctx: Arc<Context>) -> Result<Action
         let status = QuantumWorkflowStatus {
             phase: Some(TASK_PENDING.to_string()),
             task_statuses: Some(initial_statuses),
+            task_messages: None,
         };
         update_status(&wf_api, &wf.metadata.name.clone().unwrap(), status).await?;
         return Ok(Action::requeue(Duration::from_secs(1)));
     }
 
-    let mut graph = DiGraphMap::<&str, _, RandomState>::new();
-    let task_map: HashMap<&str, &QFlowTask> =
-        wf.spec.tasks.iter().map(|t| (t.name.as_str(), t)).collect();
-
-    for task in &wf.spec.tasks {
-        graph.add_node(&task.name);
-    }
-    for task in &wf.spec.tasks {
-        if let Some(deps) = &task.depends_on {
-            for dep_name in deps {
-                if !graph.contains_node(dep_name) {
-                    return Err(Error::InvalidWorkflow(format!(
-                        "Task '{}' depends on non-existent task '{}'",
-                        task.name, dep_name
-                    )));
-                }
-                graph.add_edge(dep_name, &task.name, ());
-            }
-        }
-    }
-    if petgraph::algo::is_cyclic_directed(&graph) {
-        return Err(Error::InvalidWorkflow("Workflow has a cycle".to_string()));
-    }
+    let order = wf
+        .spec
+        .topological_order()
+        .map_err(Error::InvalidWorkflow)?;
 
     let mut current_statuses = wf
         .status
@@ -300,6 +401,12 @@ async fn reconcile(wf: Arc<QuantumWorkflow>, ctx: Arc<Context>) -> Result<Action
         .and_then(|s| s.task_statuses.as_ref())
         .cloned()
         .unwrap_or_default();
+    let mut task_messages = wf
+        .status
+        .as_ref()
+        .and_then(|s| s.task_messages.as_ref())
+        .cloned()
+        .unwrap_or_default();
     let mut made_change = false;
 
     for (task_name, status) in current_statuses.iter_mut() {
@@ -310,9 +417,17 @@ async fn reconcile(wf: Arc<QuantumWorkflow>, ctx: Arc<Context>) -> Result<Action
                     if let Some(s) = job.status {
                         if s.succeeded.unwrap_or(0) > 0 {
                             *status = TASK_SUCCEEDED.to_string();
+                            task_messages.remove(task_name);
                             made_change = true;
                         } else if s.failed.unwrap_or(0) > 0 {
                             *status = TASK_FAILED.to_string();
+                            task_messages.remove(task_name);
+                            made_change = true;
+                        } else if let Some(message) =
+                            fetch_progress_message(&pod_api, &job_name).await
+                            && task_messages.get(task_name) != Some(&message)
+                        {
+                            task_messages.insert(task_name.clone(), message);
                             made_change = true;
                         }
                     }
@@ -329,79 +444,57 @@ async fn reconcile(wf: Arc<QuantumWorkflow>, ctx: Arc<Context>) -> Result<Action
         }
     }
 
-    let mut topo = Topo::new(&graph);
-    while let Some(node_idx) = topo.next(&graph) {
-        let task = task_map[node_idx];
+    for task in schedulable_tasks(&order, &current_statuses) {
         let task_name = &task.name;
-        if current_statuses.get(task_name) == Some(&TASK_PENDING.to_string()) {
-            let deps_succeeded = task.depends_on.as_ref().map_or(true, |deps| {
-                deps.iter().all(|dep_name| {
-                    current_statuses.get(dep_name) == Some(&TASK_SUCCEEDED.to_string())
-                })
-            });
-
-            if deps_succeeded {
-                info!("Dependencies met for task '{}', starting job.", task_name);
-                let cm_name = if let QFlowTaskSpec::Quantum {
-                    circuit, params, ..
-                } = &task.spec
-                {
-                    let cm_name = format!("{}-{}-cm", wf.metadata.name.clone().unwrap(), task.name);
-                    match cm_api.get(&cm_name).await {
-                        Ok(_) => {
-                            info!("ConfigMap '{}' already exists, skipping creation.", cm_name);
-                        }
-                        Err(_) => {
-                            let cm = ConfigMap {
-                                metadata: ObjectMeta {
-                                    name: Some(cm_name.clone()),
-                                    owner_references: Some(vec![
-                                        wf.controller_owner_ref(&()).unwrap(),
-                                    ]),
-                                    ..Default::default()
-                                },
-                                data: Some(
-                                    [
-                                        ("circuit.qasm".to_string(), circuit.clone()),
-                                        ("params.json".to_string(), params.clone()),
-                                    ]
-                                    .into(),
-                                ),
-                                ..Default::default()
-                            };
-                            cm_api.create(&PostParams::default(), &cm).await?;
-                        }
-                    }
-                    Some(cm_name)
-                } else {
-                    None
-                };
-
-                // This single function call now handles all task types
-                let job_name = format!("{}-{}", wf.metadata.name.clone().unwrap(), task_name);
-                match job_api.get(&job_name).await {
-                    Ok(_) => {
-                        info!("Job '{}' already exists, skipping creation.", job_name);
-                    }
-                    Err(_) => {
-                        let job = create_job_for_task(&wf, task, cm_name)?;
-                        job_api.create(&PostParams::default(), &job).await?;
-                    }
+        info!("Dependencies met for task '{}', starting job.", task_name);
+        let cm_name = if let QFlowTaskSpec::Quantum {
+            circuit, params, ..
+        } = &task.spec
+        {
+            let cm_name = format!("{}-{}-cm", wf.metadata.name.clone().unwrap(), task.name);
+            match cm_api.get(&cm_name).await {
+                Ok(_) => {
+                    info!("ConfigMap '{}' already exists, skipping creation.", cm_name);
+                }
+                Err(_) => {
+                    let cm = ConfigMap {
+                        metadata: ObjectMeta {
+                            name: Some(cm_name.clone()),
+                            owner_references: Some(vec![wf.controller_owner_ref(&()).unwrap()]),
+                            ..Default::default()
+                        },
+                        data: Some(
+                            [
+                                ("circuit.qasm".to_string(), circuit.clone()),
+                                ("params.json".to_string(), params.clone()),
+                            ]
+                            .into(),
+                        ),
+                        ..Default::default()
+                    };
+                    cm_api.create(&PostParams::default(), &cm).await?;
                 }
-                current_statuses.insert(task_name.clone(), TASK_RUNNING.to_string());
-                made_change = true;
             }
+            Some(cm_name)
         } else {
-            // print all current statuses
-            for (task_name, current_status) in &current_statuses {
-                println!("Task '{}' depends on '{}'", task_name, current_status);
+            None
+        };
+
+        // This single function call now handles all task types
+        let job_name = format!("{}-{}", wf.metadata.name.clone().unwrap(), task_name);
+        match job_api.get(&job_name).await {
+            Ok(_) => {
+                info!("Job '{}' already exists, skipping creation.", job_name);
+            }
+            Err(_) => {
+                let job = create_job_for_task(&wf, task, cm_name, ctx.image_registry.as_deref())?;
+                job_api.create(&PostParams::default(), &job).await?;
             }
-            println!(
-                "task: '{}', status: '{:?}'",
-                task_name,
-                current_statuses.get(task_name)
-            );
         }
+        current_statuses.insert(task_name.clone(), TASK_RUNNING.to_string());
+        // A task that runs again starts without the progress of its previous run.
+        task_messages.remove(task_name);
+        made_change = true;
     }
 
     let final_phase = if current_statuses.values().any(|s| s == TASK_FAILED) {
@@ -416,6 +509,7 @@ async fn reconcile(wf: Arc<QuantumWorkflow>, ctx: Arc<Context>) -> Result<Action
         let new_status = QuantumWorkflowStatus {
             phase: final_phase,
             task_statuses: Some(current_statuses),
+            task_messages: (!task_messages.is_empty()).then_some(task_messages),
         };
         update_status(&wf_api, &wf.metadata.name.clone().unwrap(), new_status).await?;
     }
@@ -425,6 +519,7 @@ async fn reconcile(wf: Arc<QuantumWorkflow>, ctx: Arc<Context>) -> Result<Action
 
 struct Context {
     client: Client,
+    image_registry: Option<String>,
 }
 
 fn on_error(wf: Arc<QuantumWorkflow>, error: &Error, _ctx: Arc<Context>) -> Action {
@@ -444,6 +539,7 @@ async fn main() -> anyhow::Result<()> {
     let client = Client::try_default().await?;
     let context = Arc::new(Context {
         client: client.clone(),
+        image_registry: std::env::var(IMAGE_REGISTRY_ENV).ok(),
     });
 
     let workflows = Api::<QuantumWorkflow>::all(client);
@@ -462,3 +558,295 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qflow_types::{QcbmOptimizerSpec, QcbmTaskSpec, QuantumWorkflowSpec};
+
+    fn task(name: &str, depends_on: &[&str]) -> QFlowTask {
+        QFlowTask {
+            name: name.to_string(),
+            depends_on: (!depends_on.is_empty())
+                .then(|| depends_on.iter().map(|d| d.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    /// An empty workflow with the uid its Jobs' owner references need.
+    fn demo_workflow() -> QuantumWorkflow {
+        let mut wf = QuantumWorkflow::new(
+            "demo",
+            QuantumWorkflowSpec {
+                volume: None,
+                tasks: vec![],
+            },
+        );
+        wf.metadata.uid = Some("1234".to_string());
+        wf
+    }
+
+    /// The container of the Job created for `task` in [`demo_workflow`].
+    fn container_for(task: &QFlowTask) -> Container {
+        create_job_for_task(&demo_workflow(), task, None, None)
+            .unwrap()
+            .spec
+            .unwrap()
+            .template
+            .spec
+            .unwrap()
+            .containers[0]
+            .clone()
+    }
+
+    #[test]
+    fn test_qcbm_job_uses_default_optimizer() {
+        let qcbm = QFlowTask {
+            spec: QFlowTaskSpec::Qcbm(QcbmTaskSpec {
+                image: "vqa-runner:latest".to_string(),
+                ansatz: "hardware_efficient".to_string(),
+                training_data: vec!["00".to_string(), "11".to_string()],
+                optimizer: None,
+            }),
+            ..task("train", &[])
+        };
+
+        let args = container_for(&qcbm).args.unwrap();
+        let defaults = QcbmOptimizerSpec::default();
+        let arg_after = |flag: &str| {
+            let pos = args.iter().position(|a| a == flag).unwrap();
+            args[pos + 1].clone()
+        };
+        assert_eq!(arg_after("--epochs"), defaults.epochs.to_string());
+        assert_eq!(
+            arg_after("--learning-rate"),
+            defaults.learning_rate.to_string()
+        );
+        assert_eq!(arg_after("--epochs"), "100");
+        assert_eq!(arg_after("--learning-rate"), "0.01");
+        assert!(!args.contains(&"--initial-params".to_string()));
+    }
+
+    #[test]
+    fn test_image_registry_prefix() {
+        let classical = |image: &str| QFlowTask {
+            spec: QFlowTaskSpec::Classical {
+                image: image.to_string(),
+                command: None,
+                args: None,
+            },
+            ..task("prep", &[])
+        };
+        let wf = demo_workflow();
+        let image_for = |task: &QFlowTask, registry: Option<&str>| {
+            create_job_for_task(&wf, task, None, registry)
+                .unwrap()
+                .spec
+                .unwrap()
+                .template
+                .spec
+                .unwrap()
+                .containers[0]
+                .image
+                .clone()
+                .unwrap()
+        };
+
+        let registry = Some("myregistry.io");
+        assert_eq!(
+            image_for(&classical("qsim:latest"), registry),
+            "myregistry.io/qsim:latest"
+        );
+        assert_eq!(
+            image_for(&classical("ghcr.io/org/qsim:1"), registry),
+            "ghcr.io/org/qsim:1"
+        );
+        assert_eq!(
+            image_for(&classical("localhost:5000/qsim"), registry),
+            "localhost:5000/qsim"
+        );
+        assert_eq!(image_for(&classical("qsim:latest"), None), "qsim:latest");
+        assert_eq!(
+            qualify_image(DEFAULT_QSIM_IMAGE, Some("myregistry.io/team/")),
+            "myregistry.io/team/qsim:latest"
+        );
+    }
+
+    #[test]
+    fn test_job_labels_name_workflow_and_task() {
+        let job = create_job_for_task(&demo_workflow(), &task("prep", &[]), None, None).unwrap();
+        let labels = job.metadata.labels.unwrap();
+        assert_eq!(labels[QFLOW_TASK_NAME_LABEL], "prep");
+        assert_eq!(labels[QFLOW_WORKFLOW_NAME_LABEL], "demo");
+    }
+
+    #[test]
+    fn test_progress_message_formatting() {
+        let progress = TaskProgress {
+            epoch: 30,
+            total_epochs: 100,
+        };
+        let logs = format!(
+            "Starting training with MMD loss...\nEpoch 30/100 - Loss (MMD): 0.012000\n{}\n",
+            progress.to_log_line()
+        );
+        assert_eq!(progress_message(&logs).as_deref(), Some("epoch 30/100"));
+        assert_eq!(progress_message("Training finished."), None);
+    }
+
+    #[test]
+    fn test_task_retries_set_backoff_limit() {
+        let wf = demo_workflow();
+        let backoff_limit = |retries: Option<i32>| {
+            let task = QFlowTask {
+                retries,
+                spec: QFlowTaskSpec::Classical {
+                    image: "prep:latest".to_string(),
+                    command: None,
+                    args: None,
+                },
+                ..task("prep", &[])
+            };
+            create_job_for_task(&wf, &task, None, None)
+                .unwrap()
+                .spec
+                .unwrap()
+                .backoff_limit
+        };
+
+        assert_eq!(backoff_limit(Some(0)), Some(0));
+        assert_eq!(backoff_limit(Some(10)), Some(10));
+        assert_eq!(backoff_limit(None), Some(DEFAULT_TASK_RETRIES));
+    }
+
+    #[test]
+    fn test_task_resources_set_container_resources() {
+        let task = QFlowTask {
+            resources: Some(qflow_types::ResourceRequirements {
+                requests: Some(qflow_types::ResourceQuantities {
+                    cpu: None,
+                    memory: Some("2Gi".to_string()),
+                }),
+                limits: None,
+            }),
+            spec: QFlowTaskSpec::Classical {
+                image: "sim:latest".to_string(),
+                command: None,
+                args: None,
+            },
+            ..task("simulate", &[])
+        };
+
+        let resources = container_for(&task).resources.unwrap();
+        let requests = resources.requests.as_ref().unwrap();
+        assert_eq!(requests.get("memory"), Some(&Quantity("2Gi".to_string())));
+        assert!(!requests.contains_key("cpu"));
+        assert_eq!(resources.limits, None);
+
+        let plain = QFlowTask {
+            resources: None,
+            ..task.clone()
+        };
+        assert_eq!(container_for(&plain).resources, None);
+    }
+
+    #[test]
+    fn test_classical_task_command_and_args() {
+        let classical = |command: Option<Vec<String>>, args: Option<Vec<String>>| QFlowTask {
+            spec: QFlowTaskSpec::Classical {
+                image: "preprocess:latest".to_string(),
+                command,
+                args,
+            },
+            ..task("preprocess", &[])
+        };
+
+        let custom = container_for(&classical(
+            Some(vec!["python".to_string(), "prep.py".to_string()]),
+            Some(vec!["--rows".to_string(), "100".to_string()]),
+        ));
+        assert_eq!(
+            custom.command,
+            Some(vec!["python".to_string(), "prep.py".to_string()])
+        );
+        assert_eq!(
+            custom.args,
+            Some(vec!["--rows".to_string(), "100".to_string()])
+        );
+
+        let default = container_for(&classical(None, None));
+        assert_eq!(default.command, Some(vec!["/qsim".to_string()]));
+        assert_eq!(default.args, None);
+    }
+
+    #[test]
+    fn test_image_pull_policy() {
+        let pull_policy = |image_pull_policy: Option<&str>| {
+            let task = QFlowTask {
+                image_pull_policy: image_pull_policy.map(str::to_string),
+                spec: QFlowTaskSpec::Classical {
+                    image: "prep:latest".to_string(),
+                    command: None,
+                    args: None,
+                },
+                ..task("prep", &[])
+            };
+            container_for(&task).image_pull_policy
+        };
+
+        assert_eq!(pull_policy(None).as_deref(), Some("IfNotPresent"));
+        assert_eq!(pull_policy(Some("Never")).as_deref(), Some("Never"));
+    }
+
+    #[test]
+    fn test_scheduling_is_deterministic() {
+        // A diamond: prepare -> {left, right} -> combine, plus an independent task.
+        let tasks = vec![
+            task("combine", &["left", "right"]),
+            task("right", &["prepare"]),
+            task("left", &["prepare"]),
+            task("prepare", &[]),
+            task("standalone", &[]),
+        ];
+        let statuses: BTreeMap<String, String> = [
+            ("prepare", TASK_SUCCEEDED),
+            ("left", TASK_PENDING),
+            ("right", TASK_PENDING),
+            ("combine", TASK_PENDING),
+            ("standalone", TASK_RUNNING),
+        ]
+        .into_iter()
+        .map(|(name, status)| (name.to_string(), status.to_string()))
+        .collect();
+
+        let spec = QuantumWorkflowSpec {
+            volume: None,
+            tasks,
+        };
+        let decide = || {
+            let order = spec.topological_order().unwrap();
+            schedulable_tasks(&order, &statuses)
+                .into_iter()
+                .map(|t| t.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let first = decide();
+        assert_eq!(first, decide());
+        // Ready tasks come in declaration order.
+        assert_eq!(first, vec!["right", "left"]);
+    }
+
+    #[test]
+    fn test_invalid_workflows_are_rejected() {
+        for tasks in [
+            vec![task("a", &["ghost"])],
+            vec![task("a", &["b"]), task("b", &["a"])],
+        ] {
+            let spec = QuantumWorkflowSpec {
+                volume: None,
+                tasks,
+            };
+            assert!(spec.topological_order().is_err());
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use kube::CustomResource;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(CustomResource, Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[kube(
@@ -17,17 +17,98 @@ pub struct QuantumWorkflowSpec {
     pub tasks: Vec<QFlowTask>,
 }
 
+impl QuantumWorkflowSpec {
+    /// Returns the tasks in execution order, each after all of its dependencies. At every
+    /// step the earliest-declared task whose dependencies are all placed comes next, so the
+    /// order depends only on the spec. Fails on duplicate task names, dependencies on tasks
+    /// that don't exist, and dependency cycles.
+    pub fn topological_order(&self) -> Result<Vec<&QFlowTask>, String> {
+        let mut names = HashSet::new();
+        for task in &self.tasks {
+            if !names.insert(task.name.as_str()) {
+                return Err(format!("Duplicate task name '{}'", task.name));
+            }
+        }
+        for task in &self.tasks {
+            for dep_name in task.depends_on.iter().flatten() {
+                if !names.contains(dep_name.as_str()) {
+                    return Err(format!(
+                        "Task '{}' depends on non-existent task '{}'",
+                        task.name, dep_name
+                    ));
+                }
+            }
+        }
+
+        let mut done: HashSet<&str> = HashSet::new();
+        let mut order = Vec::with_capacity(self.tasks.len());
+        while order.len() < self.tasks.len() {
+            let next = self.tasks.iter().find(|task| {
+                !done.contains(task.name.as_str())
+                    && task
+                        .depends_on
+                        .iter()
+                        .flatten()
+                        .all(|dep_name| done.contains(dep_name.as_str()))
+            });
+            let Some(task) = next else {
+                let stuck: Vec<&str> = self
+                    .tasks
+                    .iter()
+                    .map(|t| t.name.as_str())
+                    .filter(|name| !done.contains(name))
+                    .collect();
+                return Err(format!(
+                    "Workflow has a cycle; cannot order tasks: {}",
+                    stuck.join(", ")
+                ));
+            };
+            done.insert(&task.name);
+            order.push(task);
+        }
+        Ok(order)
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
 pub struct VolumeSpec {
     pub size: String,
 }
 
+/// CPU and memory requested by, and capped for, a task's container.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ResourceRequirements {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests: Option<ResourceQuantities>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<ResourceQuantities>,
+}
+
+/// Kubernetes quantities, e.g. `cpu: "500m"` or `memory: "2Gi"`.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ResourceQuantities {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct QFlowTask {
     pub name: String,
     #[serde(rename = "dependsOn")]
     pub depends_on: Option<Vec<String>>,
+    /// How many times a failed task is retried, i.e. its Job's `backoffLimit`. The operator
+    /// uses 4 when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceRequirements>,
+    /// `Always`, `IfNotPresent` or `Never`. The operator uses `IfNotPresent` when unset;
+    /// set `Never` to run images loaded straight into a local cluster such as kind.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_pull_policy: Option<String>,
     #[serde(flatten)]
     pub spec: QFlowTaskSpec,
 }
@@ -37,6 +118,11 @@ pub struct QFlowTask {
 pub enum QFlowTaskSpec {
     Classical {
         image: String,
+        /// Overrides the image's entrypoint; tasks without one run `/qsim`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        command: Option<Vec<String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        args: Option<Vec<String>>,
     },
     Quantum {
         image: String,
@@ -50,6 +136,8 @@ impl Default for QFlowTaskSpec {
     fn default() -> Self {
         QFlowTaskSpec::Classical {
             image: String::new(),
+            command: None,
+            args: None,
         }
     }
 }
@@ -59,6 +147,8 @@ impl Default for QFlowTaskSpec {
 pub struct QuantumWorkflowStatus {
     pub phase: Option<String>,
     pub task_statuses: Option<BTreeMap<String, String>>,
+    /// Progress of running tasks, e.g. `epoch 30/100`, keyed by task name.
+    pub task_messages: Option<BTreeMap<String, String>>,
 }
 
 #[derive(Serialize, Debug)]
@@ -198,9 +288,162 @@ pub struct QcbmOptimizerSpec {
     pub initial_params: Option<String>,
 }
 
+/// The optimizer used when a QCBM task omits one: Adam for 100 epochs at a learning
+/// rate of 0.01, with the runner's own initial parameters.
+impl Default for QcbmOptimizerSpec {
+    fn default() -> Self {
+        QcbmOptimizerSpec {
+            name: "Adam".to_string(),
+            epochs: default_epochs(),
+            learning_rate: default_learning_rate(),
+            initial_params: None,
+        }
+    }
+}
+
 fn default_epochs() -> i32 {
     100
 }
 fn default_learning_rate() -> f64 {
     0.01
 }
+
+/// The result document emitted by the QCBM runner as the last line of its output.
+/// The backend deserializes task logs into this when serving QCBM task results.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QcbmResult {
+    /// The trained ansatz parameters.
+    pub final_params: Vec<f64>,
+    /// The model's probability distribution over bitstrings for `final_params`.
+    pub distribution: BTreeMap<String, f64>,
+    /// The MMD loss recorded after each training epoch.
+    pub loss_history: Vec<f64>,
+}
+
+/// Marks a progress line in a task container's output.
+pub const PROGRESS_PREFIX: &str = "QFLOW_PROGRESS ";
+
+/// Intermediate progress reported by a long-running task. The container prints it as a
+/// `PROGRESS_PREFIX` line and the operator surfaces the latest one in the workflow status.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TaskProgress {
+    pub epoch: usize,
+    pub total_epochs: usize,
+}
+
+impl TaskProgress {
+    pub fn to_log_line(&self) -> String {
+        format!(
+            "{}{}",
+            PROGRESS_PREFIX,
+            serde_json::to_string(self).expect("progress serializes")
+        )
+    }
+
+    /// The most recent well-formed progress line in `logs`, if any.
+    pub fn parse_last(logs: &str) -> Option<TaskProgress> {
+        logs.lines()
+            .rev()
+            .filter_map(|line| line.trim().strip_prefix(PROGRESS_PREFIX))
+            .find_map(|json| serde_json::from_str(json).ok())
+    }
+}
+
+impl std::fmt::Display for TaskProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "epoch {}/{}", self.epoch, self.total_epochs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workflow(tasks: &[(&str, &[&str])]) -> QuantumWorkflowSpec {
+        QuantumWorkflowSpec {
+            volume: None,
+            tasks: tasks
+                .iter()
+                .map(|(name, deps)| QFlowTask {
+                    name: name.to_string(),
+                    depends_on: (!deps.is_empty())
+                        .then(|| deps.iter().map(|d| d.to_string()).collect()),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let spec = workflow(&[
+            ("combine", &["left", "right"]),
+            ("right", &["prepare"]),
+            ("left", &["prepare"]),
+            ("prepare", &[]),
+            ("standalone", &[]),
+        ]);
+        let order: Vec<&str> = spec
+            .topological_order()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(order, ["prepare", "right", "left", "combine", "standalone"]);
+
+        let missing = workflow(&[("a", &["ghost"])]);
+        assert_eq!(
+            missing.topological_order().unwrap_err(),
+            "Task 'a' depends on non-existent task 'ghost'"
+        );
+
+        let cyclic = workflow(&[("a", &["b"]), ("b", &["a"]), ("c", &[])]);
+        assert_eq!(
+            cyclic.topological_order().unwrap_err(),
+            "Workflow has a cycle; cannot order tasks: a, b"
+        );
+
+        let duplicate = workflow(&[("a", &[]), ("a", &[])]);
+        assert!(duplicate.topological_order().is_err());
+    }
+
+    #[test]
+    fn test_qcbm_result_round_trip() {
+        let result = QcbmResult {
+            final_params: vec![0.5, -1.25],
+            distribution: [("00".to_string(), 0.75), ("11".to_string(), 0.25)].into(),
+            loss_history: vec![0.4, 0.2, 0.1],
+        };
+
+        let json = serde_json::to_string(&result).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value.get("final_params").is_some());
+        assert!(value.get("distribution").is_some());
+        assert!(value.get("loss_history").is_some());
+
+        let parsed: QcbmResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, result);
+    }
+
+    #[test]
+    fn test_task_progress_message_and_log_round_trip() {
+        let progress = TaskProgress {
+            epoch: 30,
+            total_epochs: 100,
+        };
+        assert_eq!(progress.to_string(), "epoch 30/100");
+
+        let logs = format!(
+            "Starting training with MMD loss...\n{}\nEpoch 10/100 - Loss (MMD): 0.1\n{}\n{}garbage\n",
+            TaskProgress {
+                epoch: 10,
+                total_epochs: 100
+            }
+            .to_log_line(),
+            progress.to_log_line(),
+            PROGRESS_PREFIX
+        );
+        assert_eq!(TaskProgress::parse_last(&logs), Some(progress));
+        assert_eq!(TaskProgress::parse_last("no progress here"), None);
+    }
+}
@@ -37,7 +37,11 @@ fn compile(ast: AstWorkflow) -> Result<QuantumWorkflow> {
         .into_iter()
         .map(|task| -> Result<QFlowTask> {
             let spec = match task.spec {
-                AstTaskSpec::Classical { image } => QFlowTaskSpec::Classical { image },
+                AstTaskSpec::Classical { image } => QFlowTaskSpec::Classical {
+                    image,
+                    command: None,
+                    args: None,
+                },
                 AstTaskSpec::Quantum {
                     image,
                     circuit_from,
@@ -60,6 +64,9 @@ fn compile(ast: AstWorkflow) -> Result<QuantumWorkflow> {
                 name: task.name,
                 spec,
                 depends_on: task.depends_on,
+                retries: None,
+                resources: None,
+                image_pull_policy: None,
             })
         })
         .collect::<Result<Vec<_>>>()?;
@@ -185,12 +192,112 @@ fn workflow_parser() -> impl Parser<char, AstWorkflow, Error = Simple<char>> {
     workflow.padded().then_ignore(end())
 }
 
-pub fn compile_qflow_file<P: AsRef<std::path::Path>>(path: P) -> Result<String> {
+/// Escapes backslashes and double quotes so `s` can sit inside a quoted DOT string. Task
+/// names such as `my-task` are only valid DOT identifiers when quoted.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Renders the task dependency graph as Graphviz DOT: one node per task, labelled with
+/// its type, and an edge from each dependency to the task that depends on it.
+fn workflow_to_dot(ast: &AstWorkflow) -> String {
+    let mut dot = format!("digraph \"{}\" {{\n", dot_escape(&ast.name));
+    for task in &ast.tasks {
+        let kind = match task.spec {
+            AstTaskSpec::Classical { .. } => "classical",
+            AstTaskSpec::Quantum { .. } => "quantum",
+        };
+        let name = dot_escape(&task.name);
+        dot.push_str(&format!(
+            "    \"{}\" [label=\"{}\\n({})\"];\n",
+            name, name, kind
+        ));
+    }
+    for task in &ast.tasks {
+        for dependency in task.depends_on.iter().flatten() {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\";\n",
+                dot_escape(dependency),
+                dot_escape(&task.name)
+            ));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn parse_qflow_file<P: AsRef<std::path::Path>>(path: P) -> Result<AstWorkflow> {
     let src = std::fs::read_to_string(&path)?;
-    let ast = workflow_parser()
+    workflow_parser()
         .parse(src)
-        .map_err(|e| anyhow!("Parser errors: {:?}", e))?;
+        .map_err(|e| anyhow!("Parser errors: {:?}", e))
+}
+
+pub fn compile_qflow_file<P: AsRef<std::path::Path>>(path: P) -> Result<String> {
+    let ast = parse_qflow_file(path)?;
     let k8s_resource = compile(ast)?;
     let yaml_output = serde_yaml::to_string(&k8s_resource)?;
     Ok(yaml_output)
 }
+
+/// Like `compile_qflow_file`, but emits the workflow DAG as Graphviz DOT instead of YAML.
+/// Circuit and params files are not read.
+pub fn compile_qflow_file_to_dot<P: AsRef<std::path::Path>>(path: P) -> Result<String> {
+    Ok(workflow_to_dot(&parse_qflow_file(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_task_workflow_to_dot() {
+        let src = r#"
+            workflow pipeline {
+                task prepare {
+                    image: "alpine:latest"
+                }
+                task analyse {
+                    image: "python:3",
+                    depends_on: ["prepare"]
+                }
+            }
+        "#;
+        let ast = workflow_parser().parse(src).unwrap();
+        let dot = workflow_to_dot(&ast);
+
+        assert!(dot.starts_with("digraph \"pipeline\" {"));
+        assert!(dot.contains("\"prepare\" [label=\"prepare\\n(classical)\"];"));
+        assert!(dot.contains("\"analyse\" [label=\"analyse\\n(classical)\"];"));
+        assert!(dot.contains("\"prepare\" -> \"analyse\";"));
+    }
+
+    #[test]
+    fn test_dot_escapes_quotes_in_names() {
+        let ast = AstWorkflow {
+            name: "my-flow".to_string(),
+            tasks: vec![
+                AstTask {
+                    name: "load-data".to_string(),
+                    depends_on: None,
+                    spec: AstTaskSpec::Classical {
+                        image: "alpine:latest".to_string(),
+                    },
+                },
+                AstTask {
+                    name: "say \"hi\"".to_string(),
+                    depends_on: Some(vec!["load-data".to_string()]),
+                    spec: AstTaskSpec::Classical {
+                        image: "alpine:latest".to_string(),
+                    },
+                },
+            ],
+        };
+        let dot = workflow_to_dot(&ast);
+
+        assert!(dot.starts_with("digraph \"my-flow\" {"));
+        assert!(dot.contains("\"load-data\" [label=\"load-data\\n(classical)\"];"));
+        assert!(dot.contains("\"say \\\"hi\\\"\" [label=\"say \\\"hi\\\"\\n(classical)\"];"));
+        assert!(dot.contains("\"load-data\" -> \"say \\\"hi\\\"\";"));
+    }
+}
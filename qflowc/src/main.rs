@@ -10,6 +10,10 @@ use qflow_types::{QFlowTask, QFlowTaskSpec, QuantumWorkflow, QuantumWorkflowSpec
 struct Args {
     #[arg(short, long)]
     file: Option<String>,
+
+    /// Print the task dependency graph as Graphviz DOT instead of the workflow YAML.
+    #[arg(long)]
+    dot: bool,
 }
 
 fn main() -> Result<()> {
@@ -17,6 +21,10 @@ fn main() -> Result<()> {
     let path = args
         .file
         .unwrap_or_else(|| "./qflow-operator/tests/dag-test.qflow".to_string());
+    if args.dot {
+        print!("{}", qflowc::compile_qflow_file_to_dot(&path)?);
+        return Ok(());
+    }
     let yaml_output = qflowc::compile_qflow_file(&path)?;
     println!("---\n{}", yaml_output);
     Ok(())
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 // src/api.rs
 use crate::StateVector;
-use crate::circuit::Circuit;
+use crate::circuit::{Circuit, ParameterizedCircuit};
 use crate::statevector_backend::StatevectorSimulator;
 
 /// A lightweight error enum so callers don't rely on your internals.
@@ -11,6 +11,20 @@ pub enum SimError {
     Qasm(String),
     #[error("Invalid qubit index: {0}")]
     Qubit(usize),
+    #[error("Invalid moment index: {0}")]
+    Moment(usize),
+    #[error("Invalid gate index: {0}")]
+    GateIndex(usize),
+    #[error("Invalid gate matrix: {0}")]
+    Matrix(String),
+    #[error("Invalid statevector: {0}")]
+    State(String),
+    #[error("Cannot simulate {requested} qubits; the limit is {max}")]
+    TooManyQubits { requested: usize, max: usize },
+    #[error("Expected {expected} parameters, got {got}")]
+    ParamCount { expected: usize, got: usize },
+    #[error("Gate has no inverse: {0}")]
+    NotInvertible(String),
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -27,6 +41,10 @@ pub enum Pauli {
 pub trait SimulatorApi {
     fn reset(&mut self, num_qubits: usize);
     fn run(&mut self, circuit: &Circuit) -> Result<(), SimError>;
+
+    /// Applies `circuit` on top of the current state without resetting first,
+    /// e.g. to append measurement-basis rotations to a prepared ansatz.
+    fn apply_circuit_no_reset(&mut self, circuit: &Circuit) -> Result<(), SimError>;
     fn statevector(&self) -> &StateVector;
 
     /// Measure a single qubit in Z; collapses the state.
@@ -39,6 +57,34 @@ pub trait SimulatorApi {
     /// Sample computational-basis shots without permanently destroying
     /// the original state (implementation can clone internally).
     fn sample(&self, shots: u32) -> Result<std::collections::HashMap<String, u32>, SimError>;
+
+    /// Gradient of ⟨P⟩ with respect to each circuit parameter via the ±π/2
+    /// parameter-shift rule. Assumes each parameter feeds a single Pauli
+    /// rotation; backends may override with something cheaper (e.g. adjoint).
+    /// Leaves the simulator holding the last shifted state.
+    fn expectation_gradient(
+        &mut self,
+        circuit: &ParameterizedCircuit,
+        ops: &[(Pauli, usize)],
+        params: &[f64],
+    ) -> Result<Vec<f64>, SimError> {
+        let shift = std::f64::consts::FRAC_PI_2;
+        let mut shifted = params.to_vec();
+        let mut grad = Vec::with_capacity(params.len());
+        for i in 0..params.len() {
+            shifted[i] = params[i] + shift;
+            self.run(&circuit.bind(&shifted)?)?;
+            let plus = self.expectation(ops)?;
+
+            shifted[i] = params[i] - shift;
+            self.run(&circuit.bind(&shifted)?)?;
+            let minus = self.expectation(ops)?;
+
+            shifted[i] = params[i];
+            grad.push(0.5 * (plus - minus));
+        }
+        Ok(grad)
+    }
 }
 
 // Small helper: absolute diff
@@ -165,3 +211,146 @@ fn can_reuse_simulator_with_reset() {
     let ex = sim.expectation(&[(Pauli::X, 0)]).unwrap();
     assert!(approx_eq(ex, 1.0, 1e-9), "⟨X⟩ was {}", ex);
 }
+
+#[test]
+fn apply_circuit_no_reset_builds_on_current_state() {
+    let plus = Circuit::from_qasm("qreg q[1];\nh q[0];").unwrap();
+    let flip = Circuit::from_qasm("qreg q[1];\nz q[0];").unwrap();
+
+    let mut sim = StatevectorSimulator::new(1);
+    sim.run(&plus).unwrap();
+    sim.apply_circuit_no_reset(&flip).unwrap();
+
+    // Z|+> = |->, whereas run() would have started again from |0>.
+    let h = std::f64::consts::FRAC_1_SQRT_2;
+    let amps = &sim.statevector().amplitudes;
+    assert!(approx_eq(amps[0].re, h, 1e-9));
+    assert!(approx_eq(amps[1].re, -h, 1e-9));
+    let ex = sim.expectation(&[(Pauli::X, 0)]).unwrap();
+    assert!(approx_eq(ex, -1.0, 1e-9), "⟨X⟩ was {}", ex);
+
+    let too_wide = Circuit::from_qasm("qreg q[2];\nh q[1];").unwrap();
+    assert!(sim.apply_circuit_no_reset(&too_wide).is_err());
+
+    let mut out_of_range = Circuit::with_qubits(1);
+    out_of_range.add_gate(crate::Gate::h(3));
+    assert!(matches!(
+        sim.apply_circuit_no_reset(&out_of_range),
+        Err(SimError::Qubit(3))
+    ));
+}
+
+#[test]
+fn oversized_registers_are_rejected() {
+    assert!(matches!(
+        StatevectorSimulator::try_new(40),
+        Err(SimError::TooManyQubits { requested: 40, .. })
+    ));
+
+    let mut sim = StatevectorSimulator::with_max_qubits(1, 2).unwrap();
+    let wide = Circuit::with_qubits(3);
+    assert!(matches!(
+        sim.run(&wide),
+        Err(SimError::TooManyQubits {
+            requested: 3,
+            max: 2
+        })
+    ));
+}
+
+#[test]
+fn expectation_gradient_matches_analytic_ry() {
+    // <Z> after RY(θ)|0> is cos θ, so d<Z>/dθ = -sin θ
+    let circ = ParameterizedCircuit::new(1, 1, |p| {
+        let mut c = Circuit::with_qubits(1);
+        c.add_gate(crate::Gate::RY {
+            qubit: 0,
+            theta: p[0],
+        });
+        c
+    });
+    let mut sim = StatevectorSimulator::new(1);
+
+    for theta in [0.0, 0.3, 1.2, 2.5] {
+        let grad = sim
+            .expectation_gradient(&circ, &[(Pauli::Z, 0)], &[theta])
+            .unwrap();
+        assert_eq!(grad.len(), 1);
+        assert!(
+            approx_eq(grad[0], -theta.sin(), 1e-9),
+            "gradient at θ={} was {}",
+            theta,
+            grad[0]
+        );
+    }
+
+    assert!(matches!(
+        sim.expectation_gradient(&circ, &[(Pauli::Z, 0)], &[0.1, 0.2]),
+        Err(SimError::ParamCount {
+            expected: 1,
+            got: 2
+        })
+    ));
+}
+
+#[test]
+fn pruning_keeps_basis_states_sparse() {
+    // RX(π) leaves cos(π/2) ≈ 6e-17 behind on |0>.
+    let mut c = Circuit::with_qubits(2);
+    c.add_gate(crate::Gate::rx(0, std::f64::consts::PI));
+    c.add_gate(crate::Gate::x(1));
+    let non_zero = |sim: &StatevectorSimulator| {
+        sim.statevector()
+            .amplitudes
+            .iter()
+            .filter(|a| a.norm() > 0.0)
+            .count()
+    };
+
+    let mut dense = StatevectorSimulator::new(2);
+    dense.run(&c).unwrap();
+    assert_eq!(non_zero(&dense), 2);
+
+    let mut pruned = StatevectorSimulator::new(2).with_prune_threshold(1e-12);
+    pruned.run(&c).unwrap();
+    assert_eq!(non_zero(&pruned), 1);
+    assert!(pruned.statevector().amplitudes[3].norm() > 0.0);
+    assert!(pruned.statevector().is_normalized(1e-12));
+}
+
+#[test]
+fn reset_after_x_returns_qubit_to_zero() {
+    let c = Circuit::from_qasm("qreg q[2];\nx q[0];\nx q[1];\nbarrier q;\nreset q[0];\n").unwrap();
+    assert_eq!(
+        c.gates_flat(),
+        vec![
+            &crate::Gate::x(0),
+            &crate::Gate::x(1),
+            &crate::Gate::Barrier { qubits: vec![0, 1] },
+            &crate::Gate::reset(0),
+        ]
+    );
+
+    let mut sim = StatevectorSimulator::new(2);
+    sim.run(&c).unwrap();
+    // q0 is back in |0⟩ while q1 stays |1⟩, i.e. |10⟩ at index 2.
+    assert!((sim.statevector().amplitudes[2].norm() - 1.0).abs() < 1e-12);
+
+    let mut quantum = crate::QuantumSimulator::new(2);
+    quantum.apply_circuit(&c).unwrap();
+    assert!((quantum.get_probability(2) - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn seeded_simulators_sample_identically() {
+    let mut c = Circuit::with_qubits(2);
+    c.add_moment(vec![crate::Gate::h(0), crate::Gate::h(1)]);
+    let histogram = |seed: u64| {
+        let mut sim = StatevectorSimulator::new(2).with_seed(seed);
+        sim.run(&c).unwrap();
+        let counts = sim.sample(1000).unwrap();
+        let outcome = sim.measure(0).unwrap();
+        (counts, outcome, sim.statevector().amplitudes.clone())
+    };
+    assert_eq!(histogram(7), histogram(7));
+}
@@ -1,9 +1,14 @@
+use crate::Gate;
 use crate::api::SimError;
-use crate::{Gate, parse_qasm};
-use serde::Deserialize;
+use crate::parser::{QasmVersion, parse_qasm3, try_parse_qasm};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 
-#[derive(Debug, Clone, Deserialize)]
+/// Circuits compare and hash structurally (same width, same gates in the same moments),
+/// so they can key a cache of simulation results. Angles are compared by bit pattern, with
+/// -0.0 equal to 0.0 and all NaNs equal to each other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Circuit {
     pub num_qubits: usize,
@@ -33,6 +38,62 @@ impl Circuit {
         self.moments.push(gates);
     }
 
+    /// Checks that every qubit a gate acts on, controls included, is below `num_qubits`.
+    /// Returns the first offending index as `SimError::Qubit`.
+    pub fn validate(&self) -> Result<(), SimError> {
+        for gate in self.moments.iter().flatten() {
+            if let Some(&q) = self
+                .gate_qubits(gate)
+                .iter()
+                .find(|&&q| q >= self.num_qubits)
+            {
+                return Err(SimError::Qubit(q));
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the moments of `other` after this circuit's, widening it to
+    /// `other.num_qubits` if that is larger.
+    pub fn append(&mut self, other: &Circuit) {
+        self.num_qubits = self.num_qubits.max(other.num_qubits);
+        self.moments.extend(other.moments.iter().cloned());
+    }
+
+    /// Inserts `gate` as a new moment at `moment_idx`, shifting later moments back.
+    pub fn insert_gate(&mut self, moment_idx: usize, gate: Gate) -> Result<(), SimError> {
+        if moment_idx > self.moments.len() {
+            return Err(SimError::Moment(moment_idx));
+        }
+        self.moments.insert(moment_idx, vec![gate]);
+        Ok(())
+    }
+
+    /// Removes the moment at `idx` and returns its gates.
+    pub fn remove_moment(&mut self, idx: usize) -> Result<Vec<Gate>, SimError> {
+        if idx >= self.moments.len() {
+            return Err(SimError::Moment(idx));
+        }
+        Ok(self.moments.remove(idx))
+    }
+
+    /// Replaces a single gate within a moment and returns the gate it replaced.
+    pub fn replace_gate(
+        &mut self,
+        moment_idx: usize,
+        gate_idx: usize,
+        gate: Gate,
+    ) -> Result<Gate, SimError> {
+        let moment = self
+            .moments
+            .get_mut(moment_idx)
+            .ok_or(SimError::Moment(moment_idx))?;
+        let slot = moment
+            .get_mut(gate_idx)
+            .ok_or(SimError::GateIndex(gate_idx))?;
+        Ok(std::mem::replace(slot, gate))
+    }
+
     pub fn num_moments(&self) -> usize {
         self.moments.len()
     }
@@ -45,12 +106,100 @@ impl Circuit {
         &self.moments
     }
 
+    /// True if the circuit contains no gates. An empty circuit with `num_qubits > 0` is a
+    /// valid identity: it displays as bare wires, exports as just the QASM header and
+    /// register, and simulates to |0…0⟩.
+    pub fn is_empty(&self) -> bool {
+        self.moments.iter().all(|m| m.is_empty())
+    }
+
     pub fn gates_flat(&self) -> Vec<&Gate> {
         self.moments.iter().flat_map(|m| m.iter()).collect()
     }
 
+    /// The length of the critical path: the most gates any chain of qubit-sharing gates
+    /// passes through. Unlike `num_moments`, it doesn't count moments that could run in
+    /// parallel. Gates acting on no qubit (global phases) add no depth, and a barrier adds
+    /// none either but lines up the qubits it spans.
+    pub fn depth(&self) -> usize {
+        let mut frontier: HashMap<usize, usize> = HashMap::new();
+        let mut depth = 0;
+        for gate in self.gates_flat() {
+            let qubits = self.gate_qubits(gate);
+            if qubits.is_empty() {
+                continue;
+            }
+            let start = qubits
+                .iter()
+                .map(|q| frontier.get(q).copied().unwrap_or(0))
+                .max()
+                .unwrap_or(0);
+            let level = if matches!(gate, Gate::Barrier { .. }) {
+                start
+            } else {
+                start + 1
+            };
+            for q in qubits {
+                frontier.insert(q, level);
+            }
+            depth = depth.max(level);
+        }
+        depth
+    }
+
+    /// The total number of gates, measurements and resets included but barriers not.
+    pub fn gate_count(&self) -> usize {
+        self.gates_flat()
+            .into_iter()
+            .filter(|g| !matches!(g, Gate::Barrier { .. }))
+            .count()
+    }
+
+    /// The number of gates acting on exactly two qubits, e.g. CX and CP.
+    pub fn two_qubit_gate_count(&self) -> usize {
+        self.gates_flat()
+            .into_iter()
+            .filter(|g| {
+                !matches!(g, Gate::Measure | Gate::Barrier { .. }) && self.gate_qubits(g).len() == 2
+            })
+            .count()
+    }
+
+    /// Builds a circuit from a gate list, placing each gate in the earliest moment after
+    /// every earlier gate that shares a qubit with it. Gates on disjoint qubits end up side
+    /// by side, while the order of gates on any one qubit is preserved. The register is
+    /// sized from the highest qubit used.
+    pub fn from_gates_packed(gates: Vec<Gate>) -> Circuit {
+        let mut circuit = Circuit::new();
+        circuit.num_qubits = gates
+            .iter()
+            .flat_map(|g| circuit.gate_qubits(g))
+            .max()
+            .map_or(0, |q| q + 1);
+
+        // The first moment each qubit is free in.
+        let mut frontier = vec![0; circuit.num_qubits];
+        for gate in gates {
+            let qubits = circuit.gate_qubits(&gate);
+            let moment = qubits.iter().map(|&q| frontier[q]).max().unwrap_or(0);
+            for q in qubits {
+                frontier[q] = moment + 1;
+            }
+            if moment == circuit.moments.len() {
+                circuit.moments.push(Vec::new());
+            }
+            circuit.moments[moment].push(gate);
+        }
+        circuit
+    }
+
+    /// Parses OpenQASM 2.0 or 3.0, picking the parser from the program's header.
+    /// A gate on a qubit outside its register is an error in either version.
     pub fn from_qasm(src: &str) -> Result<Self, SimError> {
-        let (num_qubits, gates) = parse_qasm(src);
+        let (num_qubits, gates) = match QasmVersion::detect(src) {
+            QasmVersion::V2 => try_parse_qasm(src)?,
+            QasmVersion::V3 => parse_qasm3(src)?,
+        };
         let mut c = Circuit::with_qubits(num_qubits);
         // Put each gate in its own moment by default (keeps ordering simple)
         for g in gates {
@@ -58,7 +207,175 @@ impl Circuit {
         }
         Ok(c)
     }
+
+    /// The adjoint circuit: moments in reverse order, each gate replaced by its inverse,
+    /// so that running `self` then `self.inverse()` is the identity.
+    /// Fails if the circuit contains a measurement.
+    pub fn inverse(&self) -> Result<Circuit, SimError> {
+        let moments = self
+            .moments
+            .iter()
+            .rev()
+            .map(|moment| moment.iter().map(Gate::inverse).collect())
+            .collect::<Result<_, _>>()?;
+        Ok(Circuit {
+            num_qubits: self.num_qubits,
+            moments,
+        })
+    }
+
+    /// The quantum Fourier transform on `num_qubits` qubits, mapping |x⟩ to
+    /// (1/√N) Σ_k e^{2πi·xk/N} |k⟩ with the same little-endian bit order on both sides.
+    /// Built from H and CP gates, with the final bit reversal done by CX swaps.
+    pub fn qft(num_qubits: usize) -> Circuit {
+        let mut circuit = Circuit::with_qubits(num_qubits);
+        for target in (0..num_qubits).rev() {
+            circuit.add_gate(Gate::h(target));
+            for control in (0..target).rev() {
+                let theta = std::f64::consts::PI / (1u64 << (target - control)) as f64;
+                circuit.add_gate(Gate::cp(control, target, theta));
+            }
+        }
+        for q in 0..num_qubits / 2 {
+            let other = num_qubits - 1 - q;
+            circuit.add_gate(Gate::cx(q, other));
+            circuit.add_gate(Gate::cx(other, q));
+            circuit.add_gate(Gate::cx(q, other));
+        }
+        circuit
+    }
+
+    /// The inverse of [`Circuit::qft`]: the same gates in reverse order with negated phases.
+    pub fn inverse_qft(num_qubits: usize) -> Circuit {
+        let mut circuit = Circuit::qft(num_qubits);
+        circuit.moments.reverse();
+        for gate in circuit.moments.iter_mut().flatten() {
+            if let Gate::CP { theta, .. } = gate {
+                *theta = -*theta;
+            }
+        }
+        circuit
+    }
 }
+
+impl std::ops::Add for Circuit {
+    type Output = Circuit;
+
+    fn add(mut self, other: Circuit) -> Circuit {
+        self.num_qubits = self.num_qubits.max(other.num_qubits);
+        self.moments.extend(other.moments);
+        self
+    }
+}
+
+/// Maps a parameter vector to the concrete circuit it describes.
+type CircuitBuilder = Box<dyn Fn(&[f64]) -> Circuit>;
+
+/// A circuit template whose gates depend on a parameter vector, such as a
+/// variational ansatz. `bind` produces the concrete circuit for given values.
+pub struct ParameterizedCircuit {
+    pub num_qubits: usize,
+    pub num_params: usize,
+    builder: CircuitBuilder,
+}
+
+impl ParameterizedCircuit {
+    pub fn new(
+        num_qubits: usize,
+        num_params: usize,
+        builder: impl Fn(&[f64]) -> Circuit + 'static,
+    ) -> Self {
+        Self {
+            num_qubits,
+            num_params,
+            builder: Box::new(builder),
+        }
+    }
+
+    pub fn bind(&self, params: &[f64]) -> Result<Circuit, SimError> {
+        if params.len() != self.num_params {
+            return Err(SimError::ParamCount {
+                expected: self.num_params,
+                got: params.len(),
+            });
+        }
+        let mut circuit = (self.builder)(params);
+        circuit.set_num_qubits(self.num_qubits);
+        Ok(circuit)
+    }
+}
+
+impl Circuit {
+    /// Renders the circuit as a Graphviz DOT digraph. Each gate is a node, and an edge
+    /// labelled `q<i>` joins consecutive gates acting on qubit i. Every qubit line starts
+    /// at a plaintext `q<i>` input node, so idle qubits still appear.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph circuit {\n    rankdir=LR;\n");
+        let mut last_node: Vec<String> = (0..self.num_qubits).map(|q| format!("q{}", q)).collect();
+        for node in &last_node {
+            dot.push_str(&format!("    {} [shape=plaintext];\n", node));
+        }
+
+        for (moment_idx, moment) in self.moments.iter().enumerate() {
+            for (gate_idx, gate) in moment.iter().enumerate() {
+                let node = format!("g{}_{}", moment_idx, gate_idx);
+                dot.push_str(&format!(
+                    "    {} [label=\"{}\", shape=box];\n",
+                    node,
+                    dot_label(gate)
+                ));
+                for qubit in self.gate_qubits(gate) {
+                    if let Some(prev) = last_node.get_mut(qubit) {
+                        dot.push_str(&format!(
+                            "    {} -> {} [label=\"q{}\"];\n",
+                            prev, node, qubit
+                        ));
+                        *prev = node.clone();
+                    }
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Every qubit a gate touches, controls included.
+    fn gate_qubits(&self, gate: &Gate) -> Vec<usize> {
+        match gate {
+            Gate::CX { control, target }
+            | Gate::CNOT { control, target }
+            | Gate::CP {
+                control, target, ..
+            } => vec![*control, *target],
+            Gate::I { qubit } => vec![*qubit],
+            Gate::Measure => (0..self.num_qubits).collect(),
+            _ => gate.target(),
+        }
+    }
+}
+
+fn dot_label(gate: &Gate) -> String {
+    match gate {
+        Gate::I { .. } => "I".to_string(),
+        Gate::H { .. } => "H".to_string(),
+        Gate::X { .. } => "X".to_string(),
+        Gate::Y { .. } => "Y".to_string(),
+        Gate::Z { .. } => "Z".to_string(),
+        Gate::CX { .. } | Gate::CNOT { .. } => "CX".to_string(),
+        Gate::RX { theta, .. } => format!("RX({})", theta),
+        Gate::RY { theta, .. } => format!("RY({})", theta),
+        Gate::RZ { theta, .. } => format!("RZ({})", theta),
+        Gate::CP { theta, .. } => format!("CP({})", theta),
+        Gate::GPhase { theta } => format!("GPhase({})", theta),
+        Gate::Unitary { .. } => "U".to_string(),
+        Gate::Measure => "Measure".to_string(),
+        Gate::MeasureQubit { creg_bit, .. } => format!("M -> c[{}]", creg_bit),
+        Gate::Barrier { .. } => "Barrier".to_string(),
+        Gate::Reset { .. } => "Reset".to_string(),
+    }
+}
+
 impl fmt::Display for Circuit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.num_qubits == 0 {
@@ -66,9 +383,11 @@ impl fmt::Display for Circuit {
         }
 
         // 1. Create a grid to place gate symbols.
-        // The grid dimensions are num_qubits x num_moments.
+        // The grid dimensions are num_qubits x num_moments, with at least one column so
+        // that a circuit without gates still draws its wires.
         let num_moments = self.moments.len();
-        let mut grid = vec![vec![String::from("───"); num_moments]; self.num_qubits];
+        let num_columns = num_moments.max(1);
+        let mut grid = vec![vec![String::from("───"); num_columns]; self.num_qubits];
 
         // 2. Populate the grid with gate representations.
         for (moment_idx, moment) in self.moments.iter().enumerate() {
@@ -85,8 +404,28 @@ impl fmt::Display for Circuit {
                             grid[i][moment_idx] = " │ ".to_string();
                         }
                     }
+                    Gate::CP {
+                        control, target, ..
+                    } => {
+                        grid[control][moment_idx] = "─●─".to_string();
+                        grid[target][moment_idx] = "[P]".to_string();
+                        let start = control.min(target);
+                        let end = control.max(target);
+                        for row in &mut grid[(start + 1)..end] {
+                            row[moment_idx] = " │ ".to_string();
+                        }
+                    }
                     Gate::Y { qubit } => grid[qubit][moment_idx] = "[Y]".to_string(),
                     Gate::Z { qubit } => grid[qubit][moment_idx] = "[Z]".to_string(),
+                    Gate::MeasureQubit { qubit, .. } => grid[qubit][moment_idx] = "[M]".to_string(),
+                    Gate::Reset { qubit } => grid[qubit][moment_idx] = "[R]".to_string(),
+                    Gate::Barrier { ref qubits } => {
+                        for &qubit in qubits {
+                            grid[qubit][moment_idx] = "─░─".to_string();
+                        }
+                    }
+                    // A global phase doesn't act on any wire.
+                    Gate::GPhase { .. } => {}
                     _ => {
                         panic!("Unknown gate {:?}", gate);
                     }
@@ -98,7 +437,7 @@ impl fmt::Display for Circuit {
         let mut output = String::new();
         for qubit_idx in 0..self.num_qubits {
             output.push_str(&format!("q{}: ", qubit_idx));
-            for moment_idx in 0..num_moments {
+            for moment_idx in 0..num_columns {
                 output.push_str(&grid[qubit_idx][moment_idx]);
             }
             output.push('\n');
@@ -111,18 +450,23 @@ impl fmt::Display for Circuit {
 pub fn gates_to_circuit(gates: Vec<Gate>) -> Circuit {
     let mut circuit = Circuit::new();
 
+    // With no gates there is nothing to size the register from.
+    if gates.is_empty() {
+        return circuit;
+    }
+
     // Determine the number of qubits based on the highest qubit index in the gates
     let mut highest_qubit = 0;
 
     for gate in gates {
-        circuit.add_gate(gate);
-
         // iterate through the targets of the gate to find the highest qubit index
         for qubit in gate.target() {
             if qubit > highest_qubit {
                 highest_qubit = qubit;
             }
         }
+
+        circuit.add_gate(gate);
     }
 
     circuit.set_num_qubits(highest_qubit + 1); // +1 because qubits are 0-indexed
@@ -135,26 +479,55 @@ pub fn circuit_to_qasm(circuit: &Circuit) -> String {
     qasm.push_str("OPENQASM 2.0;\n");
     qasm.push_str("include \"qelib1.inc\";\n");
     qasm.push_str(&format!("qreg q[{}];\n", circuit.num_qubits));
+    // One classical bit per qubit, widened if a measurement targets a higher bit.
+    let creg_size = circuit
+        .moments
+        .iter()
+        .flatten()
+        .filter_map(|g| match g {
+            Gate::Measure => Some(circuit.num_qubits),
+            Gate::MeasureQubit { creg_bit, .. } => Some(circuit.num_qubits.max(creg_bit + 1)),
+            _ => None,
+        })
+        .max();
+    if let Some(size) = creg_size {
+        qasm.push_str(&format!("creg c[{}];\n", size));
+    }
 
     for moment in &circuit.moments {
         for gate in moment {
             match gate {
-                Gate::H { qubit } => qasm.push_str(&format!("H q[{}];\n", qubit)),
-                Gate::X { qubit } => qasm.push_str(&format!("X q[{}];\n", qubit)),
-                Gate::Y { qubit } => qasm.push_str(&format!("Y q[{}];\n", qubit)),
-                Gate::Z { qubit } => qasm.push_str(&format!("Z q[{}];\n", qubit)),
+                Gate::I { qubit } => qasm.push_str(&format!("id q[{}];\n", qubit)),
+                Gate::H { qubit } => qasm.push_str(&format!("h q[{}];\n", qubit)),
+                Gate::X { qubit } => qasm.push_str(&format!("x q[{}];\n", qubit)),
+                Gate::Y { qubit } => qasm.push_str(&format!("y q[{}];\n", qubit)),
+                Gate::Z { qubit } => qasm.push_str(&format!("z q[{}];\n", qubit)),
                 Gate::RX { qubit, theta } => {
-                    qasm.push_str(&format!("RX q[{}], {};\n", qubit, theta))
+                    qasm.push_str(&format!("rx({}) q[{}];\n", theta, qubit))
                 }
                 Gate::RY { qubit, theta } => {
-                    qasm.push_str(&format!("RY q[{}], {};\n", qubit, theta))
+                    qasm.push_str(&format!("ry({}) q[{}];\n", theta, qubit))
                 }
                 Gate::RZ { qubit, theta } => {
-                    qasm.push_str(&format!("RZ q[{}], {};\n", qubit, theta))
+                    qasm.push_str(&format!("rz({}) q[{}];\n", theta, qubit))
                 }
                 Gate::CX { control, target } | Gate::CNOT { control, target } => {
-                    qasm.push_str(&format!("CX q[{}],q[{}];\n", control, target));
+                    qasm.push_str(&format!("cx q[{}],q[{}];\n", control, target));
+                }
+                Gate::CP {
+                    control,
+                    target,
+                    theta,
+                } => qasm.push_str(&format!("cp({}) q[{}],q[{}];\n", theta, control, target)),
+                Gate::Measure => qasm.push_str("measure q -> c;\n"),
+                Gate::MeasureQubit { qubit, creg_bit } => {
+                    qasm.push_str(&format!("measure q[{}] -> c[{}];\n", qubit, creg_bit))
                 }
+                Gate::Barrier { qubits } => {
+                    let qargs: Vec<String> = qubits.iter().map(|q| format!("q[{}]", q)).collect();
+                    qasm.push_str(&format!("barrier {};\n", qargs.join(",")));
+                }
+                Gate::Reset { qubit } => qasm.push_str(&format!("reset q[{}];\n", qubit)),
                 _ => panic!("Unsupported gate type: {:?}", gate),
             }
         }
@@ -162,11 +535,60 @@ pub fn circuit_to_qasm(circuit: &Circuit) -> String {
     qasm
 }
 
+/// Emits `circuit` as OpenQASM 3.0 with the `stdgates.inc` gate names. The classical
+/// register has one bit per qubit. Panics on gates with no 3.0 equivalent (`Unitary`).
+pub fn circuit_to_qasm3(circuit: &Circuit) -> String {
+    let mut qasm = String::new();
+    qasm.push_str("OPENQASM 3.0;\n");
+    qasm.push_str("include \"stdgates.inc\";\n");
+    qasm.push_str(&format!("qubit[{}] q;\n", circuit.num_qubits));
+    qasm.push_str(&format!("bit[{}] c;\n", circuit.num_qubits));
+
+    for gate in circuit.moments.iter().flatten() {
+        let line = match gate {
+            Gate::I { qubit } => format!("id q[{}];", qubit),
+            Gate::H { qubit } => format!("h q[{}];", qubit),
+            Gate::X { qubit } => format!("x q[{}];", qubit),
+            Gate::Y { qubit } => format!("y q[{}];", qubit),
+            Gate::Z { qubit } => format!("z q[{}];", qubit),
+            Gate::RX { qubit, theta } => format!("rx({}) q[{}];", theta, qubit),
+            Gate::RY { qubit, theta } => format!("ry({}) q[{}];", theta, qubit),
+            Gate::RZ { qubit, theta } => format!("rz({}) q[{}];", theta, qubit),
+            Gate::CX { control, target } | Gate::CNOT { control, target } => {
+                format!("cx q[{}], q[{}];", control, target)
+            }
+            Gate::CP {
+                control,
+                target,
+                theta,
+            } => format!("cp({}) q[{}], q[{}];", theta, control, target),
+            Gate::GPhase { theta } => format!("gphase({});", theta),
+            Gate::Measure => "c = measure q;".to_string(),
+            Gate::MeasureQubit { qubit, creg_bit } => {
+                format!("c[{}] = measure q[{}];", creg_bit, qubit)
+            }
+            Gate::Barrier { qubits } => {
+                let qargs: Vec<String> = qubits.iter().map(|q| format!("q[{}]", q)).collect();
+                format!("barrier {};", qargs.join(", "))
+            }
+            Gate::Reset { qubit } => format!("reset q[{}];", qubit),
+            Gate::Unitary { .. } => panic!("Unsupported gate type: {:?}", gate),
+        };
+        qasm.push_str(&line);
+        qasm.push('\n');
+    }
+    qasm
+}
+
 // tests
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Gate;
+    use crate::api::SimulatorApi;
+    use crate::statevector_backend::StatevectorSimulator;
+    use crate::{Gate, parse_qasm};
+    use num_complex::Complex;
+    use rand::Rng;
 
     #[test]
     fn test_circuit_display() {
@@ -184,6 +606,293 @@ mod tests {
         println!("{}", circuit);
     }
 
+    #[test]
+    fn test_from_qasm_detects_version() {
+        let v2 = Circuit::from_qasm("OPENQASM 2.0;\nqreg q[2];\nh q[0];\ncx q[0],q[1];\n").unwrap();
+        let v3 = Circuit::from_qasm("OPENQASM 3.0;\nqubit[2] q;\nh q[0];\nctrl @ x q[0], q[1];\n")
+            .unwrap();
+        assert_eq!(v2, v3);
+
+        assert!(Circuit::from_qasm("OPENQASM 3.0;\nqubit[2] q;\nh q[5];\n").is_err());
+    }
+
+    #[test]
+    fn test_circuit_to_qasm3_bell_state() {
+        let mut circuit = Circuit::with_qubits(2);
+        circuit.add_moment(vec![Gate::h(0)]);
+        circuit.add_moment(vec![Gate::cx(0, 1)]);
+        circuit.add_moment(vec![Gate::rz(1, 0.5), Gate::measure_qubit(0, 0)]);
+
+        let qasm = circuit_to_qasm3(&circuit);
+        let lines: Vec<&str> = qasm.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "OPENQASM 3.0;",
+                "include \"stdgates.inc\";",
+                "qubit[2] q;",
+                "bit[2] c;",
+                "h q[0];",
+                "cx q[0], q[1];",
+                "rz(0.5) q[1];",
+                "c[0] = measure q[0];",
+            ]
+        );
+
+        let (num_qubits, gates) = crate::parse_qasm3(&qasm).unwrap();
+        assert_eq!(num_qubits, 2);
+        assert_eq!(
+            gates,
+            circuit
+                .gates_flat()
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_from_gates_packed_fills_moments_greedily() {
+        let circuit =
+            Circuit::from_gates_packed(vec![Gate::h(0), Gate::x(1), Gate::y(2), Gate::z(0)]);
+        assert_eq!(circuit.num_qubits, 3);
+        assert_eq!(
+            circuit.moments,
+            vec![vec![Gate::h(0), Gate::x(1), Gate::y(2)], vec![Gate::z(0)]]
+        );
+
+        // A CX waits for both of its qubits; a later gate on an idle qubit does not.
+        let circuit =
+            Circuit::from_gates_packed(vec![Gate::h(0), Gate::cx(0, 1), Gate::x(1), Gate::x(2)]);
+        assert_eq!(
+            circuit.moments,
+            vec![
+                vec![Gate::h(0), Gate::x(2)],
+                vec![Gate::cx(0, 1)],
+                vec![Gate::x(1)]
+            ]
+        );
+        assert_eq!(
+            format!("{}", circuit),
+            "q0: [H]─●────\nq1: ────⊕─[X]\nq2: [X]──────\n"
+        );
+    }
+
+    #[test]
+    fn test_ghz_circuit_metrics() {
+        let mut circuit = Circuit::with_qubits(3);
+        circuit.add_gate(Gate::h(0));
+        circuit.add_gate(Gate::cx(0, 1));
+        circuit.add_gate(Gate::cx(1, 2));
+        // Runs alongside the second CX, so the depth stays 3.
+        circuit.add_gate(Gate::x(0));
+
+        assert_eq!(circuit.num_moments(), 4);
+        assert_eq!(circuit.gate_count(), 4);
+        assert_eq!(circuit.two_qubit_gate_count(), 2);
+        assert_eq!(circuit.depth(), 3);
+
+        circuit.add_gate(Gate::Measure);
+        assert_eq!(circuit.depth(), 4);
+        assert_eq!(circuit.two_qubit_gate_count(), 2);
+        assert_eq!(Circuit::with_qubits(2).depth(), 0);
+    }
+
+    #[test]
+    fn test_qft_of_zero_is_uniform() {
+        let n = 4;
+        let mut sim = StatevectorSimulator::new(n);
+        sim.run(&Circuit::qft(n)).unwrap();
+
+        let amp = 1.0 / ((1 << n) as f64).sqrt();
+        for a in &sim.statevector().amplitudes {
+            assert!((a.re - amp).abs() < 1e-9 && a.im.abs() < 1e-9, "{}", a);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_out_of_range_qubits() {
+        let mut circuit = Circuit::with_qubits(2);
+        circuit.add_gate(Gate::h(0));
+        circuit.add_gate(Gate::cx(0, 1));
+        circuit.add_gate(Gate::Measure);
+        assert!(circuit.validate().is_ok());
+
+        circuit.add_gate(Gate::rx(5, 0.1));
+        assert!(matches!(circuit.validate(), Err(SimError::Qubit(5))));
+
+        let mut control = Circuit::with_qubits(2);
+        control.add_gate(Gate::cp(3, 1, 0.2));
+        assert!(matches!(control.validate(), Err(SimError::Qubit(3))));
+
+        let mut sim = StatevectorSimulator::new(2);
+        assert!(matches!(sim.run(&circuit), Err(SimError::Qubit(5))));
+        assert!(sim.statevector().amplitudes[0].re == 1.0);
+
+        let mut quantum = crate::QuantumSimulator::new(2);
+        assert!(matches!(
+            quantum.apply_circuit(&circuit),
+            Err(SimError::Qubit(5))
+        ));
+        assert!(matches!(
+            quantum.apply_circuit(&Circuit::qft(3)),
+            Err(SimError::Qubit(2))
+        ));
+    }
+
+    #[test]
+    fn test_barrier_and_reset_in_qasm_and_metrics() {
+        let mut circuit = Circuit::with_qubits(2);
+        circuit.add_gate(Gate::h(0));
+        circuit.add_gate(Gate::Barrier { qubits: vec![0, 1] });
+        circuit.add_gate(Gate::x(1));
+        circuit.add_gate(Gate::reset(0));
+
+        let qasm = circuit_to_qasm(&circuit);
+        assert!(qasm.contains("barrier q[0],q[1];\n"));
+        assert!(qasm.contains("reset q[0];\n"));
+        assert_eq!(Circuit::from_qasm(&qasm).unwrap(), circuit);
+
+        let qasm3 = circuit_to_qasm3(&circuit);
+        assert!(qasm3.contains("barrier q[0], q[1];\n"));
+        let (_, gates) = parse_qasm3(&qasm3).unwrap();
+        assert_eq!(gates.iter().collect::<Vec<_>>(), circuit.gates_flat());
+
+        // The barrier lines X up after H without adding a layer of its own.
+        assert_eq!(circuit.depth(), 2);
+        assert_eq!(circuit.gate_count(), 3);
+        assert_eq!(circuit.two_qubit_gate_count(), 0);
+        assert_eq!(
+            format!("{}", circuit),
+            "q0: [H]─░────[R]\nq1: ────░─[X]───\n"
+        );
+    }
+
+    #[test]
+    fn test_append_and_add() {
+        let mut layered = Circuit::with_qubits(2);
+        layered.add_gate(Gate::h(0));
+        layered.add_gate(Gate::cx(0, 1));
+        let mut wider = Circuit::with_qubits(3);
+        wider.add_moment(vec![Gate::x(2)]);
+
+        let mut appended = layered.clone();
+        appended.append(&wider);
+        assert_eq!(appended.num_moments(), 3);
+        assert_eq!(appended.num_qubits, 3);
+        assert_eq!(appended.moments()[2], vec![Gate::x(2)]);
+
+        let mut narrow = Circuit::with_qubits(1);
+        narrow.add_gate(Gate::z(0));
+        let mut widened = wider.clone();
+        widened.append(&narrow);
+        assert_eq!(widened.num_qubits, 3);
+
+        assert_eq!(layered + wider, appended);
+    }
+
+    #[test]
+    fn test_inverse_undoes_circuit_on_random_state() {
+        let n = 3;
+        let mut rng = rand::thread_rng();
+        let mut prepare = Circuit::with_qubits(n);
+        for q in 0..n {
+            prepare.add_gate(Gate::ry(q, rng.gen_range(0.0..std::f64::consts::TAU)));
+            prepare.add_gate(Gate::rz(q, rng.gen_range(0.0..std::f64::consts::TAU)));
+        }
+        prepare.add_gate(Gate::cx(0, 1));
+        prepare.add_gate(Gate::cx(1, 2));
+
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        let mut circuit = Circuit::with_qubits(n);
+        circuit.add_moment(vec![Gate::h(0), Gate::rx(1, 0.3), Gate::y(2)]);
+        circuit.add_moment(vec![Gate::cx(0, 2), Gate::rz(1, -1.1)]);
+        circuit.add_gate(Gate::cp(1, 2, 0.7));
+        circuit.add_gate(Gate::GPhase { theta: 0.4 });
+        circuit.add_gate(Gate::Unitary {
+            qubits: vec![1],
+            matrix: vec![
+                vec![Complex::new(s, 0.0), Complex::new(0.0, s)],
+                vec![Complex::new(0.0, s), Complex::new(s, 0.0)],
+            ],
+        });
+        circuit.add_moment(vec![Gate::ry(0, 2.5), Gate::z(1), Gate::x(2)]);
+
+        let mut sim = StatevectorSimulator::new(n);
+        sim.apply_circuit_no_reset(&prepare).unwrap();
+        let original = sim.statevector().clone();
+        sim.apply_circuit_no_reset(&circuit).unwrap();
+        sim.apply_circuit_no_reset(&circuit.inverse().unwrap())
+            .unwrap();
+
+        let (idx, diff) = sim.statevector().max_amplitude_diff(&original);
+        assert!(diff < 1e-9, "amplitude {} differs by {}", idx, diff);
+
+        circuit.add_gate(Gate::measure_qubit(0, 0));
+        assert!(matches!(circuit.inverse(), Err(SimError::NotInvertible(_))));
+    }
+
+    #[test]
+    fn test_qft_matches_dft_and_inverse_restores_basis_state() {
+        let n = 3;
+        let dim = 1 << n;
+        let x = rand::thread_rng().gen_range(0..dim);
+        let prepare = gates_to_circuit((0..n).filter(|q| x & (1 << q) != 0).map(Gate::x).collect());
+
+        let mut sim = StatevectorSimulator::new(n);
+        sim.apply_circuit_no_reset(&prepare).unwrap();
+        sim.apply_circuit_no_reset(&Circuit::qft(n)).unwrap();
+        for (k, a) in sim.statevector().amplitudes.iter().enumerate() {
+            let angle = 2.0 * std::f64::consts::PI * (x * k) as f64 / dim as f64;
+            let expected = Complex::from_polar(1.0 / (dim as f64).sqrt(), angle);
+            assert!((a - expected).norm() < 1e-9, "x={} k={}: {}", x, k, a);
+        }
+
+        sim.apply_circuit_no_reset(&Circuit::inverse_qft(n))
+            .unwrap();
+        for (i, a) in sim.statevector().amplitudes.iter().enumerate() {
+            let expected = if i == x { 1.0 } else { 0.0 };
+            assert!(
+                (a - Complex::new(expected, 0.0)).norm() < 1e-9,
+                "{}: {}",
+                i,
+                a
+            );
+        }
+    }
+
+    #[test]
+    fn test_structurally_identical_circuits_are_equal_and_hash_equally() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |c: &Circuit| {
+            let mut h = DefaultHasher::new();
+            c.hash(&mut h);
+            h.finish()
+        };
+        let build =
+            |theta: f64| gates_to_circuit(vec![Gate::h(0), Gate::ry(1, theta), Gate::cx(0, 1)]);
+
+        let a = build(0.5);
+        let b = build(0.5);
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+
+        assert_ne!(a, build(0.25));
+        assert_eq!(build(0.0), build(-0.0));
+        assert_eq!(hash(&build(0.0)), hash(&build(-0.0)));
+
+        let nan = build(f64::NAN);
+        assert_eq!(nan, nan.clone());
+        assert_eq!(nan, build(-f64::NAN));
+        assert_eq!(hash(&nan), hash(&build(-f64::NAN)));
+
+        let mut cache = std::collections::HashMap::new();
+        cache.insert(a, 1.0);
+        assert_eq!(cache.get(&b), Some(&1.0));
+    }
+
     #[test]
     fn test_gates_to_circuit() {
         let gates = vec![
@@ -199,6 +908,55 @@ mod tests {
         assert_eq!(circuit.num_qubits, 2);
     }
 
+    #[test]
+    fn test_insert_gate_into_middle() {
+        let mut circuit = gates_to_circuit(vec![Gate::H { qubit: 0 }, Gate::X { qubit: 1 }]);
+        circuit
+            .insert_gate(
+                1,
+                Gate::CX {
+                    control: 0,
+                    target: 1,
+                },
+            )
+            .unwrap();
+
+        let gates: Vec<Gate> = circuit.gates_flat().into_iter().cloned().collect();
+        assert_eq!(
+            gates,
+            vec![
+                Gate::H { qubit: 0 },
+                Gate::CX {
+                    control: 0,
+                    target: 1
+                },
+                Gate::X { qubit: 1 },
+            ]
+        );
+        assert!(circuit.insert_gate(4, Gate::H { qubit: 0 }).is_err());
+    }
+
+    #[test]
+    fn test_remove_and_replace() {
+        let mut circuit = gates_to_circuit(vec![
+            Gate::H { qubit: 0 },
+            Gate::X { qubit: 0 },
+            Gate::Z { qubit: 0 },
+        ]);
+
+        let removed = circuit.remove_moment(1).unwrap();
+        assert_eq!(removed, vec![Gate::X { qubit: 0 }]);
+
+        let replaced = circuit.replace_gate(1, 0, Gate::Y { qubit: 0 }).unwrap();
+        assert_eq!(replaced, Gate::Z { qubit: 0 });
+
+        let gates: Vec<Gate> = circuit.gates_flat().into_iter().cloned().collect();
+        assert_eq!(gates, vec![Gate::H { qubit: 0 }, Gate::Y { qubit: 0 }]);
+
+        assert!(circuit.remove_moment(2).is_err());
+        assert!(circuit.replace_gate(0, 1, Gate::X { qubit: 0 }).is_err());
+    }
+
     #[test]
     fn circuit_to_qasm_test() {
         let mut circuit = Circuit::new();
@@ -212,7 +970,98 @@ mod tests {
 
         let qasm = circuit_to_qasm(&circuit);
         let expected_qasm =
-            "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[2];\nH q[0];\nCX q[0],q[1];\nX q[1];\n";
+            "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[2];\nh q[0];\ncx q[0],q[1];\nx q[1];\n";
         assert_eq!(qasm, expected_qasm);
     }
+
+    #[test]
+    fn test_circuit_to_qasm_round_trips_through_parser() {
+        let mut circuit = Circuit::with_qubits(3);
+        circuit.add_moment(vec![Gate::h(0), Gate::rx(1, 0.5)]);
+        circuit.add_moment(vec![Gate::cx(0, 2), Gate::ry(1, -1.25)]);
+        circuit.add_moment(vec![Gate::rz(2, std::f64::consts::PI), Gate::y(1)]);
+        circuit.add_moment(vec![Gate::cp(1, 0, 0.75), Gate::z(2)]);
+        circuit.add_moment(vec![Gate::measure_qubit(1, 1)]);
+
+        let qasm = circuit_to_qasm(&circuit);
+        assert!(qasm.contains("rx(0.5) q[1];\n"));
+        assert!(qasm.contains("creg c[3];\n"));
+
+        let (num_qubits, gates) = parse_qasm(&qasm);
+        assert_eq!(num_qubits, 3);
+        assert_eq!(
+            gates,
+            circuit
+                .gates_flat()
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_measured_circuit_exports_and_reimports() {
+        // The JSON shape the UI sends, ending in measurements.
+        let circuit: Circuit = serde_json::from_str(
+            r#"{
+                "numQubits": 2,
+                "moments": [
+                    [{ "type": "H", "qubit": 0 }],
+                    [{ "type": "CX", "control": 0, "target": 1 }],
+                    [{ "type": "MeasureQubit", "qubit": 1, "creg_bit": 3 }],
+                    [{ "type": "Measure" }]
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let qasm = circuit_to_qasm(&circuit);
+        assert!(qasm.contains("creg c[4];\n"), "{}", qasm);
+        assert!(qasm.contains("measure q[1] -> c[3];\n"));
+        assert!(qasm.ends_with("measure q -> c;\n"));
+
+        let reimported = Circuit::from_qasm(&qasm).unwrap();
+        assert_eq!(reimported.num_qubits, 2);
+        assert_eq!(reimported.gates_flat(), circuit.gates_flat());
+    }
+
+    #[test]
+    fn test_empty_circuit_is_identity() {
+        let circuit = Circuit::with_qubits(3);
+        assert!(circuit.is_empty());
+
+        assert_eq!(format!("{}", circuit), "q0: ───\nq1: ───\nq2: ───\n");
+        assert_eq!(
+            circuit_to_qasm(&circuit),
+            "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[3];\n"
+        );
+
+        let mut sim = StatevectorSimulator::new(1);
+        sim.run(&circuit).unwrap();
+        let amps = &sim.statevector().amplitudes;
+        assert_eq!(amps.len(), 8);
+        assert_eq!(amps[0], Complex::new(1.0, 0.0));
+        assert!(amps[1..].iter().all(|a| *a == Complex::new(0.0, 0.0)));
+
+        // Only the start event; there are no gates to report.
+        assert_eq!(crate::simulator::run_circuit(&circuit).unwrap().len(), 1);
+
+        let from_gates = gates_to_circuit(Vec::new());
+        assert!(from_gates.is_empty());
+        assert_eq!(from_gates.num_qubits, 0);
+        assert_eq!(format!("{}", from_gates), "Empty Circuit");
+    }
+
+    #[test]
+    fn test_bell_circuit_to_dot() {
+        let circuit = gates_to_circuit(vec![Gate::h(0), Gate::cx(0, 1)]);
+        let dot = circuit.to_dot();
+
+        assert!(dot.starts_with("digraph circuit {"));
+        assert!(dot.contains("g0_0 [label=\"H\", shape=box];"));
+        assert!(dot.contains("g1_0 [label=\"CX\", shape=box];"));
+        assert!(dot.contains("q0 -> g0_0 [label=\"q0\"];"));
+        assert!(dot.contains("g0_0 -> g1_0 [label=\"q0\"];"));
+        assert!(dot.contains("q1 -> g1_0 [label=\"q1\"];"));
+    }
 }
@@ -0,0 +1,421 @@
+// src/density.rs
+use crate::StateVector;
+use crate::api::{Pauli, SimError, SimulatorApi};
+use crate::circuit::Circuit;
+use crate::parser::Gate;
+use crate::simulator::{HADAMARD, PAULI_X, PAULI_Y, PAULI_Z, construct_gate_matrix};
+use crate::state::DEFAULT_MAX_QUBITS;
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+type Matrix2 = [[Complex<f64>; 2]; 2];
+
+/// A mixed-state simulator that tracks the full 2^n x 2^n density matrix ρ.
+///
+/// ρ is stored vectorised as a statevector on 2n qubits, with ρ[i][j] at index
+/// `i + (j << n)`. Then `U ρ U†` is U applied to the low n (row) qubits and conj(U) to
+/// the high n (column) qubits, so the `StateVector` gate kernels can be reused as-is.
+/// Memory is 4^n amplitudes, so the register is limited to half of `DEFAULT_MAX_QUBITS`.
+pub struct DensityMatrixSimulator {
+    num_qubits: usize,
+    rho: StateVector,
+    /// Drives `measure` and `sample`; `sample` only has `&self`.
+    rng: RefCell<StdRng>,
+}
+
+impl DensityMatrixSimulator {
+    /// Panics if `num_qubits` exceeds the density-matrix limit; see [`DensityMatrixSimulator::try_new`].
+    pub fn new(num_qubits: usize) -> Self {
+        Self::try_new(num_qubits).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Starts in the pure state |0..0⟩⟨0..0|.
+    pub fn try_new(num_qubits: usize) -> Result<Self, SimError> {
+        check_width(num_qubits)?;
+        Ok(Self {
+            num_qubits,
+            rho: StateVector::new(2 * num_qubits),
+            rng: RefCell::new(StdRng::from_entropy()),
+        })
+    }
+
+    /// Seeds the RNG behind measurements and sampling so that results are reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = RefCell::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// ρ[i][j] as a dense matrix.
+    pub fn density_matrix(&self) -> Vec<Vec<Complex<f64>>> {
+        let dim = 1 << self.num_qubits;
+        (0..dim)
+            .map(|i| {
+                (0..dim)
+                    .map(|j| self.rho.amplitudes[i + (j << self.num_qubits)])
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The computational-basis probabilities, i.e. the diagonal of ρ.
+    pub fn probabilities(&self) -> Vec<f64> {
+        let dim = 1 << self.num_qubits;
+        (0..dim)
+            .map(|i| self.rho.amplitudes[i + (i << self.num_qubits)].re)
+            .collect()
+    }
+
+    /// Tr(ρ²): 1 for a pure state, 1/2^n for the maximally mixed state.
+    pub fn purity(&self) -> f64 {
+        // ρ is Hermitian, so Tr(ρ²) = Σ |ρ_ij|².
+        self.rho.amplitudes.iter().map(|a| a.norm_sqr()).sum()
+    }
+
+    pub fn apply_gate(&mut self, gate: &Gate) -> Result<(), SimError> {
+        let n = self.num_qubits;
+        match gate {
+            Gate::I { qubit } => self.check_qubit(*qubit)?,
+            Gate::H { qubit } => self.apply_single_qubit_unitary(&HADAMARD, *qubit)?,
+            Gate::X { qubit } => self.apply_single_qubit_unitary(&PAULI_X, *qubit)?,
+            Gate::Y { qubit } => self.apply_single_qubit_unitary(&PAULI_Y, *qubit)?,
+            Gate::Z { qubit } => self.apply_single_qubit_unitary(&PAULI_Z, *qubit)?,
+            Gate::CX { control, target } | Gate::CNOT { control, target } => {
+                self.check_qubit(*control)?;
+                self.check_qubit(*target)?;
+                self.rho.apply_cx(*control, *target);
+                self.rho.apply_cx(*control + n, *target + n);
+            }
+            Gate::CP {
+                control,
+                target,
+                theta,
+            } => {
+                self.check_qubit(*control)?;
+                self.check_qubit(*target)?;
+                self.rho.apply_cp(*control, *target, *theta);
+                self.rho.apply_cp(*control + n, *target + n, -*theta);
+            }
+            Gate::Unitary { qubits, matrix } => {
+                for &q in qubits {
+                    self.check_qubit(q)?;
+                }
+                let conj: Vec<Vec<Complex<f64>>> = matrix
+                    .iter()
+                    .map(|row| row.iter().map(|m| m.conj()).collect())
+                    .collect();
+                let shifted: Vec<usize> = qubits.iter().map(|q| q + n).collect();
+                self.rho.apply_arbitrary_gate(matrix, qubits)?;
+                self.rho.apply_arbitrary_gate(&conj, &shifted)?;
+            }
+            // A global phase cancels in U ρ U†.
+            Gate::GPhase { .. } | Gate::Barrier { .. } => {}
+            Gate::Reset { qubit } => self.apply_reset(*qubit)?,
+            Gate::Measure | Gate::MeasureQubit { .. } => {
+                return Err(SimError::Internal(
+                    "measurement is not supported by the density-matrix simulator".to_string(),
+                ));
+            }
+            Gate::RX { qubit, .. } | Gate::RY { qubit, .. } | Gate::RZ { qubit, .. } => {
+                let matrix = construct_gate_matrix(gate)
+                    .ok_or_else(|| SimError::Internal(format!("no matrix for {:?}", gate)))?;
+                self.apply_single_qubit_unitary(&matrix, *qubit)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Depolarizing channel on `qubit`: ρ → (1 - p) ρ + p (I/2 ⊗ Tr_qubit ρ), using the
+    /// Kraus operators √(1 - 3p/4) I and √(p/4) X, Y, Z. `p = 1` fully randomizes the qubit.
+    pub fn apply_depolarizing(&mut self, qubit: usize, p: f64) -> Result<(), SimError> {
+        self.check_qubit(qubit)?;
+        if !(0.0..=1.0).contains(&p) {
+            return Err(SimError::Internal(format!(
+                "depolarizing probability {} is outside [0, 1]",
+                p
+            )));
+        }
+
+        let mut result: Vec<Complex<f64>> = self
+            .rho
+            .amplitudes
+            .iter()
+            .map(|a| a * (1.0 - 0.75 * p))
+            .collect();
+        for pauli in [&PAULI_X, &PAULI_Y, &PAULI_Z] {
+            let mut term = self.rho.clone();
+            term.apply_single_qubit_gate(pauli, qubit);
+            term.apply_single_qubit_gate(&conjugate(pauli), qubit + self.num_qubits);
+            for (r, t) in result.iter_mut().zip(&term.amplitudes) {
+                *r += t * (0.25 * p);
+            }
+        }
+        self.rho.amplitudes = result;
+        Ok(())
+    }
+
+    /// Reset to |0⟩ as a channel: ρ → K0 ρ K0† + K1 ρ K1† with K0 = |0⟩⟨0| and K1 = |0⟩⟨1|.
+    fn apply_reset(&mut self, qubit: usize) -> Result<(), SimError> {
+        self.check_qubit(qubit)?;
+        let zero = Complex::new(0.0, 0.0);
+        let one = Complex::new(1.0, 0.0);
+        let kraus: [Matrix2; 2] = [[[one, zero], [zero, zero]], [[zero, one], [zero, zero]]];
+
+        let mut result = vec![zero; self.rho.amplitudes.len()];
+        for k in &kraus {
+            let mut term = self.rho.clone();
+            term.apply_single_qubit_gate(k, qubit);
+            term.apply_single_qubit_gate(&conjugate(k), qubit + self.num_qubits);
+            for (r, t) in result.iter_mut().zip(&term.amplitudes) {
+                *r += t;
+            }
+        }
+        self.rho.amplitudes = result;
+        Ok(())
+    }
+
+    fn apply_single_qubit_unitary(
+        &mut self,
+        matrix: &Matrix2,
+        qubit: usize,
+    ) -> Result<(), SimError> {
+        self.check_qubit(qubit)?;
+        self.rho.apply_single_qubit_gate(matrix, qubit);
+        self.rho
+            .apply_single_qubit_gate(&conjugate(matrix), qubit + self.num_qubits);
+        Ok(())
+    }
+
+    // The vectorised ρ has 2n qubits, so out-of-range indices would silently hit the
+    // column half instead of panicking.
+    fn check_qubit(&self, qubit: usize) -> Result<(), SimError> {
+        if qubit >= self.num_qubits {
+            return Err(SimError::Qubit(qubit));
+        }
+        Ok(())
+    }
+}
+
+impl SimulatorApi for DensityMatrixSimulator {
+    /// Returns to |0..0⟩⟨0..0| on `num_qubits`; panics past the density-matrix limit.
+    fn reset(&mut self, num_qubits: usize) {
+        check_width(num_qubits).unwrap_or_else(|e| panic!("{}", e));
+        self.num_qubits = num_qubits;
+        self.rho = StateVector::new(2 * num_qubits);
+    }
+
+    /// Resets to |0..0⟩ on the circuit's width and applies every gate.
+    fn run(&mut self, circuit: &Circuit) -> Result<(), SimError> {
+        check_width(circuit.num_qubits)?;
+        self.reset(circuit.num_qubits);
+        self.apply_circuit_no_reset(circuit)
+    }
+
+    fn apply_circuit_no_reset(&mut self, circuit: &Circuit) -> Result<(), SimError> {
+        if circuit.num_qubits > self.num_qubits {
+            return Err(SimError::Qubit(circuit.num_qubits - 1));
+        }
+        for moment in &circuit.moments {
+            for gate in moment {
+                self.apply_gate(gate)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The vectorised ρ on 2n qubits (see the type's docs), since a mixed state has no
+    /// single statevector.
+    fn statevector(&self) -> &StateVector {
+        &self.rho
+    }
+
+    /// Projects `qubit` onto the outcome drawn from ρ's diagonal and renormalizes.
+    fn measure(&mut self, qubit: usize) -> Result<u8, SimError> {
+        self.check_qubit(qubit)?;
+        let p1: f64 = self
+            .probabilities()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (i >> qubit) & 1 == 1)
+            .map(|(_, p)| p)
+            .sum();
+        let outcome = u8::from(self.rng.get_mut().r#gen::<f64>() < p1);
+
+        let zero = Complex::new(0.0, 0.0);
+        let one = Complex::new(1.0, 0.0);
+        let (projector, p): (Matrix2, f64) = if outcome == 1 {
+            ([[zero, zero], [zero, one]], p1)
+        } else {
+            ([[one, zero], [zero, zero]], 1.0 - p1)
+        };
+        // The projector is real, so it is its own conjugate on the column qubits.
+        self.rho.apply_single_qubit_gate(&projector, qubit);
+        self.rho
+            .apply_single_qubit_gate(&projector, qubit + self.num_qubits);
+        for a in &mut self.rho.amplitudes {
+            *a /= p;
+        }
+        Ok(outcome)
+    }
+
+    /// Tr(ρP) for a Pauli string, e.g. [(Z,0),(X,2)].
+    fn expectation(&self, ops: &[(Pauli, usize)]) -> Result<f64, SimError> {
+        let mut p_rho = self.rho.clone();
+        for &(p, q) in ops {
+            self.check_qubit(q)?;
+            match p {
+                Pauli::I => {}
+                Pauli::X => p_rho.apply_single_qubit_gate(&PAULI_X, q),
+                Pauli::Y => p_rho.apply_single_qubit_gate(&PAULI_Y, q),
+                Pauli::Z => p_rho.apply_single_qubit_gate(&PAULI_Z, q),
+            }
+        }
+        let dim = 1 << self.num_qubits;
+        let trace: Complex<f64> = (0..dim)
+            .map(|i| p_rho.amplitudes[i + (i << self.num_qubits)])
+            .sum();
+        Ok(trace.re)
+    }
+
+    /// Draws computational-basis shots from `probabilities()` without changing ρ.
+    fn sample(&self, shots: u32) -> Result<HashMap<String, u32>, SimError> {
+        use rand::distributions::{Distribution, WeightedIndex};
+        // Rounding can leave tiny negative entries on the diagonal.
+        let probs: Vec<f64> = self.probabilities().iter().map(|p| p.max(0.0)).collect();
+        let dist = WeightedIndex::new(&probs).map_err(|e| SimError::Internal(e.to_string()))?;
+
+        let mut rng = self.rng.borrow_mut();
+        let mut counts = HashMap::new();
+        for _ in 0..shots {
+            let idx = dist.sample(&mut *rng);
+            let bitstr = format!("{:0width$b}", idx, width = self.num_qubits);
+            *counts.entry(bitstr).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+}
+
+/// Fails if ρ on `num_qubits` would exceed the density-matrix limit.
+fn check_width(num_qubits: usize) -> Result<(), SimError> {
+    let max = DEFAULT_MAX_QUBITS / 2;
+    if num_qubits > max {
+        return Err(SimError::TooManyQubits {
+            requested: num_qubits,
+            max,
+        });
+    }
+    Ok(())
+}
+
+fn conjugate(m: &Matrix2) -> Matrix2 {
+    [
+        [m[0][0].conj(), m[0][1].conj()],
+        [m[1][0].conj(), m[1][1].conj()],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn approx_eq(a: Complex<f64>, b: Complex<f64>) -> bool {
+        (a - b).norm() < EPSILON
+    }
+
+    #[test]
+    fn test_full_depolarizing_gives_maximally_mixed_state() {
+        let mut sim = DensityMatrixSimulator::new(1);
+        sim.apply_depolarizing(0, 1.0).unwrap();
+
+        let rho = sim.density_matrix();
+        assert!(approx_eq(rho[0][0], Complex::new(0.5, 0.0)));
+        assert!(approx_eq(rho[1][1], Complex::new(0.5, 0.0)));
+        assert!(approx_eq(rho[0][1], Complex::new(0.0, 0.0)));
+        assert!(approx_eq(rho[1][0], Complex::new(0.0, 0.0)));
+        assert!((sim.purity() - 0.5).abs() < EPSILON);
+
+        assert!(sim.apply_depolarizing(0, 1.5).is_err());
+        assert!(sim.apply_depolarizing(1, 0.5).is_err());
+    }
+
+    #[test]
+    fn test_reset_maps_superposition_to_zero() {
+        let circuit =
+            Circuit::from_qasm("qreg q[2];\nh q[0];\ncx q[0], q[1];\nreset q[0];").unwrap();
+        let mut sim = DensityMatrixSimulator::new(2);
+        sim.run(&circuit).unwrap();
+
+        // Resetting half of a Bell pair leaves |0⟩ ⊗ (I/2) on q1.
+        let probabilities = sim.probabilities();
+        for (i, expected) in [0.5, 0.0, 0.5, 0.0].into_iter().enumerate() {
+            assert!((probabilities[i] - expected).abs() < EPSILON, "p[{}]", i);
+        }
+        assert!((sim.purity() - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_bell_circuit_matches_pure_state() {
+        let circuit = Circuit::from_qasm("qreg q[2];\nh q[0];\ncx q[0], q[1];").unwrap();
+        let mut sim = DensityMatrixSimulator::new(2);
+        sim.run(&circuit).unwrap();
+
+        // |Φ+⟩⟨Φ+| has 1/2 in the four corners.
+        let rho = sim.density_matrix();
+        for (i, j) in [(0, 0), (0, 3), (3, 0), (3, 3)] {
+            assert!(
+                approx_eq(rho[i][j], Complex::new(0.5, 0.0)),
+                "ρ[{}][{}]",
+                i,
+                j
+            );
+        }
+        assert!((sim.purity() - 1.0).abs() < EPSILON);
+
+        let zz = sim.expectation(&[(Pauli::Z, 0), (Pauli::Z, 1)]).unwrap();
+        let yy = sim.expectation(&[(Pauli::Y, 0), (Pauli::Y, 1)]).unwrap();
+        assert!((zz - 1.0).abs() < EPSILON, "⟨ZZ⟩ was {}", zz);
+        assert!((yy + 1.0).abs() < EPSILON, "⟨YY⟩ was {}", yy);
+
+        // Depolarizing one half of the pair shrinks the correlation by (1 - p).
+        sim.apply_depolarizing(0, 0.4).unwrap();
+        let zz = sim.expectation(&[(Pauli::Z, 0), (Pauli::Z, 1)]).unwrap();
+        assert!((zz - 0.6).abs() < EPSILON, "⟨ZZ⟩ was {}", zz);
+    }
+
+    #[test]
+    fn test_bell_pair_through_simulator_api() {
+        fn bell_counts<S: SimulatorApi>(sim: &mut S) -> HashMap<String, u32> {
+            let circuit = Circuit::from_qasm("qreg q[2];\nh q[0];\ncx q[0], q[1];").unwrap();
+            sim.run(&circuit).unwrap();
+            sim.sample(200).unwrap()
+        }
+
+        let counts = bell_counts(&mut DensityMatrixSimulator::new(2).with_seed(7));
+        assert_eq!(counts.values().sum::<u32>(), 200);
+        assert!(
+            counts.keys().all(|k| k == "00" || k == "11"),
+            "{:?}",
+            counts
+        );
+        assert_eq!(
+            counts,
+            bell_counts(&mut DensityMatrixSimulator::new(2).with_seed(7))
+        );
+
+        // Measuring one half of the pair collapses the other to the same outcome.
+        let mut sim = DensityMatrixSimulator::new(2).with_seed(7);
+        bell_counts(&mut sim);
+        let first = sim.measure(0).unwrap();
+        assert_eq!(sim.measure(1).unwrap(), first);
+        assert!((sim.purity() - 1.0).abs() < EPSILON);
+        assert!(sim.measure(2).is_err());
+    }
+}
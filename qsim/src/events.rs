@@ -1,5 +1,6 @@
 use crate::state::StateVector;
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::io::Write;
 
 #[derive(Serialize, Debug)]
@@ -7,7 +8,9 @@ use std::io::Write;
 pub enum Event {
     SimulationStart(SimulationStartInfo),
     GateApplication(GateInfo),
+    MomentApplication(MomentInfo),
     MeasurementResult(MeasurementInfo),
+    MeasurementCounts(MeasurementCountsInfo),
 }
 
 #[derive(Serialize, Debug)]
@@ -25,12 +28,37 @@ pub struct GateInfo {
     pub state_vector: StateVector,
 }
 
+/// The state after every gate in one moment has been applied.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MomentInfo {
+    pub step: usize,
+    pub gates: Vec<String>,
+    pub state_vector: StateVector,
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MeasurementInfo {
     pub classical_outcome: usize,
     pub binary_outcome: String,
     pub final_state_vector: StateVector,
+    /// Set for single-qubit measurements; absent when the whole register was measured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qubit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creg_bit: Option<usize>,
+}
+
+/// Outcome counts from sampling a full-register measurement `shots` times.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MeasurementCountsInfo {
+    pub shots: u32,
+    /// Bitstrings with qubit 0 as the rightmost character.
+    pub counts: BTreeMap<String, u32>,
+    /// The state just before measurement, which every shot was sampled from.
+    pub final_state_vector: StateVector,
 }
 
 /// Helper function to serialize and print an event to a writer.
@@ -34,3 +34,59 @@ pub fn run_qasm_counts(
     sim.run(&circ)?;
     sim.sample(shots)
 }
+
+/// Runs the circuit once and returns both the final statevector and `shots` sampled counts.
+pub fn run_qasm_full(
+    qasm: &str,
+    shots: u32,
+) -> Result<(StateVector, std::collections::HashMap<String, u32>), SimError> {
+    let circ = Circuit::from_qasm(qasm)?;
+    let mut sim = StatevectorSimulator::new(circ.num_qubits);
+    sim.run(&circ)?;
+    let counts = sim.sample(shots)?;
+    Ok((sim.statevector().clone(), counts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_qasm_full_bell_state() {
+        let qasm = r#"
+        OPENQASM 2.0;
+        qreg q[2];
+        h q[0];
+        cx q[0], q[1];
+        "#;
+        let (state, counts) = run_qasm_full(qasm, 500).unwrap();
+
+        assert_eq!(counts.values().sum::<u32>(), 500);
+        for key in counts.keys() {
+            let idx = usize::from_str_radix(key, 2).unwrap();
+            assert!(
+                state.amplitudes[idx].norm_sqr() > 0.0,
+                "sampled {} which has zero amplitude",
+                key
+            );
+        }
+        assert!(counts.keys().all(|k| k == "00" || k == "11"));
+    }
+
+    #[test]
+    fn test_custom_bell_gate_prepares_bell_state() {
+        let qasm = r#"
+        OPENQASM 2.0;
+        gate bell a, b { h a; cx a, b; }
+        qreg q[2];
+        bell q[0], q[1];
+        "#;
+        let state = run_qasm_return_statevector(qasm).unwrap();
+
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let zero = num_complex::Complex::new(0.0, 0.0);
+        let amp = num_complex::Complex::new(h, 0.0);
+        let expected = StateVector::from(vec![amp, zero, zero, amp]);
+        assert!(state.approx_eq_up_to_global_phase(&expected, 1e-9));
+    }
+}
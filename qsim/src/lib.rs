@@ -4,14 +4,19 @@ pub mod state;
 
 pub mod api;
 pub mod circuit;
+pub mod density;
 pub mod events;
 pub mod facade;
+mod linalg;
+pub mod mock;
+pub mod product_backend;
 pub mod statevector_backend;
 
-pub use parser::{Gate, parse_qasm};
+pub use linalg::hermitian_eigenvalues;
+pub use parser::{Gate, QasmVersion, parse_qasm, parse_qasm3, try_parse_qasm};
 pub use simulator::QuantumSimulator;
-pub use simulator::run_simulation;
-pub use state::StateVector;
+pub use simulator::{run_simulation, run_simulation_shots};
+pub use state::{StateVector, expectation_from_counts};
 
 #[cfg(test)]
 mod tests {
@@ -23,18 +28,30 @@ mod tests {
     #[test]
     fn test_ry_rotation_to_one() {
         let mut simulator = QuantumSimulator::new(1);
-        let mut circuit = Circuit::new();
+        let mut circuit = Circuit::with_qubits(1);
 
         circuit.add_gate(Gate::RY {
             qubit: 0,
             theta: PI,
         });
 
-        simulator.apply_circuit(&circuit);
+        simulator.apply_circuit(&circuit).unwrap();
 
         // State |1> is at index 1
         let prob_1 = simulator.get_probability(1);
 
         assert!((prob_1 - 1.0).abs() < EPSILON);
     }
+
+    #[test]
+    fn test_probability_of_plus_state_is_one_half() {
+        let mut simulator = QuantumSimulator::new(1);
+        let mut circuit = Circuit::with_qubits(1);
+        circuit.add_gate(Gate::H { qubit: 0 });
+
+        simulator.apply_circuit(&circuit).unwrap();
+
+        assert!((simulator.get_probability(0) - 0.5).abs() < EPSILON);
+        assert!((simulator.get_probability(1) - 0.5).abs() < EPSILON);
+    }
 }
@@ -0,0 +1,79 @@
+// src/linalg.rs
+use num_complex::Complex;
+
+/// Eigenvalues of a small Hermitian matrix, in ascending order.
+///
+/// The n x n Hermitian `A = R + iI` is embedded as the real symmetric 2n x 2n matrix
+/// `[[R, -I], [I, R]]`, whose spectrum is that of `A` with every eigenvalue doubled,
+/// and then diagonalized with cyclic Jacobi rotations. This is only meant for
+/// handful-of-qubits matrices, such as reduced density matrices or small Hamiltonians.
+pub fn hermitian_eigenvalues(matrix: &[Vec<Complex<f64>>]) -> Vec<f64> {
+    let n = matrix.len();
+    let m = 2 * n;
+    let mut a = vec![vec![0.0; m]; m];
+    for (i, row) in matrix.iter().enumerate() {
+        assert_eq!(row.len(), n, "matrix must be square");
+        for (j, entry) in row.iter().enumerate() {
+            a[i][j] = entry.re;
+            a[i + n][j + n] = entry.re;
+            a[i][j + n] = -entry.im;
+            a[i + n][j] = entry.im;
+        }
+    }
+
+    for _sweep in 0..100 {
+        let off: f64 = (0..m)
+            .flat_map(|p| (0..m).filter(move |&q| q != p).map(move |q| (p, q)))
+            .map(|(p, q)| a[p][q] * a[p][q])
+            .sum();
+        if off < 1e-24 {
+            break;
+        }
+        for p in 0..m {
+            for q in (p + 1)..m {
+                if a[p][q].abs() < 1e-300 {
+                    continue;
+                }
+                // Choose the rotation angle that zeroes a[p][q].
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                // p < q, so rows p and q can be borrowed mutably together.
+                let (upper, lower) = a.split_at_mut(q);
+                for (apk, aqk) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                    let (x, y) = (*apk, *aqk);
+                    *apk = c * x - s * y;
+                    *aqk = s * x + c * y;
+                }
+            }
+        }
+    }
+
+    let mut doubled: Vec<f64> = (0..m).map(|i| a[i][i]).collect();
+    doubled.sort_by(|x, y| x.total_cmp(y));
+    doubled.into_iter().step_by(2).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hermitian_eigenvalues_with_complex_off_diagonal() {
+        // [[2, i], [-i, 2]] has eigenvalues 1 and 3.
+        let m = vec![
+            vec![Complex::new(2.0, 0.0), Complex::new(0.0, 1.0)],
+            vec![Complex::new(0.0, -1.0), Complex::new(2.0, 0.0)],
+        ];
+        let eigs = hermitian_eigenvalues(&m);
+        assert_eq!(eigs.len(), 2);
+        assert!((eigs[0] - 1.0).abs() < 1e-9, "{:?}", eigs);
+        assert!((eigs[1] - 3.0).abs() < 1e-9, "{:?}", eigs);
+    }
+}
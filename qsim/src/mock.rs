@@ -0,0 +1,144 @@
+// src/mock.rs
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::StateVector;
+use crate::api::Pauli;
+use crate::circuit::{Circuit, circuit_to_qasm};
+use crate::parser::Gate;
+use crate::simulator::Simulator;
+
+/// A `Simulator` test double that returns programmed results instead of simulating.
+///
+/// Gates are recorded rather than applied, so the statevector stays |0..0⟩. The call
+/// counters are shared between clones: runners such as `VqeRunner` prepare every
+/// evaluation on a clone of a template simulator, and the calls stay observable through
+/// the original.
+#[derive(Clone)]
+pub struct MockSimulator {
+    num_qubits: usize,
+    state: StateVector,
+    expectation: f64,
+    samples: HashMap<String, u32>,
+    gates: Vec<Gate>,
+    resets: Arc<AtomicUsize>,
+    expectation_calls: Arc<AtomicUsize>,
+}
+
+impl MockSimulator {
+    /// Every expectation value is 0 and sampling returns no counts until programmed.
+    pub fn new(num_qubits: usize) -> Self {
+        MockSimulator {
+            num_qubits,
+            state: StateVector::new(num_qubits),
+            expectation: 0.0,
+            samples: HashMap::new(),
+            gates: Vec::new(),
+            resets: Arc::new(AtomicUsize::new(0)),
+            expectation_calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The value returned for every Pauli-string expectation.
+    pub fn with_expectation(mut self, value: f64) -> Self {
+        self.expectation = value;
+        self
+    }
+
+    /// The counts returned by [`MockSimulator::sample_counts`].
+    pub fn with_samples(mut self, counts: HashMap<String, u32>) -> Self {
+        self.samples = counts;
+        self
+    }
+
+    /// Returns the programmed counts whatever the number of shots.
+    pub fn sample_counts(&self, _shots: u32) -> HashMap<String, u32> {
+        self.samples.clone()
+    }
+
+    /// The gates applied since the last reset.
+    pub fn gates(&self) -> &[Gate] {
+        &self.gates
+    }
+
+    /// How many times this simulator or any of its clones has been reset.
+    pub fn reset_count(&self) -> usize {
+        self.resets.load(Ordering::SeqCst)
+    }
+
+    /// How many expectation values this simulator or any of its clones has returned.
+    pub fn expectation_count(&self) -> usize {
+        self.expectation_calls.load(Ordering::SeqCst)
+    }
+}
+
+impl Simulator for MockSimulator {
+    fn reset(&mut self) {
+        self.gates.clear();
+        self.resets.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn apply_gate(&mut self, gate: &Gate) {
+        self.gates.push(gate.clone());
+    }
+
+    fn measure_pauli_string_expectation(&mut self, _operators: Vec<Gate>) -> f64 {
+        self.expectation_calls.fetch_add(1, Ordering::SeqCst);
+        self.expectation
+    }
+
+    fn expectation_pauli_string(&self, _ops: &[(Pauli, usize)]) -> f64 {
+        self.expectation_calls.fetch_add(1, Ordering::SeqCst);
+        self.expectation
+    }
+
+    fn get_statevector(&self) -> &StateVector {
+        &self.state
+    }
+
+    fn get_num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    fn compile_to_qasm(&self) -> String {
+        let mut circuit = Circuit::with_qubits(self.num_qubits);
+        for gate in &self.gates {
+            circuit.add_gate(gate.clone());
+        }
+        circuit_to_qasm(&circuit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_records_gates_and_shares_counters_between_clones() {
+        let template = MockSimulator::new(2)
+            .with_expectation(-0.5)
+            .with_samples([("11".to_string(), 7)].into());
+
+        let mut sim = template.clone();
+        sim.reset();
+        sim.apply_gate(&Gate::H { qubit: 0 });
+        sim.apply_gate(&Gate::CX {
+            control: 0,
+            target: 1,
+        });
+        assert_eq!(sim.gates().len(), 2);
+        assert!(sim.compile_to_qasm().contains("qreg q[2];"));
+        assert_eq!(sim.expectation_pauli_string(&[(Pauli::Z, 0)]), -0.5);
+        assert_eq!(sim.sample_counts(100), [("11".to_string(), 7)].into());
+
+        // The recorded gates are per clone, the counters are shared.
+        assert!(template.gates().is_empty());
+        assert_eq!(template.reset_count(), 1);
+        assert_eq!(template.expectation_count(), 1);
+        assert_eq!(
+            template.get_statevector().amplitudes,
+            StateVector::new(2).amplitudes
+        );
+    }
+}
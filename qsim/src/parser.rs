@@ -1,20 +1,84 @@
-use serde::Deserialize;
+use crate::api::SimError;
+use num_complex::Complex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Gate {
-    I { qubit: usize },
-    H { qubit: usize },
-    X { qubit: usize },
-    Y { qubit: usize },
-    Z { qubit: usize },
-    CX { control: usize, target: usize },
-    CNOT { control: usize, target: usize }, // Alias for CX
-    RX { qubit: usize, theta: f64 },        // target and theta
-    RY { qubit: usize, theta: f64 },        // target and theta
-    RZ { qubit: usize, theta: f64 },        // target and theta
+    I {
+        qubit: usize,
+    },
+    H {
+        qubit: usize,
+    },
+    X {
+        qubit: usize,
+    },
+    Y {
+        qubit: usize,
+    },
+    Z {
+        qubit: usize,
+    },
+    CX {
+        control: usize,
+        target: usize,
+    },
+    /// Alias for `CX`.
+    CNOT {
+        control: usize,
+        target: usize,
+    },
+    /// Rotates `qubit` about the X axis by `theta`.
+    RX {
+        qubit: usize,
+        theta: f64,
+    },
+    /// Rotates `qubit` about the Y axis by `theta`.
+    RY {
+        qubit: usize,
+        theta: f64,
+    },
+    /// Rotates `qubit` about the Z axis by `theta`.
+    RZ {
+        qubit: usize,
+        theta: f64,
+    },
+    /// An arbitrary 2^k x 2^k unitary on `qubits`; `qubits[0]` is the least significant bit
+    /// of the matrix index.
+    Unitary {
+        qubits: Vec<usize>,
+        matrix: Vec<Vec<Complex<f64>>>,
+    },
+    /// Controlled phase: multiplies |11⟩ on (control, target) by e^{i theta}.
+    CP {
+        control: usize,
+        target: usize,
+        theta: f64,
+    },
+    /// Multiplies the whole state by the global phase e^{i theta}.
+    GPhase {
+        theta: f64,
+    },
+    /// Collapses the whole register.
     Measure,
+    /// Measures one qubit in Z into classical bit `creg_bit`; the rest of the circuit continues.
+    MeasureQubit {
+        qubit: usize,
+        creg_bit: usize,
+    },
+    /// An optimization fence across `qubits`. It has no effect on the state, but keeps
+    /// gates from being moved across it and is preserved in QASM output.
+    Barrier {
+        qubits: Vec<usize>,
+    },
+    /// Returns `qubit` to |0⟩: it is measured in Z and flipped back if the outcome was 1.
+    Reset {
+        qubit: usize,
+    },
 }
 
 impl Display for Gate {
@@ -31,11 +95,253 @@ impl Display for Gate {
             Gate::RX { qubit, theta } => write!(f, "RX q[{}],{}", qubit, theta),
             Gate::RY { qubit, theta } => write!(f, "RY q[{}],{}", qubit, theta),
             Gate::RZ { qubit, theta } => write!(f, "RZ q[{}],{}", qubit, theta),
+            Gate::Unitary { qubits, .. } => {
+                let qargs: Vec<String> = qubits.iter().map(|q| format!("q[{}]", q)).collect();
+                write!(f, "U {}", qargs.join(","))
+            }
+            Gate::CP {
+                control,
+                target,
+                theta,
+            } => write!(f, "CP q[{}],q[{}],{}", control, target, theta),
+            Gate::GPhase { theta } => write!(f, "GPhase {}", theta),
             Gate::Measure => write!(f, "Measure"),
+            Gate::MeasureQubit { qubit, creg_bit } => {
+                write!(f, "Measure q[{}] -> c[{}]", qubit, creg_bit)
+            }
+            Gate::Barrier { qubits } => {
+                let qargs: Vec<String> = qubits.iter().map(|q| format!("q[{}]", q)).collect();
+                write!(f, "Barrier {}", qargs.join(","))
+            }
+            Gate::Reset { qubit } => write!(f, "Reset q[{}]", qubit),
+        }
+    }
+}
+
+/// The bit pattern gates compare and hash angles by: -0.0 is folded into 0.0 and every NaN
+/// into a single NaN, so that equality is reflexive and agrees with hashing.
+fn canonical_bits(value: f64) -> u64 {
+    if value == 0.0 {
+        0.0f64.to_bits()
+    } else if value.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        value.to_bits()
+    }
+}
+
+fn hash_f64<H: Hasher>(value: f64, state: &mut H) {
+    canonical_bits(value).hash(state);
+}
+
+impl PartialEq for Gate {
+    fn eq(&self, other: &Self) -> bool {
+        let same = |a: &f64, b: &f64| canonical_bits(*a) == canonical_bits(*b);
+        match (self, other) {
+            (Gate::I { qubit: a }, Gate::I { qubit: b })
+            | (Gate::H { qubit: a }, Gate::H { qubit: b })
+            | (Gate::X { qubit: a }, Gate::X { qubit: b })
+            | (Gate::Y { qubit: a }, Gate::Y { qubit: b })
+            | (Gate::Z { qubit: a }, Gate::Z { qubit: b })
+            | (Gate::Reset { qubit: a }, Gate::Reset { qubit: b }) => a == b,
+            (
+                Gate::CX {
+                    control: c1,
+                    target: t1,
+                },
+                Gate::CX {
+                    control: c2,
+                    target: t2,
+                },
+            )
+            | (
+                Gate::CNOT {
+                    control: c1,
+                    target: t1,
+                },
+                Gate::CNOT {
+                    control: c2,
+                    target: t2,
+                },
+            ) => c1 == c2 && t1 == t2,
+            (
+                Gate::RX {
+                    qubit: q1,
+                    theta: a,
+                },
+                Gate::RX {
+                    qubit: q2,
+                    theta: b,
+                },
+            )
+            | (
+                Gate::RY {
+                    qubit: q1,
+                    theta: a,
+                },
+                Gate::RY {
+                    qubit: q2,
+                    theta: b,
+                },
+            )
+            | (
+                Gate::RZ {
+                    qubit: q1,
+                    theta: a,
+                },
+                Gate::RZ {
+                    qubit: q2,
+                    theta: b,
+                },
+            ) => q1 == q2 && same(a, b),
+            (
+                Gate::Unitary {
+                    qubits: q1,
+                    matrix: m1,
+                },
+                Gate::Unitary {
+                    qubits: q2,
+                    matrix: m2,
+                },
+            ) => {
+                q1 == q2
+                    && m1.len() == m2.len()
+                    && m1.iter().zip(m2).all(|(r1, r2)| {
+                        r1.len() == r2.len()
+                            && r1
+                                .iter()
+                                .zip(r2)
+                                .all(|(x, y)| same(&x.re, &y.re) && same(&x.im, &y.im))
+                    })
+            }
+            (
+                Gate::CP {
+                    control: c1,
+                    target: t1,
+                    theta: a,
+                },
+                Gate::CP {
+                    control: c2,
+                    target: t2,
+                    theta: b,
+                },
+            ) => c1 == c2 && t1 == t2 && same(a, b),
+            (Gate::GPhase { theta: a }, Gate::GPhase { theta: b }) => same(a, b),
+            (Gate::Measure, Gate::Measure) => true,
+            (
+                Gate::MeasureQubit {
+                    qubit: q1,
+                    creg_bit: c1,
+                },
+                Gate::MeasureQubit {
+                    qubit: q2,
+                    creg_bit: c2,
+                },
+            ) => q1 == q2 && c1 == c2,
+            (Gate::Barrier { qubits: a }, Gate::Barrier { qubits: b }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Gate {}
+
+impl Hash for Gate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Gate::I { qubit }
+            | Gate::H { qubit }
+            | Gate::X { qubit }
+            | Gate::Y { qubit }
+            | Gate::Z { qubit } => qubit.hash(state),
+            Gate::CX { control, target } | Gate::CNOT { control, target } => {
+                control.hash(state);
+                target.hash(state);
+            }
+            Gate::RX { qubit, theta } | Gate::RY { qubit, theta } | Gate::RZ { qubit, theta } => {
+                qubit.hash(state);
+                hash_f64(*theta, state);
+            }
+            Gate::Unitary { qubits, matrix } => {
+                qubits.hash(state);
+                for entry in matrix.iter().flatten() {
+                    hash_f64(entry.re, state);
+                    hash_f64(entry.im, state);
+                }
+            }
+            Gate::CP {
+                control,
+                target,
+                theta,
+            } => {
+                control.hash(state);
+                target.hash(state);
+                hash_f64(*theta, state);
+            }
+            Gate::GPhase { theta } => hash_f64(*theta, state),
+            Gate::Measure => {}
+            Gate::MeasureQubit { qubit, creg_bit } => {
+                qubit.hash(state);
+                creg_bit.hash(state);
+            }
+            Gate::Barrier { qubits } => qubits.hash(state),
+            Gate::Reset { qubit } => qubit.hash(state),
         }
     }
 }
 
+/// Shorthand constructors, e.g. `Gate::cx(0, 1)` for `Gate::CX { control: 0, target: 1 }`.
+impl Gate {
+    pub fn h(qubit: usize) -> Self {
+        Gate::H { qubit }
+    }
+
+    pub fn x(qubit: usize) -> Self {
+        Gate::X { qubit }
+    }
+
+    pub fn y(qubit: usize) -> Self {
+        Gate::Y { qubit }
+    }
+
+    pub fn z(qubit: usize) -> Self {
+        Gate::Z { qubit }
+    }
+
+    pub fn cx(control: usize, target: usize) -> Self {
+        Gate::CX { control, target }
+    }
+
+    pub fn cp(control: usize, target: usize, theta: f64) -> Self {
+        Gate::CP {
+            control,
+            target,
+            theta,
+        }
+    }
+
+    pub fn rx(qubit: usize, theta: f64) -> Self {
+        Gate::RX { qubit, theta }
+    }
+
+    pub fn ry(qubit: usize, theta: f64) -> Self {
+        Gate::RY { qubit, theta }
+    }
+
+    pub fn rz(qubit: usize, theta: f64) -> Self {
+        Gate::RZ { qubit, theta }
+    }
+
+    pub fn measure_qubit(qubit: usize, creg_bit: usize) -> Self {
+        Gate::MeasureQubit { qubit, creg_bit }
+    }
+
+    pub fn reset(qubit: usize) -> Self {
+        Gate::Reset { qubit }
+    }
+}
+
 impl Gate {
     pub fn target(&self) -> Vec<usize> {
         match self {
@@ -45,16 +351,76 @@ impl Gate {
             | Gate::H { qubit }
             | Gate::RX { qubit, .. }
             | Gate::RY { qubit, .. }
-            | Gate::RZ { qubit, .. } => vec![*qubit],
-            Gate::CX { target, .. } | Gate::CNOT { target, .. } => vec![*target],
+            | Gate::RZ { qubit, .. }
+            | Gate::MeasureQubit { qubit, .. }
+            | Gate::Reset { qubit } => vec![*qubit],
+            Gate::CX { target, .. } | Gate::CNOT { target, .. } | Gate::CP { target, .. } => {
+                vec![*target]
+            }
+            Gate::Unitary { qubits, .. } | Gate::Barrier { qubits } => qubits.clone(),
 
             _ => vec![],
         }
     }
+
+    /// The adjoint of this gate. Measurements have no inverse and are rejected.
+    pub fn inverse(&self) -> Result<Gate, SimError> {
+        let inverse = match self {
+            Gate::I { .. }
+            | Gate::H { .. }
+            | Gate::X { .. }
+            | Gate::Y { .. }
+            | Gate::Z { .. }
+            | Gate::CX { .. }
+            | Gate::CNOT { .. }
+            | Gate::Barrier { .. } => self.clone(),
+            Gate::RX { qubit, theta } => Gate::rx(*qubit, -theta),
+            Gate::RY { qubit, theta } => Gate::ry(*qubit, -theta),
+            Gate::RZ { qubit, theta } => Gate::rz(*qubit, -theta),
+            Gate::CP {
+                control,
+                target,
+                theta,
+            } => Gate::cp(*control, *target, -theta),
+            Gate::GPhase { theta } => Gate::GPhase { theta: -theta },
+            Gate::Unitary { qubits, matrix } => Gate::Unitary {
+                qubits: qubits.clone(),
+                matrix: (0..matrix.len())
+                    .map(|i| matrix.iter().map(|row| row[i].conj()).collect())
+                    .collect(),
+            },
+            Gate::Measure | Gate::MeasureQubit { .. } | Gate::Reset { .. } => {
+                return Err(SimError::NotInvertible(self.to_string()));
+            }
+        };
+        Ok(inverse)
+    }
 }
 
 pub fn parse_qasm(qasm_str: &str) -> (usize, Vec<Gate>) {
+    let (num_qubits, gates, _) = parse_qasm2(qasm_str);
+    (num_qubits, gates)
+}
+
+/// Like `parse_qasm`, but a gate whose operands cannot be resolved, e.g. because an index is
+/// past the end of its register, or a call of a user-defined gate that cannot be expanded is
+/// an error instead of being skipped.
+pub fn try_parse_qasm(qasm_str: &str) -> Result<(usize, Vec<Gate>), SimError> {
+    let (num_qubits, gates, rejected) = parse_qasm2(qasm_str);
+    match rejected.into_iter().next() {
+        Some(message) => Err(SimError::Qasm(message)),
+        None => Ok((num_qubits, gates)),
+    }
+}
+
+/// Parses an OpenQASM 2.0 program, also returning why each skipped gate statement was
+/// skipped: an operand that could not be resolved or a gate call that could not be expanded.
+fn parse_qasm2(qasm_str: &str) -> (usize, Vec<Gate>, Vec<String>) {
+    let (expanded, mut rejected) = expand_gate_definitions(qasm_str);
+    let qasm_str = expanded.as_str();
     let mut num_qubits = 0;
+    // Register name -> (offset, size); registers are laid out in declaration order.
+    let mut registers: HashMap<String, (usize, usize)> = HashMap::new();
     let mut gates = Vec::new();
     let mut has_measured = false; // Flag to ensure we only measure once.
 
@@ -68,72 +434,638 @@ pub fn parse_qasm(qasm_str: &str) -> (usize, Vec<Gate>) {
             continue;
         }
 
-        if trimmed_line.starts_with("qreg") {
-            if let Some(start) = trimmed_line.find('[') {
-                if let Some(end) = trimmed_line.find(']') {
-                    if let Ok(n) = trimmed_line[start + 1..end].parse::<usize>() {
-                        num_qubits = n;
-                    }
-                }
+        if let Some(decl) = trimmed_line.strip_prefix("qreg") {
+            if let Some((name, rest)) = decl.split_once('[')
+                && let Some((size, _)) = rest.split_once(']')
+                && let Ok(n) = size.trim().parse::<usize>()
+            {
+                registers.insert(name.trim().to_string(), (num_qubits, n));
+                num_qubits += n;
             }
         }
         // Explicitly ignore classical register declarations.
         else if trimmed_line.starts_with("creg") {
             continue;
-        } else if trimmed_line.starts_with("h ") {
-            if let Some(start) = trimmed_line.find('[') {
-                if let Some(end) = trimmed_line.find(']') {
-                    if let Ok(q) = trimmed_line[start + 1..end].parse::<usize>() {
-                        gates.push(Gate::H { qubit: q });
-                    }
-                }
+        } else if let Some(operand) = trimmed_line.strip_prefix("h ") {
+            match resolve_qubit(operand, &registers) {
+                Some(q) => gates.push(Gate::H { qubit: q }),
+                None => rejected.push(format!("invalid operand in: {}", trimmed_line)),
             }
-        } else if trimmed_line.starts_with("x ") {
-            if let Some(start) = trimmed_line.find('[') {
-                if let Some(end) = trimmed_line.find(']') {
-                    if let Ok(q) = trimmed_line[start + 1..end].parse::<usize>() {
-                        gates.push(Gate::X { qubit: q });
-                    }
-                }
+        } else if let Some(operand) = trimmed_line.strip_prefix("x ") {
+            match resolve_qubit(operand, &registers) {
+                Some(q) => gates.push(Gate::X { qubit: q }),
+                None => rejected.push(format!("invalid operand in: {}", trimmed_line)),
             }
-        } else if trimmed_line.starts_with("y ") {
-            if let Some(start) = trimmed_line.find('[') {
-                if let Some(end) = trimmed_line.find(']') {
-                    if let Ok(q) = trimmed_line[start + 1..end].parse::<usize>() {
-                        gates.push(Gate::Y { qubit: q });
-                    }
-                }
+        } else if let Some(operand) = trimmed_line.strip_prefix("y ") {
+            match resolve_qubit(operand, &registers) {
+                Some(q) => gates.push(Gate::Y { qubit: q }),
+                None => rejected.push(format!("invalid operand in: {}", trimmed_line)),
             }
-        } else if trimmed_line.starts_with("z ") {
-            if let Some(start) = trimmed_line.find('[') {
-                if let Some(end) = trimmed_line.find(']') {
-                    if let Ok(q) = trimmed_line[start + 1..end].parse::<usize>() {
-                        gates.push(Gate::Z { qubit: q });
-                    }
-                }
+        } else if let Some(operand) = trimmed_line.strip_prefix("z ") {
+            match resolve_qubit(operand, &registers) {
+                Some(q) => gates.push(Gate::Z { qubit: q }),
+                None => rejected.push(format!("invalid operand in: {}", trimmed_line)),
             }
-        } else if trimmed_line.starts_with("cx ") {
-            let clean_line = trimmed_line.trim_end_matches(';');
-            let parts: Vec<&str> = clean_line
-                .split(&[' ', ',', '[', ']'][..])
-                .filter(|s| !s.is_empty())
+        } else if let Some(operands) = trimmed_line.strip_prefix("cx ") {
+            let qubits: Option<Vec<usize>> = operands
+                .trim_end_matches(';')
+                .split(',')
+                .map(|o| resolve_qubit(o, &registers))
                 .collect();
-            if parts.len() == 5 && parts[0] == "cx" && parts[1] == "q" && parts[3] == "q" {
-                if let (Ok(c), Ok(t)) = (parts[2].parse::<usize>(), parts[4].parse::<usize>()) {
-                    gates.push(Gate::CX {
-                        control: c,
-                        target: t,
-                    });
+            match qubits.as_deref() {
+                Some(&[c, t]) => gates.push(Gate::CX {
+                    control: c,
+                    target: t,
+                }),
+                _ => rejected.push(format!("invalid operand in: {}", trimmed_line)),
+            }
+        } else if let Some((name, rest)) = trimmed_line.split_once('(')
+            && matches!(name, "rx" | "ry" | "rz")
+        {
+            if let Some((angle, operand)) = rest.split_once(')')
+                && let Some(theta) = parse_angle(angle)
+                && let Some(qubit) = resolve_qubit(operand, &registers)
+            {
+                gates.push(match name {
+                    "rx" => Gate::RX { qubit, theta },
+                    "ry" => Gate::RY { qubit, theta },
+                    _ => Gate::RZ { qubit, theta },
+                });
+            } else {
+                rejected.push(format!("invalid operand in: {}", trimmed_line));
+            }
+        } else if trimmed_line.starts_with("cp(") || trimmed_line.starts_with("cu1(") {
+            match parse_controlled_phase(trimmed_line, &registers) {
+                Some((theta, c, t)) => gates.push(Gate::CP {
+                    control: c,
+                    target: t,
+                    theta,
+                }),
+                None => rejected.push(format!("invalid operand in: {}", trimmed_line)),
+            }
+        } else if let Some(operands) = trimmed_line.strip_prefix("barrier") {
+            match resolve_operands(operands, &registers) {
+                Some(qubits) => gates.push(Gate::Barrier { qubits }),
+                None => rejected.push(format!("invalid operand in: {}", trimmed_line)),
+            }
+        } else if let Some(operands) = trimmed_line.strip_prefix("reset ") {
+            match resolve_operands(operands, &registers) {
+                Some(qubits) => gates.extend(qubits.into_iter().map(Gate::reset)),
+                None => rejected.push(format!("invalid operand in: {}", trimmed_line)),
+            }
+        } else if let Some(operands) = trimmed_line.strip_prefix("measure") {
+            let source = operands.split("->").next().unwrap_or_default();
+            if !source.contains('[') {
+                if !has_measured {
+                    gates.push(Gate::Measure);
+                    has_measured = true;
                 }
+            } else if let Some((q, c)) = parse_single_measure(trimmed_line, &registers) {
+                gates.push(Gate::MeasureQubit {
+                    qubit: q,
+                    creg_bit: c,
+                });
+            } else {
+                rejected.push(format!("invalid operand in: {}", trimmed_line));
             }
-        } else if trimmed_line.starts_with("measure") {
-            if !has_measured {
+        }
+    }
+    (num_qubits, gates, rejected)
+}
+
+/// Maps an operand such as `b[1]` to its global qubit index using the declared registers.
+/// Undeclared register names fall back to the bare index, as the parser always did. An index
+/// past the end of its register resolves to nothing, since offsetting it would silently
+/// address a qubit of the next register.
+fn resolve_qubit(operand: &str, registers: &HashMap<String, (usize, usize)>) -> Option<usize> {
+    let operand = operand.trim().trim_end_matches(';');
+    let (name, rest) = operand.split_once('[')?;
+    let index = rest.split_once(']')?.0.trim().parse::<usize>().ok()?;
+    match registers.get(name.trim()) {
+        Some(&(offset, size)) => (index < size).then_some(offset + index),
+        None => Some(index),
+    }
+}
+
+/// Expands a comma-separated operand list such as `a[0],b` into global qubit indices,
+/// where a bare register name stands for all of its qubits. Returns `None` if any operand
+/// cannot be resolved.
+fn resolve_operands(
+    operands: &str,
+    registers: &HashMap<String, (usize, usize)>,
+) -> Option<Vec<usize>> {
+    let mut qubits = Vec::new();
+    for operand in operands.trim().trim_end_matches(';').split(',') {
+        match registers.get(operand.trim()) {
+            Some(&(offset, size)) => qubits.extend(offset..offset + size),
+            None => qubits.push(resolve_qubit(operand, registers)?),
+        }
+    }
+    Some(qubits)
+}
+
+/// The OpenQASM language version of a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QasmVersion {
+    V2,
+    V3,
+}
+
+impl QasmVersion {
+    /// Reads the version from the `OPENQASM` header. Without a header, a `qubit`
+    /// declaration marks the program as 3.0; anything else is treated as 2.0.
+    pub fn detect(src: &str) -> Self {
+        let mut lines = src
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with("//"));
+        match lines
+            .clone()
+            .next()
+            .and_then(|l| l.strip_prefix("OPENQASM"))
+        {
+            Some(version) if version.trim().starts_with('3') => QasmVersion::V3,
+            Some(_) => QasmVersion::V2,
+            None if lines.any(|l| l.starts_with("qubit")) => QasmVersion::V3,
+            None => QasmVersion::V2,
+        }
+    }
+}
+
+/// Parses an OpenQASM 3.0 program. Supports `qubit[n] q;` and `bit[n] c;` declarations
+/// (one qubit register), the standard single-qubit and rotation gates, `cx`/`cz`/`cp`,
+/// `ctrl @` on x, z and p, `gphase`, `barrier`, `reset`, and both `c = measure q;` and
+/// `c[j] = measure q[i];`.
+/// Unlike `parse_qasm`, anything it does not understand is an error.
+pub fn parse_qasm3(src: &str) -> Result<(usize, Vec<Gate>), SimError> {
+    let code: Vec<&str> = src
+        .lines()
+        .map(|l| l.split("//").next().unwrap_or(""))
+        .collect();
+    let (expanded, errors) = expand_gate_definitions(&code.join("\n"));
+    if let Some(e) = errors.into_iter().next() {
+        return Err(SimError::Qasm(e));
+    }
+
+    let mut register: Option<(String, usize)> = None;
+    let mut gates = Vec::new();
+    let mut has_measured = false;
+
+    for statement in expanded.split(';') {
+        let statement = statement
+            .replace('@', " @ ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if statement.is_empty()
+            || statement.starts_with("OPENQASM")
+            || statement.starts_with("include")
+            || statement.starts_with("bit")
+            || statement.starts_with("creg")
+        {
+            continue;
+        }
+
+        if let Some(decl) = statement
+            .strip_prefix("qubit")
+            .or_else(|| statement.strip_prefix("qreg"))
+        {
+            if register.is_some() {
+                return Err(SimError::Qasm(
+                    "only one qubit register is supported".to_string(),
+                ));
+            }
+            register = Some(parse_qasm3_register(decl)?);
+            continue;
+        }
+
+        if statement.contains("measure") {
+            let (source, target) = match statement.split_once('=') {
+                Some((target, source)) => (source.trim(), Some(target.trim())),
+                None => match statement.split_once("->") {
+                    Some((source, target)) => (source.trim(), Some(target.trim())),
+                    None => (statement.as_str(), None),
+                },
+            };
+            let source = source
+                .strip_prefix("measure")
+                .ok_or_else(|| SimError::Qasm(format!("malformed measurement: {}", statement)))?
+                .trim();
+            if source.contains('[') {
+                let qubit = parse_qasm3_qubit(source, &register)?;
+                let creg_bit = match target {
+                    Some(target) => bracket_index(target)
+                        .ok_or_else(|| SimError::Qasm(format!("malformed bit: {}", target)))?,
+                    None => qubit,
+                };
+                gates.push(Gate::MeasureQubit { qubit, creg_bit });
+            } else if has_measured {
+                return Err(SimError::Qasm(format!(
+                    "register measured more than once: {}",
+                    statement
+                )));
+            } else {
                 gates.push(Gate::Measure);
                 has_measured = true;
             }
+            continue;
+        }
+
+        let (keyword, operands) = statement.split_once(' ').unwrap_or((&statement, ""));
+        if keyword == "barrier" || keyword == "reset" {
+            let qubits = parse_qasm3_operands(operands, &register)?;
+            if keyword == "barrier" {
+                gates.push(Gate::Barrier { qubits });
+            } else {
+                gates.extend(qubits.into_iter().map(Gate::reset));
+            }
+            continue;
         }
+
+        let (controlled, call) = match statement.strip_prefix("ctrl @ ") {
+            Some(rest) => (true, rest),
+            None => (false, statement.as_str()),
+        };
+        let (name, param, operands) = match call.find('(') {
+            Some(open) if !call[..open].contains(' ') => {
+                let close = call
+                    .find(')')
+                    .ok_or_else(|| SimError::Qasm(format!("unclosed parameter: {}", statement)))?;
+                let param = parse_angle(&call[open + 1..close])
+                    .ok_or_else(|| SimError::Qasm(format!("invalid angle: {}", statement)))?;
+                (&call[..open], Some(param), &call[close + 1..])
+            }
+            _ => match call.split_once(' ') {
+                Some((name, operands)) => (name, None, operands),
+                None => (call, None, ""),
+            },
+        };
+        let qubits = operands
+            .split(',')
+            .map(str::trim)
+            .filter(|o| !o.is_empty())
+            .map(|o| parse_qasm3_qubit(o, &register))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let gate = match (controlled, name, param, &qubits[..]) {
+            (false, "id", None, &[qubit]) => Gate::I { qubit },
+            (false, "h", None, &[qubit]) => Gate::H { qubit },
+            (false, "x", None, &[qubit]) => Gate::X { qubit },
+            (false, "y", None, &[qubit]) => Gate::Y { qubit },
+            (false, "z", None, &[qubit]) => Gate::Z { qubit },
+            (false, "rx", Some(theta), &[qubit]) => Gate::RX { qubit, theta },
+            (false, "ry", Some(theta), &[qubit]) => Gate::RY { qubit, theta },
+            (false, "rz", Some(theta), &[qubit]) => Gate::RZ { qubit, theta },
+            (false, "cx" | "CX", None, &[control, target])
+            | (true, "x", None, &[control, target]) => Gate::CX { control, target },
+            (false, "cz", None, &[control, target]) | (true, "z", None, &[control, target]) => {
+                Gate::CP {
+                    control,
+                    target,
+                    theta: std::f64::consts::PI,
+                }
+            }
+            (false, "cp" | "cphase", Some(theta), &[control, target])
+            | (true, "p" | "phase", Some(theta), &[control, target]) => Gate::CP {
+                control,
+                target,
+                theta,
+            },
+            (false, "gphase", Some(theta), &[]) => Gate::GPhase { theta },
+            _ => {
+                return Err(SimError::Qasm(format!(
+                    "unsupported statement: {}",
+                    statement
+                )));
+            }
+        };
+        gates.push(gate);
     }
-    (num_qubits, gates)
+
+    match register {
+        Some((_, num_qubits)) => Ok((num_qubits, gates)),
+        None => Err(SimError::Qasm("no qubit declaration found".to_string())),
+    }
+}
+
+/// Parses the rest of a `qubit[n] name` or `qubit name` declaration into `(name, n)`.
+/// The legacy `qreg name[n]` form is accepted too.
+fn parse_qasm3_register(decl: &str) -> Result<(String, usize), SimError> {
+    let decl = decl.trim();
+    let invalid = || SimError::Qasm(format!("invalid qubit declaration: {}", decl));
+    if let Some(rest) = decl.strip_prefix('[') {
+        let (size, name) = rest.split_once(']').ok_or_else(invalid)?;
+        let size = size.trim().parse::<usize>().map_err(|_| invalid())?;
+        return Ok((name.trim().to_string(), size));
+    }
+    match decl.split_once('[') {
+        Some((name, _)) => Ok((
+            name.trim().to_string(),
+            bracket_index(decl).ok_or_else(invalid)?,
+        )),
+        None if !decl.is_empty() => Ok((decl.to_string(), 1)),
+        None => Err(invalid()),
+    }
+}
+
+/// Resolves the operands of `barrier` or `reset`, where the register name or an empty
+/// operand list stands for every qubit.
+fn parse_qasm3_operands(
+    operands: &str,
+    register: &Option<(String, usize)>,
+) -> Result<Vec<usize>, SimError> {
+    let whole_register = |operand: &str| match register {
+        Some((name, size)) if operand.is_empty() || operand == name => Some(0..*size),
+        _ => None,
+    };
+    let mut qubits = Vec::new();
+    for operand in operands.split(',').map(str::trim) {
+        match whole_register(operand) {
+            Some(all) => qubits.extend(all),
+            None => qubits.push(parse_qasm3_qubit(operand, register)?),
+        }
+    }
+    Ok(qubits)
+}
+
+/// Resolves a qubit operand such as `q[1]`, or `q` for a single-qubit register.
+fn parse_qasm3_qubit(operand: &str, register: &Option<(String, usize)>) -> Result<usize, SimError> {
+    let (name, size) = register
+        .as_ref()
+        .ok_or_else(|| SimError::Qasm(format!("qubit {} used before declaration", operand)))?;
+    let (operand_name, index) = match operand.split_once('[') {
+        Some((operand_name, _)) => (
+            operand_name.trim(),
+            bracket_index(operand)
+                .ok_or_else(|| SimError::Qasm(format!("malformed qubit: {}", operand)))?,
+        ),
+        None if *size == 1 => (operand, 0),
+        None => {
+            return Err(SimError::Qasm(format!(
+                "register {} used where a single qubit is expected",
+                operand
+            )));
+        }
+    };
+    if operand_name != name {
+        return Err(SimError::Qasm(format!(
+            "unknown register: {}",
+            operand_name
+        )));
+    }
+    if index >= *size {
+        return Err(SimError::Qubit(index));
+    }
+    Ok(index)
+}
+
+/// The number between the first `[` and `]` in `s`.
+fn bracket_index(s: &str) -> Option<usize> {
+    let start = s.find('[')?;
+    let end = s.find(']')?;
+    s[start + 1..end].trim().parse::<usize>().ok()
+}
+
+/// A user-defined `gate name(params) qargs { body }` from the QASM source.
+struct QasmGateDef {
+    params: Vec<String>,
+    qargs: Vec<String>,
+    body: Vec<String>,
+}
+
+/// Macro nesting depth after which expansion gives up, to stop self-recursive definitions.
+const MAX_GATE_EXPANSION_DEPTH: usize = 32;
+
+/// Removes `gate ... { ... }` definitions from the source and inlines their bodies at each
+/// call site, substituting parameters and qubit arguments. The result has one statement per
+/// line so the line-based parser can read it. Sources without definitions are returned as-is.
+/// Calls that cannot be expanded are left out and reported in the returned errors.
+fn expand_gate_definitions(qasm_str: &str) -> (String, Vec<String>) {
+    if !qasm_str
+        .lines()
+        .any(|l| l.trim_start().starts_with("gate "))
+    {
+        return (qasm_str.to_string(), Vec::new());
+    }
+
+    // Split into statements, keeping a gate definition's braces and body in one statement.
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    for line in qasm_str.lines() {
+        let code = line.split("//").next().unwrap_or("");
+        for c in code.chars().chain(std::iter::once(' ')) {
+            current.push(c);
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        statements.push(std::mem::take(&mut current));
+                    }
+                }
+                ';' if depth == 0 => statements.push(std::mem::take(&mut current)),
+                _ => {}
+            }
+        }
+    }
+    if !current.trim().is_empty() {
+        statements.push(current);
+    }
+
+    let mut macros: HashMap<String, QasmGateDef> = HashMap::new();
+    let mut output = String::new();
+    let mut errors = Vec::new();
+    for statement in statements {
+        let statement = statement.trim().trim_end_matches(';').trim();
+        if statement.is_empty() {
+            continue;
+        }
+        if let Some(def) = statement.strip_prefix("gate ") {
+            if let Some((name, gate_def)) = parse_gate_definition(def) {
+                macros.insert(name, gate_def);
+            }
+            continue;
+        }
+        match expand_gate_call(statement, &macros, 0) {
+            Ok(expanded) => {
+                for line in expanded {
+                    output.push_str(&line);
+                    output.push_str(";\n");
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    (output, errors)
+}
+
+/// Parses the text after `gate ` into the gate's name and definition.
+fn parse_gate_definition(def: &str) -> Option<(String, QasmGateDef)> {
+    let (header, rest) = def.split_once('{')?;
+    let body = rest.trim_end().strip_suffix('}')?;
+
+    let header = header.trim();
+    let (name, params, qargs) = match header.find('(') {
+        Some(open) => {
+            let close = header.find(')')?;
+            (
+                &header[..open],
+                split_args(&header[open + 1..close]),
+                &header[close + 1..],
+            )
+        }
+        None => {
+            let (name, qargs) = header.split_once(char::is_whitespace)?;
+            (name, Vec::new(), qargs)
+        }
+    };
+
+    Some((
+        name.trim().to_string(),
+        QasmGateDef {
+            params,
+            qargs: split_args(qargs),
+            body: body
+                .split(';')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+        },
+    ))
+}
+
+fn split_args(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
+
+/// Expands `statement` if it calls a user-defined gate, recursively; otherwise returns it unchanged.
+/// Fails on a call with the wrong number of arguments, an unclosed parameter list, or nesting
+/// deeper than `MAX_GATE_EXPANSION_DEPTH`.
+fn expand_gate_call(
+    statement: &str,
+    macros: &HashMap<String, QasmGateDef>,
+    depth: usize,
+) -> Result<Vec<String>, String> {
+    let name_end = statement
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(statement.len());
+    let name = &statement[..name_end];
+    let Some(gate_def) = macros.get(name) else {
+        return Ok(vec![statement.to_string()]);
+    };
+    if depth >= MAX_GATE_EXPANSION_DEPTH {
+        return Err(format!(
+            "gate {} nests deeper than {} levels",
+            name, MAX_GATE_EXPANSION_DEPTH
+        ));
+    }
+
+    let rest = statement[name_end..].trim_start();
+    let (args, qargs) = match rest.strip_prefix('(') {
+        Some(after) => match after.split_once(')') {
+            Some((args, qargs)) => (split_args(args), split_args(qargs)),
+            None => return Err(format!("unclosed parameter list: {}", statement)),
+        },
+        None => (Vec::new(), split_args(rest)),
+    };
+    if args.len() != gate_def.params.len() || qargs.len() != gate_def.qargs.len() {
+        return Err(format!(
+            "gate {} takes {} parameters and {} qubits: {}",
+            name,
+            gate_def.params.len(),
+            gate_def.qargs.len(),
+            statement
+        ));
+    }
+
+    let bindings: HashMap<&str, &str> = gate_def
+        .params
+        .iter()
+        .zip(&args)
+        .chain(gate_def.qargs.iter().zip(&qargs))
+        .map(|(formal, actual)| (formal.as_str(), actual.as_str()))
+        .collect();
+    let mut expanded = Vec::new();
+    for line in &gate_def.body {
+        expanded.extend(expand_gate_call(
+            &substitute_identifiers(line, &bindings),
+            macros,
+            depth + 1,
+        )?);
+    }
+    Ok(expanded)
+}
+
+/// Replaces whole identifiers in `s` that appear in `bindings`.
+fn substitute_identifiers(s: &str, bindings: &HashMap<&str, &str>) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut ident = String::new();
+    for c in s.chars().chain(std::iter::once('\0')) {
+        if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+            continue;
+        }
+        if !ident.is_empty() {
+            out.push_str(bindings.get(ident.as_str()).copied().unwrap_or(&ident));
+            ident.clear();
+        }
+        if c != '\0' {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Parses `cp(angle) q[c],q[t];` (or `cu1`) into `(angle, c, t)` with global qubit indices.
+fn parse_controlled_phase(
+    line: &str,
+    registers: &HashMap<String, (usize, usize)>,
+) -> Option<(f64, usize, usize)> {
+    let open = line.find('(')?;
+    let close = line.find(')')?;
+    let theta = parse_angle(&line[open + 1..close])?;
+    let qargs: Vec<usize> = line[close + 1..]
+        .trim_end_matches(';')
+        .split(',')
+        .map(|q| resolve_qubit(q, registers))
+        .collect::<Option<_>>()?;
+    match qargs[..] {
+        [c, t] => Some((theta, c, t)),
+        _ => None,
+    }
+}
+
+/// Parses a QASM angle: a number, `pi`, or a simple product/quotient such as `-pi/2` or `3*pi/4`.
+fn parse_angle(expr: &str) -> Option<f64> {
+    let expr = expr.trim();
+    if let Some(rest) = expr.strip_prefix('-') {
+        return parse_angle(rest).map(|v| -v);
+    }
+    if let Some((num, den)) = expr.rsplit_once('/') {
+        return Some(parse_angle(num)? / parse_angle(den)?);
+    }
+    if let Some((a, b)) = expr.split_once('*') {
+        return Some(parse_angle(a)? * parse_angle(b)?);
+    }
+    match expr {
+        "pi" => Some(std::f64::consts::PI),
+        _ => expr.parse::<f64>().ok(),
+    }
+}
+
+/// Parses `measure q[i] -> c[j];` into `(i, j)`, with `i` a global qubit index.
+/// Whole-register measurements return `None`.
+fn parse_single_measure(
+    line: &str,
+    registers: &HashMap<String, (usize, usize)>,
+) -> Option<(usize, usize)> {
+    let (lhs, rhs) = line.trim_end_matches(';').split_once("->")?;
+    let qubit = resolve_qubit(lhs.trim().strip_prefix("measure")?, registers)?;
+    Some((qubit, bracket_index(rhs)?))
 }
 
 pub fn infer_qubits_from_gates(gates: Vec<&Gate>) -> usize {
@@ -143,17 +1075,23 @@ pub fn infer_qubits_from_gates(gates: Vec<&Gate>) -> usize {
     };
 
     for g in gates {
-        match *g {
+        match g {
             Gate::RX { qubit, .. }
             | Gate::RY { qubit, .. }
             | Gate::RZ { qubit, .. }
-            | Gate::H { qubit, .. } => bump(qubit),
+            | Gate::H { qubit, .. }
+            | Gate::MeasureQubit { qubit, .. } => bump(*qubit),
 
-            Gate::CNOT { control, target } => {
-                bump(control);
-                bump(target);
+            Gate::CNOT { control, target }
+            | Gate::CP {
+                control, target, ..
+            } => {
+                bump(*control);
+                bump(*target);
             }
 
+            Gate::Unitary { qubits, .. } => qubits.iter().for_each(|&q| bump(q)),
+
             // If you have other variants touching qubits, add them here.
             _ => {}
         }
@@ -188,4 +1126,324 @@ mod tests {
         );
         assert_eq!(gates[2], Gate::Measure);
     }
+
+    #[test]
+    fn test_qasm_parser_multiple_registers() {
+        let qasm_input = r#"
+            OPENQASM 2.0;
+            qreg a[2];
+            qreg b[1];
+            creg c[3];
+            h a[1];
+            cx a[0],b[0];
+            cp(pi) b[0], a[1];
+            measure b[0] -> c[2];
+        "#;
+        let (num_qubits, gates) = parse_qasm(qasm_input);
+
+        assert_eq!(num_qubits, 3);
+        assert_eq!(
+            gates,
+            vec![
+                Gate::h(1),
+                Gate::cx(0, 2),
+                Gate::cp(2, 1, std::f64::consts::PI),
+                Gate::measure_qubit(2, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_qasm_parser_rejects_index_past_register_end() {
+        // a[2] would otherwise alias b[0], the next qubit in the global layout.
+        let qasm_input = "OPENQASM 2.0;\nqreg a[2];\nqreg b[1];\nh b[0];\nx a[2];\n";
+        let (num_qubits, gates) = parse_qasm(qasm_input);
+
+        assert_eq!(num_qubits, 3);
+        assert_eq!(gates, vec![Gate::h(2)]);
+        assert!(matches!(try_parse_qasm(qasm_input), Err(SimError::Qasm(_))));
+        assert!(try_parse_qasm("qreg a[2];\nqreg b[1];\nx b[0];\n").is_ok());
+    }
+
+    #[test]
+    fn test_qasm_parser_rotations() {
+        let qasm_input = r#"
+            OPENQASM 2.0;
+            qreg q[2];
+            rx(0.5) q[0];
+            ry(-pi/2) q[1];
+            rz(3*pi/4) q[0];
+        "#;
+        let (_, gates) = parse_qasm(qasm_input);
+
+        assert_eq!(
+            gates,
+            vec![
+                Gate::rx(0, 0.5),
+                Gate::ry(1, -std::f64::consts::FRAC_PI_2),
+                Gate::rz(0, 3.0 * std::f64::consts::FRAC_PI_4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_qasm_parser_controlled_phase() {
+        let qasm_input = r#"
+            OPENQASM 2.0;
+            qreg q[3];
+            cp(pi/2) q[0],q[2];
+            cu1(-0.25) q[1], q[0];
+        "#;
+        let (_, gates) = parse_qasm(qasm_input);
+
+        assert_eq!(
+            gates,
+            vec![
+                Gate::cp(0, 2, std::f64::consts::FRAC_PI_2),
+                Gate::cp(1, 0, -0.25)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_qasm_parser_inlines_custom_gates() {
+        let qasm_input = r#"
+            OPENQASM 2.0;
+            include "qelib1.inc";
+            gate bell a, b {
+                h a;
+                cx a, b;
+            }
+            gate halfphase(theta) c, t { cp(theta/2) c, t; }
+            qreg q[3];
+            bell q[1], q[2];
+            halfphase(pi) q[0],q[2];
+        "#;
+        let (num_qubits, gates) = parse_qasm(qasm_input);
+
+        assert_eq!(num_qubits, 3);
+        assert_eq!(
+            gates,
+            vec![
+                Gate::h(1),
+                Gate::cx(1, 2),
+                Gate::cp(0, 2, std::f64::consts::FRAC_PI_2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gate_calls_that_cannot_be_expanded_are_reported() {
+        let qasm_input = r#"
+            OPENQASM 2.0;
+            gate pair a, b { h a; cx a, b; }
+            gate loop a { loop a; }
+            qreg q[2];
+            pair q[0];
+            loop q[1];
+            x q[1];
+        "#;
+        let (_, gates) = parse_qasm(qasm_input);
+        assert_eq!(gates, vec![Gate::x(1)]);
+
+        let (_, errors) = expand_gate_definitions(qasm_input);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("pair q[0]"));
+        assert!(errors[1].contains("nests deeper"));
+        assert!(matches!(try_parse_qasm(qasm_input), Err(SimError::Qasm(_))));
+    }
+
+    #[test]
+    fn test_qasm_parser_single_qubit_measure() {
+        let qasm_input = r#"
+            OPENQASM 2.0;
+            qreg q[2];
+            creg c[2];
+            x q[1];
+            measure q[0] -> c[0];
+            measure q[1] -> c[1];
+        "#;
+        let (_, gates) = parse_qasm(qasm_input);
+
+        assert_eq!(
+            gates[1..],
+            [
+                Gate::MeasureQubit {
+                    qubit: 0,
+                    creg_bit: 0
+                },
+                Gate::MeasureQubit {
+                    qubit: 1,
+                    creg_bit: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_barrier_and_reset() {
+        let (num_qubits, gates) = parse_qasm(
+            "qreg a[2];\nqreg b[1];\nh a[0];\nbarrier a[0],b[0];\nbarrier a;\nreset b[0];\nreset a;\n",
+        );
+        assert_eq!(num_qubits, 3);
+        assert_eq!(
+            gates,
+            vec![
+                Gate::h(0),
+                Gate::Barrier { qubits: vec![0, 2] },
+                Gate::Barrier { qubits: vec![0, 1] },
+                Gate::reset(2),
+                Gate::reset(0),
+                Gate::reset(1),
+            ]
+        );
+
+        let (_, gates) =
+            parse_qasm3("qubit[2] q;\nbarrier q[1];\nbarrier;\nreset q;\nreset q[1];\n").unwrap();
+        assert_eq!(
+            gates,
+            vec![
+                Gate::Barrier { qubits: vec![1] },
+                Gate::Barrier { qubits: vec![0, 1] },
+                Gate::reset(0),
+                Gate::reset(1),
+                Gate::reset(1),
+            ]
+        );
+        assert!(Gate::reset(0).inverse().is_err());
+    }
+
+    #[test]
+    fn test_qasm3_bell_state() {
+        let qasm_input = r#"
+            OPENQASM 3.0;
+            include "stdgates.inc";
+            qubit[2] q;
+            bit[2] c;
+            h q[0];
+            ctrl @ x q[0], q[1];
+            c = measure q;
+        "#;
+        assert_eq!(QasmVersion::detect(qasm_input), QasmVersion::V3);
+        let (num_qubits, gates) = parse_qasm3(qasm_input).unwrap();
+
+        assert_eq!(num_qubits, 2);
+        assert_eq!(gates, vec![Gate::h(0), Gate::cx(0, 1), Gate::Measure]);
+    }
+
+    #[test]
+    fn test_qasm3_gates_and_single_qubit_measure() {
+        let qasm_input = r#"
+            OPENQASM 3;
+            qubit[3] q;
+            bit[3] c;
+            rz(pi/2) q[2];
+            cx q[1], q[2];
+            ctrl @ p(0.5) q[0], q[2];
+            cz q[0], q[1];
+            c[1] = measure q[2];
+        "#;
+        let (_, gates) = parse_qasm3(qasm_input).unwrap();
+
+        assert_eq!(
+            gates,
+            vec![
+                Gate::rz(2, std::f64::consts::FRAC_PI_2),
+                Gate::cx(1, 2),
+                Gate::cp(0, 2, 0.5),
+                Gate::cp(0, 1, std::f64::consts::PI),
+                Gate::measure_qubit(2, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_qasm3_errors() {
+        assert!(matches!(
+            parse_qasm3("OPENQASM 3.0; qubit[2] q; h q[2];"),
+            Err(SimError::Qubit(2))
+        ));
+        assert!(matches!(
+            parse_qasm3("OPENQASM 3.0; qubit[2] q; swap q[0], q[1];"),
+            Err(SimError::Qasm(_))
+        ));
+        assert!(matches!(
+            parse_qasm3("OPENQASM 3.0; h q[0];"),
+            Err(SimError::Qasm(_))
+        ));
+    }
+
+    #[test]
+    fn test_qasm3_rejects_unexpandable_gate_calls() {
+        assert!(matches!(
+            parse_qasm3("gate g a, b { h a; } qubit[2] q; g q[0];"),
+            Err(SimError::Qasm(_))
+        ));
+        let (_, gates) = parse_qasm3("gate g a, b { h a; } qubit[2] q; g q[0], q[1];").unwrap();
+        assert_eq!(gates, vec![Gate::h(0)]);
+    }
+
+    #[test]
+    fn test_qasm3_rejects_second_register_measure() {
+        assert!(matches!(
+            parse_qasm3("OPENQASM 3.0; qubit[2] q; bit[2] c; c = measure q; c = measure q;"),
+            Err(SimError::Qasm(_))
+        ));
+    }
+
+    #[test]
+    fn test_qasm_version_detection() {
+        assert_eq!(
+            QasmVersion::detect("OPENQASM 2.0;\nqreg q[1];"),
+            QasmVersion::V2
+        );
+        assert_eq!(
+            QasmVersion::detect("// no header\nqubit[1] q;"),
+            QasmVersion::V3
+        );
+        assert_eq!(QasmVersion::detect("qreg q[1];"), QasmVersion::V2);
+    }
+
+    #[test]
+    fn test_shorthand_constructors_match_struct_variants() {
+        assert_eq!(Gate::h(0), Gate::H { qubit: 0 });
+        assert_eq!(Gate::x(1), Gate::X { qubit: 1 });
+        assert_eq!(Gate::y(2), Gate::Y { qubit: 2 });
+        assert_eq!(Gate::z(3), Gate::Z { qubit: 3 });
+        assert_eq!(
+            Gate::cx(0, 1),
+            Gate::CX {
+                control: 0,
+                target: 1
+            }
+        );
+        assert_eq!(
+            Gate::rx(0, 0.5),
+            Gate::RX {
+                qubit: 0,
+                theta: 0.5
+            }
+        );
+        assert_eq!(
+            Gate::ry(1, 0.5),
+            Gate::RY {
+                qubit: 1,
+                theta: 0.5
+            }
+        );
+        assert_eq!(
+            Gate::rz(2, 0.5),
+            Gate::RZ {
+                qubit: 2,
+                theta: 0.5
+            }
+        );
+        assert_eq!(
+            Gate::measure_qubit(1, 0),
+            Gate::MeasureQubit {
+                qubit: 1,
+                creg_bit: 0
+            }
+        );
+    }
 }
@@ -0,0 +1,573 @@
+// src/product_backend.rs
+use crate::StateVector;
+use crate::api::{Pauli, SimError};
+use crate::circuit::Circuit;
+use crate::parser::Gate;
+use crate::simulator::{
+    GateMatrix, HADAMARD, PAULI_X, PAULI_Y, PAULI_Z, QuantumSimulator, Simulator,
+    construct_gate_matrix,
+};
+use crate::state::DEFAULT_MAX_QUBITS;
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::{OnceCell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+
+/// The (|0⟩, |1⟩) amplitudes of a single qubit.
+type Qubit = [Complex<f64>; 2];
+
+/// A control whose other amplitude has squared magnitude below this counts as a basis state.
+const BASIS_TOLERANCE: f64 = 1e-24;
+
+enum Repr {
+    /// The register is the tensor product of one single-qubit state per qubit.
+    Product {
+        qubits: Vec<Qubit>,
+        global_phase: Complex<f64>,
+    },
+    Dense(Box<QuantumSimulator>),
+}
+
+/// A simulator for circuits that keep their qubits unentangled.
+///
+/// While every qubit has its own single-qubit state, the register is stored as n
+/// 2-amplitude states, so memory is O(n) and a gate or measurement costs O(1).
+/// Controlled gates whose control is in a basis state keep the register separable; any
+/// other multi-qubit gate expands it into a dense [`QuantumSimulator`], which is used for
+/// the rest of the run. Expanding a register wider than [`DEFAULT_MAX_QUBITS`] is an error.
+pub struct ProductStateSimulator {
+    num_qubits: usize,
+    repr: Repr,
+    /// The expanded statevector handed out by `get_statevector` while in product form.
+    dense_view: OnceCell<StateVector>,
+    /// Drives every measurement and sample in product form, and seeds the dense simulator.
+    rng: RefCell<StdRng>,
+}
+
+impl ProductStateSimulator {
+    /// Starts in |0..0⟩ in product form; no statevector is allocated.
+    pub fn new(num_qubits: usize) -> Self {
+        Self::with_rng(num_qubits, StdRng::from_entropy())
+    }
+
+    /// Like `new`, but measurements and samples come from an RNG seeded with `seed`, so
+    /// two simulators with the same seed and circuit give identical results.
+    pub fn with_seed(num_qubits: usize, seed: u64) -> Self {
+        Self::with_rng(num_qubits, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(num_qubits: usize, rng: StdRng) -> Self {
+        Self {
+            num_qubits,
+            repr: zero_state(num_qubits),
+            dense_view: OnceCell::new(),
+            rng: RefCell::new(rng),
+        }
+    }
+
+    /// Whether the state is still stored qubit by qubit.
+    pub fn is_product(&self) -> bool {
+        matches!(self.repr, Repr::Product { .. })
+    }
+
+    /// Applies every gate of `circuit`, with the same checks as `QuantumSimulator::apply_circuit`.
+    pub fn apply_circuit(&mut self, circuit: &Circuit) -> Result<(), SimError> {
+        circuit.validate()?;
+        if circuit.num_qubits > self.num_qubits {
+            return Err(SimError::Qubit(circuit.num_qubits - 1));
+        }
+        for moment in &circuit.moments {
+            for gate in moment {
+                self.try_apply_gate(gate)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a single gate. Fails if it names a qubit outside the register, or if it
+    /// entangles a register too wide to expand into a statevector.
+    pub fn try_apply_gate(&mut self, gate: &Gate) -> Result<(), SimError> {
+        self.dense_view.take();
+        if let Repr::Product {
+            qubits,
+            global_phase,
+        } = &mut self.repr
+        {
+            if apply_to_product(qubits, global_phase, gate, self.rng.get_mut())? {
+                return Ok(());
+            }
+            self.densify()?;
+        }
+        match &mut self.repr {
+            Repr::Dense(sim) => sim.try_apply_gate(gate),
+            Repr::Product { .. } => unreachable!("densify leaves a dense register"),
+        }
+    }
+
+    /// Samples `shots` computational-basis outcomes, with qubit 0 as the rightmost
+    /// character. In product form every qubit is sampled independently.
+    pub fn sample_counts(&self, shots: u32) -> HashMap<String, u32> {
+        let qubits = match &self.repr {
+            Repr::Product { qubits, .. } => qubits,
+            Repr::Dense(sim) => return sim.sample_counts(shots),
+        };
+        let mut rng = self.rng.borrow_mut();
+        let mut counts = HashMap::new();
+        for _ in 0..shots {
+            let bits: String = qubits
+                .iter()
+                .rev()
+                .map(|q| {
+                    if rng.r#gen::<f64>() < q[1].norm_sqr() {
+                        '1'
+                    } else {
+                        '0'
+                    }
+                })
+                .collect();
+            *counts.entry(bits).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Switches to the dense representation. Fails, leaving the product state untouched,
+    /// if the register is too wide for it.
+    fn densify(&mut self) -> Result<(), SimError> {
+        if let Repr::Product {
+            qubits,
+            global_phase,
+        } = &self.repr
+        {
+            let state = expand(qubits, *global_phase)?;
+            // Derived from our own RNG, so a seeded run stays reproducible after expanding.
+            let rng = StdRng::from_rng(self.rng.get_mut()).expect("StdRng seeding cannot fail");
+            self.repr = Repr::Dense(Box::new(QuantumSimulator::from_state(state, rng)));
+        }
+        Ok(())
+    }
+}
+
+impl Simulator for ProductStateSimulator {
+    fn reset(&mut self) {
+        self.repr = zero_state(self.num_qubits);
+        self.dense_view.take();
+    }
+
+    /// Panics where `try_apply_gate` would return an error.
+    fn apply_gate(&mut self, gate: &Gate) {
+        if let Err(e) = self.try_apply_gate(gate) {
+            panic!("Failed to apply gate: {}", e);
+        }
+    }
+
+    fn measure_pauli_string_expectation(&mut self, operators: Vec<Gate>) -> f64 {
+        match &mut self.repr {
+            Repr::Product { qubits, .. } => {
+                let ops = operators.iter().map(|op| match op {
+                    Gate::X { qubit } => (PAULI_X, *qubit),
+                    Gate::Y { qubit } => (PAULI_Y, *qubit),
+                    Gate::Z { qubit } => (PAULI_Z, *qubit),
+                    _ => panic!("Unsupported operator in Pauli string expectation"),
+                });
+                product_expectation(qubits, ops)
+            }
+            Repr::Dense(sim) => sim.measure_pauli_string_expectation(operators),
+        }
+    }
+
+    fn expectation_pauli_string(&self, ops: &[(Pauli, usize)]) -> f64 {
+        match &self.repr {
+            Repr::Product { qubits, .. } => {
+                let ops = ops.iter().filter_map(|&(p, q)| match p {
+                    Pauli::I => None,
+                    Pauli::X => Some((PAULI_X, q)),
+                    Pauli::Y => Some((PAULI_Y, q)),
+                    Pauli::Z => Some((PAULI_Z, q)),
+                });
+                product_expectation(qubits, ops)
+            }
+            Repr::Dense(sim) => sim.expectation_pauli_string(ops),
+        }
+    }
+
+    /// In product form this expands the register into a full statevector on first use,
+    /// panicking if it is wider than [`DEFAULT_MAX_QUBITS`].
+    fn get_statevector(&self) -> &StateVector {
+        match &self.repr {
+            Repr::Product {
+                qubits,
+                global_phase,
+            } => self
+                .dense_view
+                .get_or_init(|| expand(qubits, *global_phase).unwrap_or_else(|e| panic!("{}", e))),
+            Repr::Dense(sim) => sim.get_statevector(),
+        }
+    }
+
+    fn get_num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    // Like `QuantumSimulator`, the gates applied are not recorded.
+    fn compile_to_qasm(&self) -> String {
+        String::new()
+    }
+}
+
+/// |0..0⟩ in product form.
+fn zero_state(num_qubits: usize) -> Repr {
+    Repr::Product {
+        qubits: vec![[Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)]; num_qubits],
+        global_phase: Complex::new(1.0, 0.0),
+    }
+}
+
+/// Every qubit index `gate` names, controls included. A full-register `Measure` names none.
+fn named_qubits(gate: &Gate) -> Vec<usize> {
+    match gate {
+        Gate::CX { control, target }
+        | Gate::CNOT { control, target }
+        | Gate::CP {
+            control, target, ..
+        } => vec![*control, *target],
+        Gate::I { qubit } => vec![*qubit],
+        _ => gate.target(),
+    }
+}
+
+/// Applies `gate` to a product state, returning `Ok(false)` without touching it if the
+/// result would not be a product state.
+fn apply_to_product(
+    qubits: &mut [Qubit],
+    global_phase: &mut Complex<f64>,
+    gate: &Gate,
+    rng: &mut StdRng,
+) -> Result<bool, SimError> {
+    if let Some(q) = named_qubits(gate).into_iter().find(|&q| q >= qubits.len()) {
+        return Err(SimError::Qubit(q));
+    }
+    match gate {
+        Gate::I { .. } => {}
+        Gate::H { qubit } => apply_matrix(&mut qubits[*qubit], &HADAMARD),
+        Gate::X { qubit } => apply_matrix(&mut qubits[*qubit], &PAULI_X),
+        Gate::Y { qubit } => apply_matrix(&mut qubits[*qubit], &PAULI_Y),
+        Gate::Z { qubit } => apply_matrix(&mut qubits[*qubit], &PAULI_Z),
+        Gate::RX { qubit, .. } | Gate::RY { qubit, .. } | Gate::RZ { qubit, .. } => {
+            let matrix = construct_gate_matrix(gate).expect("rotation gates have a matrix");
+            apply_matrix(&mut qubits[*qubit], &matrix);
+        }
+        Gate::Unitary {
+            qubits: targets,
+            matrix,
+        } if targets.len() == 1 => {
+            if matrix.len() != 2 || matrix.iter().any(|row| row.len() != 2) {
+                return Err(SimError::Matrix(
+                    "expected a 2x2 matrix for 1 qubit(s)".to_string(),
+                ));
+            }
+            let matrix = [[matrix[0][0], matrix[0][1]], [matrix[1][0], matrix[1][1]]];
+            apply_matrix(&mut qubits[targets[0]], &matrix);
+        }
+        Gate::CX { control, target } | Gate::CNOT { control, target } => {
+            match basis_value(&qubits[*control]) {
+                Some(0) => {}
+                Some(_) => apply_matrix(&mut qubits[*target], &PAULI_X),
+                None => return Ok(false),
+            }
+        }
+        Gate::CP {
+            control,
+            target,
+            theta,
+        } => {
+            let phase = [
+                [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+                [Complex::new(0.0, 0.0), Complex::from_polar(1.0, *theta)],
+            ];
+            // CP is symmetric, so either qubit being in a basis state is enough.
+            match (
+                basis_value(&qubits[*control]),
+                basis_value(&qubits[*target]),
+            ) {
+                (Some(0), _) | (_, Some(0)) => {}
+                (Some(_), _) => apply_matrix(&mut qubits[*target], &phase),
+                (_, Some(_)) => apply_matrix(&mut qubits[*control], &phase),
+                (None, None) => return Ok(false),
+            }
+        }
+        Gate::GPhase { theta } => *global_phase *= Complex::from_polar(1.0, *theta),
+        Gate::Measure => {
+            for qubit in qubits.iter_mut() {
+                collapse(qubit, rng);
+            }
+        }
+        Gate::MeasureQubit { qubit, .. } => collapse(&mut qubits[*qubit], rng),
+        // The qubit is unentangled, so whatever the outcome the rest of the register is untouched.
+        Gate::Reset { qubit } => {
+            qubits[*qubit] = [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+        }
+        Gate::Barrier { .. } => {}
+        Gate::Unitary { .. } => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn apply_matrix(qubit: &mut Qubit, m: &GateMatrix) {
+    *qubit = [
+        m[0][0] * qubit[0] + m[0][1] * qubit[1],
+        m[1][0] * qubit[0] + m[1][1] * qubit[1],
+    ];
+}
+
+/// `Some(bit)` if the qubit is |bit⟩ up to phase.
+fn basis_value(qubit: &Qubit) -> Option<u8> {
+    if qubit[1].norm_sqr() < BASIS_TOLERANCE {
+        Some(0)
+    } else if qubit[0].norm_sqr() < BASIS_TOLERANCE {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Measures a single qubit in Z, keeping the phase of the surviving amplitude.
+fn collapse<R: Rng + ?Sized>(qubit: &mut Qubit, rng: &mut R) {
+    let zero = Complex::new(0.0, 0.0);
+    if rng.r#gen::<f64>() < qubit[1].norm_sqr() {
+        *qubit = [zero, qubit[1] / qubit[1].norm()];
+    } else {
+        *qubit = [qubit[0] / qubit[0].norm(), zero];
+    }
+}
+
+/// ⟨ψ|P|ψ⟩ for a product state factorises into one single-qubit expectation per qubit
+/// that the operators touch.
+fn product_expectation(qubits: &[Qubit], ops: impl Iterator<Item = (GateMatrix, usize)>) -> f64 {
+    let mut transformed: BTreeMap<usize, Qubit> = BTreeMap::new();
+    for (matrix, q) in ops {
+        apply_matrix(transformed.entry(q).or_insert(qubits[q]), &matrix);
+    }
+    transformed
+        .iter()
+        .map(|(&q, v)| qubits[q][0].conj() * v[0] + qubits[q][1].conj() * v[1])
+        .product::<Complex<f64>>()
+        .re
+}
+
+/// The dense statevector of a product state, little-endian like every other backend.
+fn expand(qubits: &[Qubit], global_phase: Complex<f64>) -> Result<StateVector, SimError> {
+    if qubits.len() > DEFAULT_MAX_QUBITS {
+        return Err(SimError::TooManyQubits {
+            requested: qubits.len(),
+            max: DEFAULT_MAX_QUBITS,
+        });
+    }
+    let mut amplitudes = vec![Complex::new(1.0, 0.0)];
+    for qubit in qubits {
+        let high: Vec<Complex<f64>> = amplitudes.iter().map(|a| a * qubit[1]).collect();
+        for a in amplitudes.iter_mut() {
+            *a *= qubit[0];
+        }
+        amplitudes.extend(high);
+    }
+    let mut state = StateVector::from_amplitudes(amplitudes)
+        .unwrap_or_else(|e| panic!("product state is not normalized: {}", e));
+    state.global_phase = global_phase;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    #[test]
+    fn test_deep_single_qubit_circuit_stays_in_product_form() {
+        let n = 20;
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut circuit = Circuit::with_qubits(n);
+        for _ in 0..50 {
+            circuit.add_moment((0..n).map(Gate::h).collect());
+            circuit.add_moment(
+                (0..n)
+                    .map(|q| Gate::ry(q, rng.gen_range(-3.0..3.0)))
+                    .collect(),
+            );
+            circuit.add_moment(
+                (0..n)
+                    .map(|q| Gate::rz(q, rng.gen_range(-3.0..3.0)))
+                    .collect(),
+            );
+            circuit.add_moment((0..n).step_by(3).map(Gate::x).collect());
+        }
+
+        let mut sim = ProductStateSimulator::new(n);
+        sim.apply_circuit(&circuit).unwrap();
+        assert!(sim.is_product());
+        assert!(sim.dense_view.get().is_none());
+
+        // Each qubit evolves on its own, so it matches a one-qubit dense simulation.
+        for q in [0, 7, 19] {
+            let mut single = QuantumSimulator::new(1);
+            for gate in circuit.gates_flat() {
+                if gate.target() == vec![q] {
+                    let mut gate = gate.clone();
+                    match &mut gate {
+                        Gate::H { qubit }
+                        | Gate::X { qubit }
+                        | Gate::RY { qubit, .. }
+                        | Gate::RZ { qubit, .. } => *qubit = 0,
+                        _ => unreachable!(),
+                    }
+                    single.apply_gate(&gate);
+                }
+            }
+            let expected_z = single.expectation_pauli_string(&[(Pauli::Z, 0)]);
+            let z = sim.expectation_pauli_string(&[(Pauli::Z, q)]);
+            assert!(
+                (z - expected_z).abs() < EPSILON,
+                "q{}: {} vs {}",
+                q,
+                z,
+                expected_z
+            );
+            let x = sim.measure_pauli_string_expectation(vec![Gate::x(q)]);
+            let expected_x = single.measure_pauli_string_expectation(vec![Gate::x(0)]);
+            assert!(
+                (x - expected_x).abs() < EPSILON,
+                "q{}: {} vs {}",
+                q,
+                x,
+                expected_x
+            );
+        }
+
+        let counts = sim.sample_counts(100);
+        assert_eq!(counts.values().sum::<u32>(), 100);
+        assert!(counts.keys().all(|k| k.len() == n));
+        assert!(sim.is_product());
+    }
+
+    #[test]
+    fn test_same_seed_gives_identical_samples() {
+        let mut circuit = Circuit::with_qubits(3);
+        circuit.add_moment(vec![Gate::h(0), Gate::h(1), Gate::ry(2, 1.0)]);
+        circuit.add_gate(Gate::measure_qubit(0, 0));
+
+        let run = |seed: u64, entangle: bool| {
+            let mut sim = ProductStateSimulator::with_seed(3, seed);
+            sim.apply_circuit(&circuit).unwrap();
+            if entangle {
+                sim.apply_gate(&Gate::cx(1, 2));
+                assert!(!sim.is_product());
+            }
+            (
+                sim.get_statevector().amplitudes.clone(),
+                sim.sample_counts(500),
+            )
+        };
+        for entangle in [false, true] {
+            let (state_a, counts_a) = run(42, entangle);
+            let (state_b, counts_b) = run(42, entangle);
+            assert_eq!(state_a, state_b);
+            assert_eq!(counts_a, counts_b);
+            assert!(counts_a.len() > 1);
+        }
+    }
+
+    #[test]
+    fn test_entangling_a_register_too_wide_to_expand_is_an_error() {
+        let n = DEFAULT_MAX_QUBITS + 2;
+        let mut circuit = Circuit::with_qubits(n);
+        circuit.add_moment((0..n).map(Gate::h).collect());
+        circuit.add_gate(Gate::cx(0, 1));
+
+        let mut sim = ProductStateSimulator::new(n);
+        assert!(matches!(
+            sim.apply_circuit(&circuit),
+            Err(SimError::TooManyQubits { requested, .. }) if requested == n
+        ));
+        // The failed expansion leaves the product state in place.
+        assert!(sim.is_product());
+        assert!((sim.expectation_pauli_string(&[(Pauli::X, 0)]) - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_out_of_range_qubit_is_an_error() {
+        let mut sim = ProductStateSimulator::new(2);
+        assert!(matches!(
+            sim.try_apply_gate(&Gate::I { qubit: 2 }),
+            Err(SimError::Qubit(2))
+        ));
+        assert!(matches!(
+            sim.try_apply_gate(&Gate::cx(5, 0)),
+            Err(SimError::Qubit(5))
+        ));
+        assert!(sim.is_product());
+    }
+
+    #[test]
+    fn test_malformed_single_qubit_unitary_is_an_error() {
+        let one = Complex::new(1.0, 0.0);
+        let mut sim = ProductStateSimulator::new(1);
+        for matrix in [vec![vec![one]], vec![vec![one, one], vec![one]]] {
+            assert!(matches!(
+                sim.try_apply_gate(&Gate::Unitary {
+                    qubits: vec![0],
+                    matrix,
+                }),
+                Err(SimError::Matrix(_))
+            ));
+        }
+        assert!(sim.is_product());
+    }
+
+    #[test]
+    fn test_matches_dense_simulator_and_falls_back_on_entanglement() {
+        let mut product = ProductStateSimulator::new(3);
+        let mut dense = QuantumSimulator::new(3);
+        let separable = [
+            Gate::x(0),
+            Gate::ry(1, 0.8),
+            // Control q0 is |1⟩, so this only flips q2.
+            Gate::cx(0, 2),
+            Gate::cp(2, 1, 0.5),
+            Gate::GPhase { theta: 0.3 },
+        ];
+        for gate in &separable {
+            product.apply_gate(gate);
+            dense.apply_gate(gate);
+        }
+        assert!(product.is_product());
+        assert!(
+            product
+                .get_statevector()
+                .approx_eq_up_to_global_phase(dense.get_statevector(), EPSILON)
+        );
+        assert!(
+            (product.get_statevector().global_phase - dense.get_statevector().global_phase).norm()
+                < EPSILON
+        );
+
+        // q1 is in superposition, so this CX entangles it with q0.
+        let entangling = Gate::cx(1, 0);
+        product.apply_gate(&entangling);
+        dense.apply_gate(&entangling);
+        assert!(!product.is_product());
+        let (idx, diff) = product
+            .get_statevector()
+            .max_amplitude_diff(dense.get_statevector());
+        assert!(diff < EPSILON, "amplitude {} differs by {}", idx, diff);
+        let ops = [(Pauli::Z, 0), (Pauli::Z, 1)];
+        assert!(
+            (product.expectation_pauli_string(&ops) - dense.expectation_pauli_string(&ops)).abs()
+                < EPSILON
+        );
+
+        product.reset();
+        assert!(product.is_product());
+        assert!((product.get_statevector().amplitudes[0].re - 1.0).abs() < EPSILON);
+    }
+}
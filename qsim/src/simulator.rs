@@ -1,8 +1,16 @@
-use super::parser::{Gate, parse_qasm};
+use super::parser::Gate;
 use super::state::StateVector;
+use crate::api::{Pauli, SimError};
 use crate::circuit::Circuit;
-use crate::events::{Event, GateInfo, MeasurementInfo, SimulationStartInfo};
+use crate::events::{
+    Event, GateInfo, MeasurementCountsInfo, MeasurementInfo, MomentInfo, SimulationStartInfo,
+};
 use num_complex::Complex;
+use rand::SeedableRng;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::f64::consts::FRAC_1_SQRT_2;
 
 pub trait Simulator {
@@ -15,6 +23,12 @@ pub trait Simulator {
     /// by applying the Pauli operators P to a copy of the state and
     /// calculating ⟨ψ|P|ψ⟩.
     fn measure_pauli_string_expectation(&mut self, operators: Vec<Gate>) -> f64;
+    /// Computes ⟨ψ|P|ψ⟩ for a Pauli string, e.g. [(Z,0),(X,2)], on a clone of the state.
+    /// Unlike `measure_pauli_string_expectation` the simulator is never modified, so this
+    /// works through a shared reference and stays consistent if it panics.
+    fn expectation_pauli_string(&self, ops: &[(Pauli, usize)]) -> f64 {
+        self.get_statevector().expectation_pauli_string(ops)
+    }
 
     fn get_statevector(&self) -> &StateVector;
     fn get_num_qubits(&self) -> usize;
@@ -27,42 +41,22 @@ pub trait QuantumGate {
     fn apply(&self, state: &mut [Complex<f64>]);
 }
 
+#[derive(Clone)]
 pub struct QuantumSimulator {
     pub num_qubits: usize,
     pub state: StateVector,
+    /// Drives every measurement and sample. A `RefCell` so that sampling works through `&self`.
+    rng: RefCell<StdRng>,
 }
 
 impl Simulator for QuantumSimulator {
     fn reset(&mut self) {
         self.state.reset();
     }
+    /// Panics if `gate` is an invalid `Unitary`; `try_apply_gate` returns the error instead.
     fn apply_gate(&mut self, gate: &Gate) {
-        match gate {
-            Gate::H { qubit } => self.state.apply_single_qubit_gate(&HADAMARD, *qubit),
-            Gate::X { qubit } => self.state.apply_single_qubit_gate(&PAULI_X, *qubit),
-            Gate::Y { qubit } => self.state.apply_single_qubit_gate(&PAULI_Y, *qubit),
-            Gate::Z { qubit } => self.state.apply_single_qubit_gate(&PAULI_Z, *qubit),
-            Gate::CX { control, target } | Gate::CNOT { control, target } => {
-                self.state.apply_cx(*control, *target)
-            }
-            Gate::Measure => {
-                let result = self.state.measure_all(&mut rand::thread_rng());
-            }
-            _ => {
-                let matrix = construct_gate_matrix(gate);
-
-                if let Some(matrix) = matrix {
-                    if gate.target().len() == 1 {
-                        self.state
-                            .apply_single_qubit_gate(&matrix, gate.target()[0]);
-                    } else {
-                        self.state.apply_multi_qubit_gate(&matrix, &gate.target());
-                    }
-                } else {
-                    eprintln!("Unsupported gate type: {:?}", gate);
-                    panic!("Unsupported gate type encountered during simulation.");
-                }
-            }
+        if let Err(e) = self.try_apply_gate(gate) {
+            panic!("Failed to apply gate: {}", e);
         }
     }
 
@@ -115,6 +109,27 @@ impl QuantumSimulator {
         QuantumSimulator {
             num_qubits,
             state: StateVector::new(num_qubits),
+            rng: RefCell::new(StdRng::from_entropy()),
+        }
+    }
+
+    /// Like `new`, but measurements and samples come from an RNG seeded with `seed`, so
+    /// two simulators with the same seed and circuit give identical results.
+    pub fn with_seed(num_qubits: usize, seed: u64) -> Self {
+        QuantumSimulator {
+            num_qubits,
+            state: StateVector::new(num_qubits),
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    /// Continues from an existing state, such as one expanded from another representation,
+    /// drawing measurements from `rng`.
+    pub(crate) fn from_state(state: StateVector, rng: StdRng) -> Self {
+        QuantumSimulator {
+            num_qubits: state.num_qubits,
+            state,
+            rng: RefCell::new(rng),
         }
     }
 
@@ -122,53 +137,145 @@ impl QuantumSimulator {
         self.num_qubits
     }
 
-    pub fn apply_circuit(&mut self, circuit: &Circuit) {
+    /// Applies a single gate, returning an error instead of panicking if it is an invalid
+    /// `Unitary`.
+    pub fn try_apply_gate(&mut self, gate: &Gate) -> Result<(), SimError> {
+        match gate {
+            Gate::I { .. } | Gate::Barrier { .. } => {}
+            Gate::H { qubit } => self.state.apply_single_qubit_gate(&HADAMARD, *qubit),
+            Gate::X { qubit } => self.state.apply_single_qubit_gate(&PAULI_X, *qubit),
+            Gate::Y { qubit } => self.state.apply_single_qubit_gate(&PAULI_Y, *qubit),
+            Gate::Z { qubit } => self.state.apply_single_qubit_gate(&PAULI_Z, *qubit),
+            Gate::RX { qubit, .. } | Gate::RY { qubit, .. } | Gate::RZ { qubit, .. } => {
+                let matrix = construct_gate_matrix(gate).expect("rotation gates have a matrix");
+                self.state.apply_single_qubit_gate(&matrix, *qubit)
+            }
+            Gate::CX { control, target } | Gate::CNOT { control, target } => {
+                self.state.apply_cx(*control, *target)
+            }
+            Gate::Measure => {
+                self.state.measure_all(self.rng.get_mut());
+            }
+            Gate::MeasureQubit { qubit, .. } => {
+                self.state.measure_qubit_in_z(*qubit, self.rng.get_mut());
+            }
+            Gate::Reset { qubit } => self.state.reset_qubit(*qubit, self.rng.get_mut()),
+            Gate::CP {
+                control,
+                target,
+                theta,
+            } => self.state.apply_cp(*control, *target, *theta),
+            Gate::GPhase { theta } => self.state.apply_global_phase(*theta),
+            Gate::Unitary { qubits, matrix } => self.state.apply_arbitrary_gate(matrix, qubits)?,
+        }
+        Ok(())
+    }
+
+    /// Applies every gate of `circuit`. Fails without touching the state if a gate acts on
+    /// a qubit outside the circuit or the circuit is wider than the simulator.
+    pub fn apply_circuit(&mut self, circuit: &Circuit) -> Result<(), SimError> {
+        circuit.validate()?;
+        if circuit.num_qubits > self.num_qubits {
+            return Err(SimError::Qubit(circuit.num_qubits - 1));
+        }
         for moment in &circuit.moments {
             for gate in moment {
-                self.apply_gate(gate);
+                self.try_apply_gate(gate)?;
             }
         }
+        Ok(())
+    }
+
+    /// Like `apply_circuit`, but calls `hook(step, gate, state)` after each gate, where
+    /// `step` counts gates from 0 across all moments and `state` is the state after `gate`.
+    /// Stops at the first gate that fails to apply.
+    pub fn apply_circuit_with_hook<F>(
+        &mut self,
+        circuit: &Circuit,
+        mut hook: F,
+    ) -> Result<(), SimError>
+    where
+        F: FnMut(usize, &Gate, &StateVector),
+    {
+        for (step, gate) in circuit.moments.iter().flatten().enumerate() {
+            self.try_apply_gate(gate)?;
+            hook(step, gate, &self.state);
+        }
+        Ok(())
     }
 
     // sets the simulator state to a specific configuration ie: [0, 0, 1, 0, 0] == "00100"
-    pub fn prepare_initial_state(&mut self, initial_state: &[u8]) {
+    // Returns an error without touching the state if the slice is longer than the register
+    // or contains anything other than 0 or 1.
+    pub fn prepare_initial_state(&mut self, initial_state: &[u8]) -> Result<(), String> {
+        if initial_state.len() > self.num_qubits {
+            return Err(format!(
+                "Initial state has {} entries but the simulator only has {} qubits.",
+                initial_state.len(),
+                self.num_qubits
+            ));
+        }
+        if let Some((i, &state)) = initial_state.iter().enumerate().find(|&(_, &s)| s > 1) {
+            return Err(format!(
+                "Initial state value {} for qubit {} must be 0 or 1.",
+                state, i
+            ));
+        }
+
         for (i, &state) in initial_state.iter().enumerate() {
             if state == 1 {
                 // Apply an X gate to flip |0> to |1>
                 self.apply_gate(&Gate::X { qubit: i });
             }
         }
+        Ok(())
     }
 
+    /// Samples `shots` computational-basis measurements of the current state without
+    /// collapsing it. Keys are bitstrings with qubit 0 as the rightmost character.
+    pub fn sample_counts(&self, shots: u32) -> HashMap<String, u32> {
+        self.state
+            .sample_counts_with_rng(shots, &mut *self.rng.borrow_mut())
+    }
+
+    /// The probability |amp|² of measuring the basis state `state_index`.
     pub fn get_probability(&self, state_index: usize) -> f64 {
         if state_index >= self.state.amplitudes.len() {
             eprintln!("Error: State index out of bounds.");
             return 0.0;
         }
-        let amp = self.state.amplitudes[state_index];
-        (amp.re * amp.re + amp.im * amp.im).sqrt()
+        self.state.amplitudes[state_index].norm_sqr()
     }
 
+    /// Parses a whitespace-separated Pauli string such as "X0 z10". Each token must be
+    /// exactly one Pauli letter (either case) followed by a decimal qubit index.
     fn parse_pauli_term(&self, term_str: &str) -> Result<Vec<Gate>, String> {
         term_str
             .split_whitespace()
             .map(|pauli_op| {
-                let op_char = pauli_op
-                    .chars()
+                let mut chars = pauli_op.chars();
+                let op_char = chars
                     .next()
                     .ok_or_else(|| "Empty Pauli operator in string".to_string())?;
-                let qubit_idx = pauli_op[1..]
+                let index_str = chars.as_str();
+                if index_str.is_empty() || !index_str.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(format!(
+                        "Malformed Pauli operator '{}': expected a single X, Y, Z or I followed by a qubit index",
+                        pauli_op
+                    ));
+                }
+                let qubit_idx = index_str
                     .parse::<usize>()
-                    .map_err(|_| format!("Invalid qubit index in '{}'", pauli_op))?;
+                    .map_err(|_| format!("Qubit index in '{}' is too large", pauli_op))?;
 
-                if qubit_idx >= self.num_qubits as usize {
+                if qubit_idx >= self.num_qubits {
                     return Err(format!(
                         "Qubit index {} is out of bounds for {} qubits.",
                         qubit_idx, self.num_qubits
                     ));
                 }
 
-                match op_char {
+                match op_char.to_ascii_uppercase() {
                     'X' => Ok(Gate::X { qubit: qubit_idx }),
                     'Y' => Ok(Gate::Y { qubit: qubit_idx }),
                     'Z' => Ok(Gate::Z { qubit: qubit_idx }),
@@ -179,48 +286,126 @@ impl QuantumSimulator {
             .collect()
     }
 
-    pub fn measure_expectation(&self, operator_string: &str, shots: usize) -> Result<f64, String> {
-        // For simplicity, this example only handles single-term operators like "Z0 X1".
-        // A full implementation would need to handle coefficients and multiple terms
-        // like "1.5 * Z0 - 0.5 * X1".
-
-        let pauli_terms = self.parse_pauli_term(operator_string)?;
+    /// Splits a Hamiltonian string such as "1.5 * Z0 Z1 - 0.5 * X0" into
+    /// (coefficient, Pauli product) pairs. A term without an explicit coefficient
+    /// has coefficient 1, and a bare number is a constant (identity) term.
+    fn parse_hamiltonian(&self, hamiltonian_str: &str) -> Result<Vec<(f64, String)>, String> {
+        let mut raw_terms: Vec<String> = Vec::new();
+        let mut current = String::new();
+        let mut prev: Option<char> = None;
+        for c in hamiltonian_str.chars() {
+            // A sign directly after an exponent marker belongs to the number ("1e-3").
+            let is_exponent_sign = matches!(prev, Some('e') | Some('E'));
+            if (c == '+' || c == '-') && !is_exponent_sign && !current.trim().is_empty() {
+                raw_terms.push(std::mem::take(&mut current));
+            }
+            current.push(c);
+            if !c.is_whitespace() {
+                prev = Some(c);
+            }
+        }
+        if !current.trim().is_empty() {
+            raw_terms.push(current);
+        }
+        if raw_terms.is_empty() {
+            return Err("Empty Hamiltonian string".to_string());
+        }
 
-        let mut total_eigenvalue = 0.0;
+        raw_terms
+            .iter()
+            .map(|raw| {
+                let mut term = raw.trim();
+                let mut sign = 1.0;
+                if let Some(rest) = term.strip_prefix('-') {
+                    sign = -1.0;
+                    term = rest.trim_start();
+                } else if let Some(rest) = term.strip_prefix('+') {
+                    term = rest.trim_start();
+                }
 
-        for _ in 0..shots {
-            // In a real simulator, you would sample from the final state vector's probabilities.
-            // For this example, we'll simulate a simple case to demonstrate the logic.
-            // Let's assume the measurement always results in the |0...0> state.
-            let measurement_outcome = 0; // Represents the integer value of the bitstring, e.g., "01" -> 1
-
-            let mut shot_eigenvalue = 1.0;
-            for (pauli) in &pauli_terms {
-                // Get the bit value for the specific qubit from the measurement outcome.
-                let bit = (measurement_outcome >> pauli.target()[0]) & 1;
-
-                // Determine the eigenvalue (+1 or -1) for this Pauli measurement.
-                // For Z, |0> is +1, |1> is -1.
-                // For X and Y, the eigenvalue depends on the superposition, but for the
-                // basis states, we can define a consistent (though simplified) mapping.
-                let eigenvalue = match pauli {
-                    Gate::Z { .. } => {
-                        if bit == 0 {
-                            1.0
-                        } else {
-                            -1.0
+                let (coefficient, operators) = match term.split_once('*') {
+                    Some((coeff, ops)) => {
+                        let coeff = coeff
+                            .trim()
+                            .parse::<f64>()
+                            .map_err(|_| format!("Invalid coefficient in term '{}'", raw.trim()))?;
+                        (coeff, ops.trim())
+                    }
+                    None => {
+                        let mut parts = term.splitn(2, char::is_whitespace);
+                        let first = parts.next().unwrap_or("");
+                        match first.parse::<f64>() {
+                            Ok(coeff) => (coeff, parts.next().unwrap_or("").trim()),
+                            Err(_) => (1.0, term),
                         }
                     }
-                    // For a real simulation, X and Y measurements require basis changes before measuring.
-                    // Here we provide a placeholder result.
-                    Gate::X { .. } => 1.0,
-                    Gate::Y { .. } => 1.0,
-                    Gate::I { .. } => 1.0,
-                    _ => return Err(format!("Unsupported Pauli operator: {:?}", pauli)),
                 };
-                shot_eigenvalue *= eigenvalue;
+                Ok((sign * coefficient, operators.to_string()))
+            })
+            .collect()
+    }
+
+    /// Estimates ⟨H⟩ for a weighted sum of Pauli products, e.g.
+    /// "-0.81 + 0.17 * Z0 - 0.22 * Z1 + 0.05 * X0 X1", by measuring each term
+    /// with `shots` samples and summing `coeff * ⟨term⟩`.
+    pub fn measure_hamiltonian_expectation(
+        &self,
+        hamiltonian_str: &str,
+        shots: usize,
+    ) -> Result<f64, String> {
+        let mut total = 0.0;
+        for (coefficient, operators) in self.parse_hamiltonian(hamiltonian_str)? {
+            if operators.is_empty() {
+                total += coefficient;
+            } else {
+                total += coefficient * self.measure_expectation(&operators, shots)?;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Estimates ⟨P⟩ for a Pauli product like "Z0 X1" by sampling `shots` measurements.
+    /// X and Y factors are measured by first rotating into their eigenbasis (H for X,
+    /// S†·H for Y) on a copy of the state, so the simulator itself is left untouched.
+    pub fn measure_expectation(&self, operator_string: &str, shots: usize) -> Result<f64, String> {
+        if shots == 0 {
+            return Err("Number of shots must be greater than zero.".to_string());
+        }
+
+        let pauli_terms = self.parse_pauli_term(operator_string)?;
+
+        let mut rotated = self.state.clone();
+        let mut parity_mask = 0usize;
+        for pauli in &pauli_terms {
+            match pauli {
+                Gate::X { qubit } => {
+                    rotated.apply_single_qubit_gate(&HADAMARD, *qubit);
+                    parity_mask |= 1 << qubit;
+                }
+                Gate::Y { qubit } => {
+                    rotated.apply_single_qubit_gate(&S_DAGGER, *qubit);
+                    rotated.apply_single_qubit_gate(&HADAMARD, *qubit);
+                    parity_mask |= 1 << qubit;
+                }
+                Gate::Z { qubit } => parity_mask |= 1 << qubit,
+                Gate::I { .. } => {}
+                _ => return Err(format!("Unsupported Pauli operator: {:?}", pauli)),
             }
-            total_eigenvalue += shot_eigenvalue;
+        }
+
+        let probabilities: Vec<f64> = rotated.amplitudes.iter().map(|a| a.norm_sqr()).collect();
+        let dist = WeightedIndex::new(&probabilities).map_err(|e| e.to_string())?;
+        let mut rng = self.rng.borrow_mut();
+
+        let mut total_eigenvalue = 0.0;
+        for _ in 0..shots {
+            let outcome = dist.sample(&mut *rng);
+            // Each measured |1> on a non-identity qubit flips the sign of the eigenvalue.
+            total_eigenvalue += if (outcome & parity_mask).count_ones().is_multiple_of(2) {
+                1.0
+            } else {
+                -1.0
+            };
         }
 
         // The expectation value is the average of all the single-shot eigenvalues.
@@ -252,6 +437,11 @@ pub const PAULI_Y: GateMatrix = [
     [Complex::new(0.0, 1.0), Complex::new(0.0, 0.0)],
 ];
 
+pub const S_DAGGER: GateMatrix = [
+    [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+    [Complex::new(0.0, 0.0), Complex::new(0.0, -1.0)],
+];
+
 pub const PAULI_Z: GateMatrix = [
     [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
     [Complex::new(0.0, 0.0), Complex::new(-1.0, 0.0)],
@@ -269,13 +459,14 @@ pub fn construct_gate_matrix(gate: &Gate) -> Option<GateMatrix> {
                 Complex::new((theta / 2.0).cos(), 0.0),
             ],
         ]),
+        // RY is real-valued: [[cos, -sin], [sin, cos]]
         Gate::RY { qubit, theta } => Some([
             [
                 Complex::new((theta / 2.0).cos(), 0.0),
-                Complex::new(0.0, -(theta / 2.0).sin()),
+                Complex::new(-(theta / 2.0).sin(), 0.0),
             ],
             [
-                Complex::new(0.0, (theta / 2.0).sin()),
+                Complex::new((theta / 2.0).sin(), 0.0),
                 Complex::new((theta / 2.0).cos(), 0.0),
             ],
         ]),
@@ -297,66 +488,291 @@ pub fn construct_gate_matrix(gate: &Gate) -> Option<GateMatrix> {
 }
 
 pub fn run_simulation(qasm_input: &str) -> Option<Vec<Event>> {
-    let mut events = Vec::new();
+    let circuit = Circuit::from_qasm(qasm_input).ok()?;
+    if circuit.num_qubits == 0 {
+        eprintln!("Error: Could not determine number of qubits from QASM input.");
+        return None;
+    }
+    match run_circuit(&circuit) {
+        Ok(events) => Some(events),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            None
+        }
+    }
+}
 
-    let (num_qubits, gates) = parse_qasm(qasm_input);
-    if num_qubits == 0 {
+/// Like `run_simulation`, but samples the full-register measurement `shots` times from
+/// the pre-measurement state instead of collapsing it once. The single `MeasurementResult`
+/// is replaced by one `MeasurementCounts` event; a circuit without a `Measure` is sampled
+/// after its last gate.
+pub fn run_simulation_shots(qasm_input: &str, shots: u32) -> Option<Vec<Event>> {
+    let circuit = Circuit::from_qasm(qasm_input).ok()?;
+    if circuit.num_qubits == 0 {
         eprintln!("Error: Could not determine number of qubits from QASM input.");
         return None;
     }
+    if let Err(e) = circuit.validate() {
+        eprintln!("Error: {}", e);
+        return None;
+    }
 
+    let mut events = Vec::new();
+    let gates = circuit.gates_flat();
     events.push(Event::SimulationStart(SimulationStartInfo {
-        num_qubits,
+        num_qubits: circuit.num_qubits,
         num_gates: gates.len(),
     }));
 
-    let mut state = StateVector::new(num_qubits);
+    let mut state = StateVector::new(circuit.num_qubits);
     let mut rng = rand::thread_rng();
 
-    for (i, gate) in gates.iter().enumerate() {
-        let gate_str = format!("{:?}", gate);
-        match gate {
-            Gate::H { qubit } => state.apply_single_qubit_gate(&HADAMARD, *qubit),
-            Gate::X { qubit } => state.apply_single_qubit_gate(&PAULI_X, *qubit),
-            Gate::Y { qubit } => state.apply_single_qubit_gate(&PAULI_Y, *qubit),
-            Gate::Z { qubit } => state.apply_single_qubit_gate(&PAULI_Z, *qubit),
-            Gate::CX { control, target } | Gate::CNOT { control, target } => {
-                state.apply_cx(*control, *target)
-            }
-            Gate::Measure => {
-                let result = state.measure_all(&mut rng);
-
-                events.push(Event::MeasurementResult(MeasurementInfo {
-                    classical_outcome: result,
-                    binary_outcome: format!("{:b}", result),
-                    final_state_vector: state.clone(),
-                }));
-                return Some(events); // Simulation ends on measurement.
-            }
-            _ => {
-                eprintln!("Unsupported gate: {:?}", gate);
-                panic!("Unsupported gate type encountered during simulation.");
-            }
+    for (i, gate) in gates.into_iter().enumerate() {
+        if matches!(gate, Gate::Measure) {
+            break;
+        }
+        if let Err(e) = apply_event_gate(&mut state, gate, &mut rng, &mut events) {
+            eprintln!("Error: {}", e);
+            return None;
         }
-
         events.push(Event::GateApplication(GateInfo {
             step: i + 1,
-            gate: gate_str,
+            gate: format!("{:?}", gate),
             state_vector: state.clone(),
         }));
     }
+
+    events.push(Event::MeasurementCounts(MeasurementCountsInfo {
+        shots,
+        counts: state.sample_counts(shots).into_iter().collect(),
+        final_state_vector: state,
+    }));
     Some(events)
 }
 
+/// Simulates `circuit` gate by gate from |0..0>, producing the same event stream as
+/// `run_simulation`. A full-register `Measure` ends the run; a gate that cannot be applied
+/// (such as an invalid `Unitary` or one on a qubit outside the register) is returned as an
+/// error.
+pub fn run_circuit(circuit: &Circuit) -> Result<Vec<Event>, SimError> {
+    circuit.validate()?;
+    let mut events = Vec::new();
+    let gates = circuit.gates_flat();
+
+    events.push(Event::SimulationStart(SimulationStartInfo {
+        num_qubits: circuit.num_qubits,
+        num_gates: gates.len(),
+    }));
+
+    let mut state = StateVector::new(circuit.num_qubits);
+    let mut rng = rand::thread_rng();
+
+    for (i, gate) in gates.into_iter().enumerate() {
+        if !apply_event_gate(&mut state, gate, &mut rng, &mut events)? {
+            return Ok(events); // Simulation ends on measurement.
+        }
+        events.push(Event::GateApplication(GateInfo {
+            step: i + 1,
+            gate: format!("{:?}", gate),
+            state_vector: state.clone(),
+        }));
+    }
+    Ok(events)
+}
+
+/// Like `run_circuit`, but emits one `MomentApplication` per moment so that gates which
+/// run in parallel show up together in a single frame.
+pub fn run_circuit_by_moment(circuit: &Circuit) -> Result<Vec<Event>, SimError> {
+    circuit.validate()?;
+    let mut events = Vec::new();
+
+    events.push(Event::SimulationStart(SimulationStartInfo {
+        num_qubits: circuit.num_qubits,
+        num_gates: circuit.gates_flat().len(),
+    }));
+
+    let mut state = StateVector::new(circuit.num_qubits);
+    let mut rng = rand::thread_rng();
+
+    for (i, moment) in circuit.moments.iter().enumerate() {
+        for gate in moment {
+            if !apply_event_gate(&mut state, gate, &mut rng, &mut events)? {
+                return Ok(events); // Simulation ends on measurement.
+            }
+        }
+        events.push(Event::MomentApplication(MomentInfo {
+            step: i + 1,
+            gates: moment.iter().map(|g| format!("{:?}", g)).collect(),
+            state_vector: state.clone(),
+        }));
+    }
+    Ok(events)
+}
+
+/// Applies one gate for the event-producing runners, recording any measurement event.
+/// Returns `Ok(false)` once a full-register measurement has ended the simulation.
+fn apply_event_gate(
+    state: &mut StateVector,
+    gate: &Gate,
+    rng: &mut impl rand::Rng,
+    events: &mut Vec<Event>,
+) -> Result<bool, SimError> {
+    match gate {
+        Gate::I { .. } | Gate::Barrier { .. } => {}
+        Gate::H { qubit } => state.apply_single_qubit_gate(&HADAMARD, *qubit),
+        Gate::X { qubit } => state.apply_single_qubit_gate(&PAULI_X, *qubit),
+        Gate::Y { qubit } => state.apply_single_qubit_gate(&PAULI_Y, *qubit),
+        Gate::Z { qubit } => state.apply_single_qubit_gate(&PAULI_Z, *qubit),
+        Gate::RX { qubit, .. } | Gate::RY { qubit, .. } | Gate::RZ { qubit, .. } => {
+            let matrix = construct_gate_matrix(gate).expect("rotation gates have a matrix");
+            state.apply_single_qubit_gate(&matrix, *qubit)
+        }
+        Gate::CX { control, target } | Gate::CNOT { control, target } => {
+            state.apply_cx(*control, *target)
+        }
+        Gate::CP {
+            control,
+            target,
+            theta,
+        } => state.apply_cp(*control, *target, *theta),
+        Gate::Measure => {
+            let result = state.measure_all(rng);
+
+            events.push(Event::MeasurementResult(MeasurementInfo {
+                classical_outcome: result,
+                binary_outcome: format!("{:b}", result),
+                final_state_vector: state.clone(),
+                qubit: None,
+                creg_bit: None,
+            }));
+            return Ok(false);
+        }
+        Gate::MeasureQubit { qubit, creg_bit } => {
+            let outcome = state.measure_qubit_in_z(*qubit, rng);
+
+            events.push(Event::MeasurementResult(MeasurementInfo {
+                classical_outcome: outcome as usize,
+                binary_outcome: outcome.to_string(),
+                final_state_vector: state.clone(),
+                qubit: Some(*qubit),
+                creg_bit: Some(*creg_bit),
+            }));
+        }
+        Gate::GPhase { theta } => state.apply_global_phase(*theta),
+        Gate::Unitary { qubits, matrix } => state.apply_arbitrary_gate(matrix, qubits)?,
+        Gate::Reset { qubit } => state.reset_qubit(*qubit, rng),
+    }
+    Ok(true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::SimulatorApi;
+    use crate::circuit::gates_to_circuit;
+    use crate::statevector_backend::StatevectorSimulator;
     const EPSILON: f64 = 1e-9;
 
     fn approx_eq(a: Complex<f64>, b: Complex<f64>) -> bool {
         (a.re - b.re).abs() < EPSILON && (a.im - b.im).abs() < EPSILON
     }
 
+    #[test]
+    fn test_same_seed_gives_identical_samples() {
+        let mut circuit = Circuit::with_qubits(3);
+        circuit.add_moment(vec![Gate::h(0), Gate::h(1), Gate::ry(2, 1.0)]);
+        circuit.add_gate(Gate::measure_qubit(0, 0));
+        circuit.add_gate(Gate::cx(1, 2));
+
+        let run = |seed: u64| {
+            let mut sim = QuantumSimulator::with_seed(3, seed);
+            sim.apply_circuit(&circuit).unwrap();
+            (
+                sim.state.amplitudes.clone(),
+                sim.sample_counts(500),
+                sim.measure_expectation("Z1 X2", 200).unwrap(),
+            )
+        };
+        let (state_a, counts_a, expectation_a) = run(42);
+        let (state_b, counts_b, expectation_b) = run(42);
+        assert_eq!(state_a, state_b);
+        assert_eq!(counts_a, counts_b);
+        assert_eq!(expectation_a, expectation_b);
+        assert!(counts_a.len() > 1);
+    }
+
+    #[test]
+    fn test_invalid_unitary_is_an_error_on_both_backends() {
+        let mut circuit = Circuit::with_qubits(2);
+        circuit.add_gate(Gate::h(0));
+        // A 2x2 matrix cannot act on two qubits.
+        circuit.add_gate(Gate::Unitary {
+            qubits: vec![0, 1],
+            matrix: vec![
+                vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+                vec![Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+            ],
+        });
+
+        assert!(QuantumSimulator::new(2).apply_circuit(&circuit).is_err());
+        assert!(StatevectorSimulator::new(2).run(&circuit).is_err());
+        assert!(run_circuit(&circuit).is_err());
+        assert!(run_circuit_by_moment(&circuit).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_qubit_is_an_error_instead_of_a_panic() {
+        let mut circuit = Circuit::with_qubits(2);
+        circuit.add_gate(Gate::h(5));
+
+        assert!(matches!(run_circuit(&circuit), Err(SimError::Qubit(5))));
+        assert!(matches!(
+            run_circuit_by_moment(&circuit),
+            Err(SimError::Qubit(5))
+        ));
+        let qasm = "OPENQASM 2.0;\nqreg q[2];\nh q[5];\n";
+        assert!(run_simulation(qasm).is_none());
+        assert!(run_simulation_shots(qasm, 10).is_none());
+    }
+
+    #[test]
+    fn test_apply_circuit_with_hook_sees_each_gate() {
+        let mut circuit = Circuit::with_qubits(2);
+        circuit.add_moment(vec![Gate::h(0), Gate::x(1)]);
+        circuit.add_gate(Gate::cx(0, 1));
+        circuit.add_gate(Gate::rz(1, 0.5));
+
+        let mut sim = QuantumSimulator::new(2);
+        let mut steps = Vec::new();
+        let mut gates = Vec::new();
+        let mut after_h = None;
+        sim.apply_circuit_with_hook(&circuit, |step, gate, state| {
+            steps.push(step);
+            gates.push(gate.clone());
+            if step == 0 {
+                after_h = Some(state.amplitudes.clone());
+            }
+        })
+        .unwrap();
+
+        assert_eq!(steps, vec![0, 1, 2, 3]);
+        assert_eq!(
+            gates,
+            circuit
+                .gates_flat()
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        let after_h = after_h.unwrap();
+        assert!(approx_eq(after_h[0], Complex::new(FRAC_1_SQRT_2, 0.0)));
+        assert!(approx_eq(after_h[1], Complex::new(FRAC_1_SQRT_2, 0.0)));
+
+        let mut plain = QuantumSimulator::new(2);
+        plain.apply_circuit(&circuit).unwrap();
+        assert_eq!(sim.state.amplitudes, plain.state.amplitudes);
+    }
+
     #[test]
     fn test_bell_state_simulation() {
         let mut state = StateVector::new(2);
@@ -367,5 +783,317 @@ mod tests {
         assert!(approx_eq(state.amplitudes[1], Complex::new(0.0, 0.0)));
         assert!(approx_eq(state.amplitudes[2], Complex::new(0.0, 0.0)));
         assert!(approx_eq(state.amplitudes[3], expected_amp));
+        assert!(state.approx_eq_up_to_global_phase(&bell_state_with_phase(1.3), EPSILON));
+    }
+
+    /// e^{i phi} (|00> + |11>)/√2
+    fn bell_state_with_phase(phi: f64) -> StateVector {
+        let amp = Complex::from_polar(FRAC_1_SQRT_2, phi);
+        let zero = Complex::new(0.0, 0.0);
+        let mut state = StateVector::from(vec![amp, zero, zero, amp]);
+        state.num_qubits = 2;
+        state
+    }
+
+    #[test]
+    fn test_bell_state_from_shorthand_gates() {
+        let mut sim = QuantumSimulator::new(2);
+        sim.apply_gate(&Gate::h(0));
+        sim.apply_gate(&Gate::cx(0, 1));
+        assert!(
+            sim.state
+                .approx_eq_up_to_global_phase(&bell_state_with_phase(-0.4), EPSILON)
+        );
+    }
+
+    #[test]
+    fn test_run_simulation_shots_samples_bell_state() {
+        let qasm = r#"
+            OPENQASM 2.0;
+            qreg q[2];
+            creg c[2];
+            h q[0];
+            cx q[0],q[1];
+            measure q -> c;
+        "#;
+        let events = run_simulation_shots(qasm, 1000).unwrap();
+
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, Event::MeasurementResult(_)))
+        );
+        match events.last().unwrap() {
+            Event::MeasurementCounts(info) => {
+                assert_eq!(info.shots, 1000);
+                assert!(info.counts.keys().all(|k| k == "00" || k == "11"));
+                assert_eq!(info.counts.values().sum::<u32>(), 1000);
+                // The pre-measurement state is left uncollapsed.
+                assert!(
+                    info.final_state_vector
+                        .approx_eq_up_to_global_phase(&bell_state_with_phase(0.0), EPSILON)
+                );
+            }
+            other => panic!("expected measurement counts, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_run_simulation_continues_after_single_qubit_measurements() {
+        let qasm = r#"
+            OPENQASM 2.0;
+            qreg q[2];
+            creg c[2];
+            x q[0];
+            measure q[0] -> c[0];
+            measure q[1] -> c[1];
+            x q[1];
+        "#;
+        let events = run_simulation(qasm).unwrap();
+
+        let measurements: Vec<&MeasurementInfo> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::MeasurementResult(m) => Some(m),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(measurements.len(), 2);
+        assert_eq!(measurements[0].classical_outcome, 1);
+        assert_eq!(measurements[0].creg_bit, Some(0));
+        assert_eq!(measurements[1].classical_outcome, 0);
+        assert_eq!(measurements[1].qubit, Some(1));
+
+        // The trailing X still ran, leaving |11>.
+        match events.last().unwrap() {
+            Event::GateApplication(info) => {
+                assert!(approx_eq(
+                    info.state_vector.amplitudes[3],
+                    Complex::new(1.0, 0.0)
+                ))
+            }
+            other => panic!("expected a gate application, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_run_circuit_emits_start_plus_one_event_per_gate() {
+        let circuit = gates_to_circuit(vec![
+            Gate::h(0),
+            Gate::cx(0, 1),
+            Gate::ry(2, 0.3),
+            Gate::cp(1, 2, 0.5),
+        ]);
+        let events = run_circuit(&circuit).unwrap();
+
+        assert_eq!(events.len(), circuit.gates_flat().len() + 1);
+        assert!(matches!(
+            events[0],
+            Event::SimulationStart(SimulationStartInfo {
+                num_qubits: 3,
+                num_gates: 4
+            })
+        ));
+        assert!(
+            events[1..]
+                .iter()
+                .all(|e| matches!(e, Event::GateApplication(_)))
+        );
+    }
+
+    #[test]
+    fn test_run_circuit_by_moment_groups_parallel_gates() {
+        let mut circuit = Circuit::with_qubits(2);
+        circuit.add_moment(vec![Gate::h(0), Gate::h(1)]);
+        circuit.add_gate(Gate::cx(0, 1));
+        let events = run_circuit_by_moment(&circuit).unwrap();
+
+        let moments: Vec<&MomentInfo> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::MomentApplication(m) => Some(m),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(moments.len(), 2);
+        assert_eq!(moments[0].step, 1);
+        assert_eq!(
+            moments[0].gates,
+            vec![format!("{:?}", Gate::h(0)), format!("{:?}", Gate::h(1))]
+        );
+        // After H⊗H every basis state has amplitude 1/2.
+        assert!(
+            moments[0]
+                .state_vector
+                .amplitudes
+                .iter()
+                .all(|a| approx_eq(*a, Complex::new(0.5, 0.0)))
+        );
+        assert_eq!(moments[1].gates.len(), 1);
+    }
+
+    #[test]
+    fn test_measure_expectation_in_x_and_z_bases() {
+        let sim = QuantumSimulator::new(1);
+        assert_eq!(sim.measure_expectation("Z0", 100).unwrap(), 1.0);
+
+        let mut plus = QuantumSimulator::new(1);
+        plus.apply_gate(&Gate::H { qubit: 0 });
+        let x = plus.measure_expectation("X0", 1000).unwrap();
+        assert!((x - 1.0).abs() < 0.05, "<X> on |+> was {}", x);
+        let z = plus.measure_expectation("Z0", 4000).unwrap();
+        assert!(z.abs() < 0.1, "<Z> on |+> was {}", z);
+    }
+
+    #[test]
+    fn test_measure_expectation_in_y_basis() {
+        // RX(-pi/2)|0> = |+i>, the +1 eigenstate of Y
+        let mut sim = QuantumSimulator::new(1);
+        sim.apply_gate(&Gate::RX {
+            qubit: 0,
+            theta: -std::f64::consts::FRAC_PI_2,
+        });
+        let y = sim.measure_expectation("Y0", 1000).unwrap();
+        assert!((y - 1.0).abs() < 0.05, "<Y> on |+i> was {}", y);
+    }
+
+    #[test]
+    fn test_measure_hamiltonian_expectation_h2() {
+        // H2 at equilibrium, evaluated on |01> (qubit 0 flipped): Z0 = -1, Z1 = +1,
+        // Z0 Z1 = -1 and <X0 X1> = 0, so only sampling noise on the XX term remains.
+        let h2 = "-0.8126 + 0.1712 * Z0 - 0.2228 * Z1 + 0.1686 * Z0 Z1 + 0.0453 * X0 X1";
+        let mut sim = QuantumSimulator::new(2);
+        sim.apply_gate(&Gate::X { qubit: 0 });
+
+        let expected = -0.8126 - 0.1712 - 0.2228 - 0.1686;
+        let energy = sim.measure_hamiltonian_expectation(h2, 4000).unwrap();
+        assert!((energy - expected).abs() < 0.01, "<H> was {}", energy);
+
+        // A bare product still works and an unparsable coefficient is rejected.
+        assert_eq!(sim.measure_hamiltonian_expectation("Z1", 10).unwrap(), 1.0);
+        assert!(sim.measure_hamiltonian_expectation("abc * Z0", 10).is_err());
+    }
+
+    #[test]
+    fn test_ry_pi_maps_zero_to_one_with_real_amplitude() {
+        let mut sim = QuantumSimulator::new(1);
+        sim.apply_gate(&Gate::RY {
+            qubit: 0,
+            theta: std::f64::consts::PI,
+        });
+
+        assert!(approx_eq(sim.state.amplitudes[0], Complex::new(0.0, 0.0)));
+        assert!(approx_eq(sim.state.amplitudes[1], Complex::new(1.0, 0.0)));
+    }
+
+    /// Applies `gates` to |0..0> on both backends and returns their final amplitudes.
+    fn run_on_both_backends(
+        num_qubits: usize,
+        gates: &[Gate],
+    ) -> (Vec<Complex<f64>>, Vec<Complex<f64>>) {
+        let mut qs = QuantumSimulator::new(num_qubits);
+        for gate in gates {
+            qs.apply_gate(gate);
+        }
+
+        let mut circuit = Circuit::with_qubits(num_qubits);
+        for gate in gates {
+            circuit.add_gate(gate.clone());
+        }
+        let mut sv = StatevectorSimulator::new(num_qubits);
+        sv.run(&circuit).unwrap();
+
+        (qs.state.amplitudes, sv.statevector().amplitudes.clone())
+    }
+
+    #[test]
+    fn test_ry_agrees_across_backends() {
+        let (qs, sv) = run_on_both_backends(1, &[Gate::ry(0, std::f64::consts::PI)]);
+        for amps in [&qs, &sv] {
+            assert!(approx_eq(amps[0], Complex::new(0.0, 0.0)));
+            assert!(approx_eq(amps[1], Complex::new(1.0, 0.0)));
+        }
+
+        let (qs, sv) = run_on_both_backends(2, &[Gate::h(1), Gate::ry(0, 0.7), Gate::ry(1, -1.3)]);
+        for (a, b) in qs.iter().zip(sv.iter()) {
+            assert!(approx_eq(*a, *b), "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_rx_agrees_across_backends() {
+        // RX(π/2)|0> = (|0> - i|1>)/√2
+        let (qs, sv) = run_on_both_backends(1, &[Gate::rx(0, std::f64::consts::FRAC_PI_2)]);
+        let expected = [
+            Complex::new(FRAC_1_SQRT_2, 0.0),
+            Complex::new(0.0, -FRAC_1_SQRT_2),
+        ];
+        for (i, amp) in expected.iter().enumerate() {
+            assert!(approx_eq(qs[i], *amp), "QuantumSimulator: {}", qs[i]);
+            assert!(approx_eq(sv[i], *amp), "StatevectorSimulator: {}", sv[i]);
+        }
+    }
+
+    #[test]
+    fn test_prepare_initial_state_validates_input() {
+        let mut sim = QuantumSimulator::new(3);
+        sim.prepare_initial_state(&[0, 1]).unwrap();
+        // "010" little-endian is index 2.
+        assert!(approx_eq(sim.state.amplitudes[2], Complex::new(1.0, 0.0)));
+
+        let mut sim = QuantumSimulator::new(2);
+        assert!(sim.prepare_initial_state(&[1, 0, 1]).is_err());
+        assert!(sim.prepare_initial_state(&[1, 2]).is_err());
+        // A rejected input leaves the state untouched.
+        assert!(approx_eq(sim.state.amplitudes[0], Complex::new(1.0, 0.0)));
+    }
+
+    #[test]
+    fn test_sample_counts_on_bell_state() {
+        let mut sim = QuantumSimulator::new(2);
+        sim.apply_gate(&Gate::h(0));
+        sim.apply_gate(&Gate::cx(0, 1));
+
+        let counts = sim.sample_counts(1000);
+        assert_eq!(counts.values().sum::<u32>(), 1000);
+        assert!(
+            counts.keys().all(|k| k == "00" || k == "11"),
+            "{:?}",
+            counts
+        );
+        assert!(counts.contains_key("00") && counts.contains_key("11"));
+    }
+
+    #[test]
+    fn test_expectation_pauli_string_does_not_disturb_state() {
+        // |+0>: <X0> = 1, <Z1> = 1, <Z0> = 0.
+        let mut sim = QuantumSimulator::new(2);
+        sim.apply_gate(&Gate::h(0));
+        let before = sim.state.amplitudes.clone();
+
+        let x0 = sim.expectation_pauli_string(&[(Pauli::X, 0)]);
+        let z0 = sim.expectation_pauli_string(&[(Pauli::Z, 0)]);
+        let z1 = sim.expectation_pauli_string(&[(Pauli::Z, 1)]);
+        assert!((x0 - 1.0).abs() < 1e-9, "<X0> = {}", x0);
+        assert!(z0.abs() < 1e-9, "<Z0> = {}", z0);
+        assert!((z1 - 1.0).abs() < 1e-9, "<Z1> = {}", z1);
+        assert_eq!(sim.state.amplitudes, before);
+    }
+
+    #[test]
+    fn test_parse_pauli_term_tokens() {
+        let sim = QuantumSimulator::new(11);
+        assert_eq!(
+            sim.parse_pauli_term("Z10 x0").unwrap(),
+            vec![Gate::Z { qubit: 10 }, Gate::X { qubit: 0 }]
+        );
+
+        let err = sim.parse_pauli_term("XY0").unwrap_err();
+        assert!(err.contains("Malformed"), "{}", err);
+        assert!(sim.parse_pauli_term("Z").is_err());
+        assert!(sim.parse_pauli_term("Z+1").is_err());
+        assert!(sim.parse_pauli_term("Z99999999999999999999999").is_err());
+        assert!(sim.parse_pauli_term("Z11").is_err());
+        assert!(sim.parse_pauli_term("\u{1e90}0").is_err());
     }
 }
@@ -1,4 +1,4 @@
-use crate::api::Pauli;
+use crate::api::{Pauli, SimError};
 use crate::simulator::QuantumGate;
 use num_complex::Complex;
 use rand::Rng;
@@ -7,11 +7,18 @@ use serde::Serialize;
 use std::collections::HashMap;
 use std::ops::Deref;
 
+/// The largest register `StateVector::new` will allocate: 2^28 amplitudes is 4 GiB.
+pub const DEFAULT_MAX_QUBITS: usize = 28;
+
 #[derive(Serialize, Clone, Debug)]
 pub struct StateVector {
     pub num_qubits: usize,
     #[serde(rename = "amplitudes")]
     pub amplitudes: Vec<Complex<f64>>,
+    /// Overall phase factor of the state; the physical state is `global_phase * amplitudes`.
+    /// It is unobservable on its own but becomes a relative phase when the circuit is controlled.
+    #[serde(skip)]
+    pub global_phase: Complex<f64>,
 }
 
 impl StateVector {
@@ -53,6 +60,19 @@ impl StateVector {
         outcome
     }
 
+    /// Returns `qubit` to |0⟩ by measuring it in Z and flipping it back on outcome 1, so the
+    /// rest of the register collapses to the branch that was observed.
+    pub fn reset_qubit<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) {
+        if self.measure_qubit_in_z(qubit, rng) == 1 {
+            let stride = 1usize << qubit;
+            for i in 0..self.amplitudes.len() {
+                if i & stride != 0 {
+                    self.amplitudes.swap(i, i ^ stride);
+                }
+            }
+        }
+    }
+
     /// ⟨ψ|P|ψ⟩ for a Pauli string, non-destructive.
     pub fn expectation_pauli_string(&self, ops: &[(Pauli, usize)]) -> f64 {
         // Build |φ⟩ = P|ψ⟩ by applying each single-qubit Pauli to a clone
@@ -93,16 +113,25 @@ impl StateVector {
 
     /// Sample computational-basis outcomes `shots` times and return counts.
     pub fn sample_counts(&self, shots: u32) -> HashMap<String, u32> {
+        self.sample_counts_with_rng(shots, &mut rand::thread_rng())
+    }
+
+    /// Like `sample_counts`, drawing from `rng` so that a seeded generator gives
+    /// reproducible counts.
+    pub fn sample_counts_with_rng<R: Rng + ?Sized>(
+        &self,
+        shots: u32,
+        rng: &mut R,
+    ) -> HashMap<String, u32> {
         let probs: Vec<f64> = self.amplitudes.iter().map(|a| a.norm_sqr()).collect();
         // WeightedIndex expects nonnegative and (usually) sums to ~1
         let dist = WeightedIndex::new(&probs).expect("invalid probability distribution");
 
-        let mut rng = rand::thread_rng();
         let mut counts: HashMap<String, u32> = HashMap::new();
         let width = self.num_qubits;
 
         for _ in 0..shots {
-            let idx = dist.sample(&mut rng);
+            let idx = dist.sample(rng);
             // bitstring with q_{width-1} ... q_0 (MSB..LSB)
             let bitstr = format!("{:0width$b}", idx, width = width);
             *counts.entry(bitstr).or_insert(0) += 1;
@@ -111,16 +140,143 @@ impl StateVector {
     }
 }
 
+/// Estimates ⟨Z_{q0} Z_{q1} …⟩ from computational-basis counts, e.g. hardware results or
+/// `StateVector::sample_counts`. Each shot contributes +1 if the selected bits have even
+/// parity and -1 otherwise. Bitstrings use the `sample_counts` layout, with qubit 0 as the
+/// rightmost character. Returns 0.0 if there are no shots.
+///
+/// Panics if a bitstring is too short for the requested qubits.
+pub fn expectation_from_counts(counts: &HashMap<String, u32>, qubits: &[usize]) -> f64 {
+    let mut total = 0u64;
+    let mut signed = 0i64;
+    for (bitstring, &count) in counts {
+        let bits = bitstring.as_bytes();
+        let ones = qubits
+            .iter()
+            .filter(|&&q| {
+                assert!(
+                    q < bits.len(),
+                    "qubit {} out of range for bitstring '{}'",
+                    q,
+                    bitstring
+                );
+                bits[bits.len() - 1 - q] == b'1'
+            })
+            .count();
+        let sign = if ones % 2 == 0 { 1 } else { -1 };
+        signed += sign * count as i64;
+        total += count as u64;
+    }
+    if total == 0 {
+        return 0.0;
+    }
+    signed as f64 / total as f64
+}
+
 impl StateVector {
+    /// Panics if `num_qubits` exceeds [`DEFAULT_MAX_QUBITS`]; use [`StateVector::try_new`]
+    /// to handle that case.
     pub fn new(num_qubits: usize) -> Self {
+        Self::try_new(num_qubits).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(num_qubits: usize) -> Result<Self, SimError> {
+        Self::try_new_with_limit(num_qubits, DEFAULT_MAX_QUBITS)
+    }
+
+    /// Allocates |0..0⟩ on `num_qubits` qubits, refusing anything above `max_qubits`
+    /// rather than attempting a 2^n allocation that would overflow or exhaust memory.
+    pub fn try_new_with_limit(num_qubits: usize, max_qubits: usize) -> Result<Self, SimError> {
+        if num_qubits > max_qubits {
+            return Err(SimError::TooManyQubits {
+                requested: num_qubits,
+                max: max_qubits,
+            });
+        }
         let size = 1 << num_qubits; // 2^num_qubits
         let mut amplitudes = vec![Complex::new(0.0, 0.0); size];
         if !amplitudes.is_empty() {
             amplitudes[0] = Complex::new(1.0, 0.0);
         }
-        Self {
+        Ok(Self {
             num_qubits,
             amplitudes,
+            global_phase: Complex::new(1.0, 0.0),
+        })
+    }
+
+    /// Builds a state from explicit amplitudes, checking that there are 2^n of them and
+    /// that they are normalized to within 1e-9.
+    pub fn from_amplitudes(amplitudes: Vec<Complex<f64>>) -> Result<Self, SimError> {
+        if !amplitudes.len().is_power_of_two() {
+            return Err(SimError::State(format!(
+                "length {} is not a power of two",
+                amplitudes.len()
+            )));
+        }
+        let norm_sqr: f64 = amplitudes.iter().map(|a| a.norm_sqr()).sum();
+        if (norm_sqr - 1.0).abs() > 1e-9 {
+            return Err(SimError::State(format!(
+                "squared norm is {}, expected 1",
+                norm_sqr
+            )));
+        }
+        Ok(StateVector {
+            num_qubits: amplitudes.len().trailing_zeros() as usize,
+            amplitudes,
+            global_phase: Complex::new(1.0, 0.0),
+        })
+    }
+
+    /// The Euclidean norm of the amplitudes; 1 for a valid state.
+    pub fn norm(&self) -> f64 {
+        self.amplitudes
+            .iter()
+            .map(|a| a.norm_sqr())
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    pub fn is_normalized(&self, tol: f64) -> bool {
+        (self.norm() - 1.0).abs() <= tol
+    }
+
+    /// Rescales the amplitudes to unit norm, undoing accumulated floating-point drift.
+    /// A zero vector is left unchanged.
+    pub fn normalize(&mut self) {
+        let norm = self.norm();
+        if norm > 0.0 {
+            for amp in &mut self.amplitudes {
+                *amp /= norm;
+            }
+        }
+    }
+
+    /// Multiplies the global phase by e^{i theta}.
+    pub fn apply_global_phase(&mut self, theta: f64) {
+        self.global_phase *= Complex::from_polar(1.0, theta);
+    }
+
+    /// Applies `phase` to every basis state where `control` is |1⟩. This is what a global
+    /// phase of a sub-circuit turns into once that sub-circuit is controlled on `control`.
+    pub fn apply_controlled_global_phase(&mut self, control: usize, phase: Complex<f64>) {
+        let mask = 1 << control;
+        for (i, amp) in self.amplitudes.iter_mut().enumerate() {
+            if i & mask != 0 {
+                *amp *= phase;
+            }
+        }
+    }
+
+    /// Multiplies the amplitude of every basis state with both `control` and `target` set
+    /// by e^{i theta}. The gate is symmetric in its two qubits.
+    pub fn apply_cp(&mut self, control: usize, target: usize, theta: f64) {
+        let mask = (1 << control) | (1 << target);
+        let phase = Complex::from_polar(1.0, theta);
+        for (i, amp) in self.amplitudes.iter_mut().enumerate() {
+            if i & mask == mask {
+                *amp *= phase;
+            }
         }
     }
 
@@ -129,80 +285,80 @@ impl StateVector {
         gate_matrix: &[[Complex<f64>; 2]; 2],
         target_qubit: usize,
     ) {
-        let mut new_amplitudes = self.amplitudes.clone();
         let k = 1 << target_qubit;
 
+        // Each (i, i|k) pair is only touched once, so it can be updated in place.
         for i in 0..self.amplitudes.len() {
             if (i & k) == 0 {
                 let j = i | k;
                 let amp_i = self.amplitudes[i];
                 let amp_j = self.amplitudes[j];
 
-                new_amplitudes[i] = gate_matrix[0][0] * amp_i + gate_matrix[0][1] * amp_j;
-                new_amplitudes[j] = gate_matrix[1][0] * amp_i + gate_matrix[1][1] * amp_j;
+                self.amplitudes[i] = gate_matrix[0][0] * amp_i + gate_matrix[0][1] * amp_j;
+                self.amplitudes[j] = gate_matrix[1][0] * amp_i + gate_matrix[1][1] * amp_j;
             }
         }
-        self.amplitudes = new_amplitudes;
     }
 
-    pub fn apply_multi_qubit_gate(
+    /// Applies an arbitrary 2^k x 2^k matrix to the `k` qubits in `target_qubits`.
+    /// `target_qubits[0]` is the least significant bit of the matrix row/column index.
+    pub fn apply_arbitrary_gate(
         &mut self,
-        gate_matrix: &[[Complex<f64>; 2]; 2],
+        gate_matrix: &[Vec<Complex<f64>>],
         target_qubits: &[usize],
-    ) {
-        let n = target_qubits.len();
-        let mut new_amplitudes = self.amplitudes.clone();
+    ) -> Result<(), SimError> {
+        let k = target_qubits.len();
+        let dim = 1usize << k;
+        if gate_matrix.len() != dim || gate_matrix.iter().any(|row| row.len() != dim) {
+            return Err(SimError::Matrix(format!(
+                "expected a {}x{} matrix for {} qubit(s)",
+                dim, dim, k
+            )));
+        }
+        for (i, &q) in target_qubits.iter().enumerate() {
+            if q >= self.num_qubits {
+                return Err(SimError::Qubit(q));
+            }
+            if target_qubits[..i].contains(&q) {
+                return Err(SimError::Matrix(format!("duplicate target qubit {}", q)));
+            }
+        }
 
-        for i in 0..self.amplitudes.len() {
-            // Find the basis state indices for the subspace spanned by the target qubits
-            let mut basis_indices = Vec::with_capacity(1 << n);
-            for b in 0..(1 << n) {
-                let mut idx = i;
-                for (bit_pos, &qubit) in target_qubits.iter().enumerate() {
-                    let bit = (b >> bit_pos) & 1;
-                    if bit == 1 {
-                        idx |= 1 << qubit;
-                    } else {
-                        idx &= !(1 << qubit);
-                    }
-                }
-                basis_indices.push(idx);
+        let target_mask: usize = target_qubits.iter().map(|&q| 1usize << q).sum();
+        let mut amps = vec![Complex::new(0.0, 0.0); dim];
+        let mut indices = vec![0usize; dim];
+
+        // Visit each subspace once, via its representative with all target bits cleared.
+        for base in 0..self.amplitudes.len() {
+            if base & target_mask != 0 {
+                continue;
             }
-            // Only update amplitudes for the "lowest" representative in each subspace
-            if basis_indices[0] == i {
-                let mut amps = vec![Complex::new(0.0, 0.0); 1 << n];
-                for (j, &idx) in basis_indices.iter().enumerate() {
-                    amps[j] = self.amplitudes[idx];
-                }
-                // Apply the gate matrix (assumed to be 2^n x 2^n)
-                let gate_size = 1 << n;
-                let gate: &[[Complex<f64>; 2]] = gate_matrix as &[_];
-                let mut new_amps = vec![Complex::new(0.0, 0.0); gate_size];
-                for row in 0..gate_size {
-                    for col in 0..gate_size {
-                        new_amps[row] += gate[row][col] * amps[col];
-                    }
-                }
-                for (j, &idx) in basis_indices.iter().enumerate() {
-                    new_amplitudes[idx] = new_amps[j];
-                }
+            for (local, idx) in indices.iter_mut().enumerate() {
+                *idx = target_qubits
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit_pos, _)| (local >> bit_pos) & 1 == 1)
+                    .fold(base, |acc, (_, &q)| acc | (1 << q));
+            }
+            for (amp, &idx) in amps.iter_mut().zip(indices.iter()) {
+                *amp = self.amplitudes[idx];
+            }
+            for (row, &idx) in gate_matrix.iter().zip(indices.iter()) {
+                self.amplitudes[idx] = row.iter().zip(amps.iter()).map(|(m, a)| m * a).sum();
             }
         }
-        self.amplitudes = new_amplitudes;
+        Ok(())
     }
 
     pub fn apply_cx(&mut self, control_qubit: usize, target_qubit: usize) {
-        let mut new_amplitudes = self.amplitudes.clone();
         let control_mask = 1 << control_qubit;
         let target_mask = 1 << target_qubit;
 
         for i in 0..self.amplitudes.len() {
             if (i & control_mask) != 0 && (i & target_mask) == 0 {
-                let j = i | target_mask;
-                new_amplitudes.swap(i, j);
+                self.amplitudes.swap(i, i | target_mask);
             }
         }
-        self.amplitudes = new_amplitudes;
     }
 
     pub fn measure_all(&mut self, rng: &mut impl Rng) -> usize {
@@ -228,30 +384,163 @@ impl StateVector {
         if !self.amplitudes.is_empty() {
             self.amplitudes[0] = Complex::new(1.0, 0.0);
         }
+        self.global_phase = Complex::new(1.0, 0.0);
     }
 
-    // fidelity = |⟨ψ|φ⟩|²
-    pub fn fidelity(&self, other: &StateVector) -> f64 {
+    /// Kronecker product `self ⊗ other` on `self.num_qubits + other.num_qubits` qubits.
+    /// `other` keeps qubit indices 0..m and `self`'s qubits move up to m..m+n, so the
+    /// amplitude at `i * 2^m + j` is `self[i] * other[j]`, e.g. |1⟩ ⊗ |0⟩ is basis state 2.
+    pub fn tensor(&self, other: &StateVector) -> StateVector {
+        let mut amplitudes = Vec::with_capacity(self.amplitudes.len() * other.amplitudes.len());
+        for a in &self.amplitudes {
+            for b in &other.amplitudes {
+                amplitudes.push(a * b);
+            }
+        }
+        StateVector {
+            num_qubits: self.num_qubits + other.num_qubits,
+            amplitudes,
+            global_phase: self.global_phase * other.global_phase,
+        }
+    }
+
+    /// Reduced density matrix of the qubits in `keep`, tracing out all the others.
+    /// `keep[0]` is the least significant bit of the row/column index.
+    pub fn reduced_density_matrix(&self, keep: &[usize]) -> Vec<Vec<Complex<f64>>> {
+        for (i, &q) in keep.iter().enumerate() {
+            assert!(q < self.num_qubits, "qubit {} out of range", q);
+            assert!(!keep[..i].contains(&q), "qubit {} listed twice", q);
+        }
+        let env: Vec<usize> = (0..self.num_qubits).filter(|q| !keep.contains(q)).collect();
+        let gather = |index: usize, qubits: &[usize]| {
+            qubits
+                .iter()
+                .enumerate()
+                .fold(0, |acc, (bit, &q)| acc | (((index >> q) & 1) << bit))
+        };
+
+        // Regroup the amplitudes as psi[env][kept] so each environment state is a row.
+        let dim = 1 << keep.len();
+        let mut psi = vec![vec![Complex::new(0.0, 0.0); dim]; 1 << env.len()];
+        for (i, amp) in self.amplitudes.iter().enumerate() {
+            psi[gather(i, &env)][gather(i, keep)] = *amp;
+        }
+
+        let mut rho = vec![vec![Complex::new(0.0, 0.0); dim]; dim];
+        for row in &psi {
+            for a in 0..dim {
+                for b in 0..dim {
+                    rho[a][b] += row[a] * row[b].conj();
+                }
+            }
+        }
+        rho
+    }
+
+    /// Von Neumann entropy -Σ λ log2 λ of the reduced state on `partition`, in bits.
+    /// 0 for a product state across the cut, 1 per maximally entangled qubit pair.
+    pub fn entanglement_entropy(&self, partition: &[usize]) -> f64 {
+        let rho = self.reduced_density_matrix(partition);
+        crate::linalg::hermitian_eigenvalues(&rho)
+            .into_iter()
+            .filter(|&lambda| lambda > 1e-12)
+            .map(|lambda| -lambda * lambda.log2())
+            .sum()
+    }
+
+    /// The Bloch vector (⟨X⟩, ⟨Y⟩, ⟨Z⟩) of `qubit`, read off its reduced density matrix
+    /// ρ = (I + xX + yY + zZ) / 2. Its length is 1 for a pure qubit and 0 when the qubit
+    /// is maximally entangled with the rest of the register.
+    pub fn bloch_vector(&self, qubit: usize) -> (f64, f64, f64) {
+        let rho = self.reduced_density_matrix(&[qubit]);
+        (
+            2.0 * rho[0][1].re,
+            -2.0 * rho[0][1].im,
+            rho[0][0].re - rho[1][1].re,
+        )
+    }
+
+    /// Compares two states while ignoring any overall phase difference between them.
+    /// Both vectors are rotated so their amplitude at the largest-magnitude component of
+    /// `self` is real and positive, then compared elementwise within `tol`.
+    pub fn approx_eq_up_to_global_phase(&self, other: &StateVector, tol: f64) -> bool {
+        if self.amplitudes.len() != other.amplitudes.len() {
+            return false;
+        }
+        let Some((k, reference)) = self
+            .amplitudes
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.norm_sqr().total_cmp(&b.norm_sqr()))
+        else {
+            return true;
+        };
+        if reference.norm() <= tol {
+            return other.amplitudes.iter().all(|a| a.norm() <= tol);
+        }
+        if other.amplitudes[k].norm() <= tol {
+            return false;
+        }
+
+        let phase_a = reference.conj() / reference.norm();
+        let phase_b = other.amplitudes[k].conj() / other.amplitudes[k].norm();
+        self.amplitudes
+            .iter()
+            .zip(&other.amplitudes)
+            .all(|(a, b)| (a * phase_a - b * phase_b).norm() <= tol)
+    }
+
+    /// Finds where two states diverge most: the basis index whose amplitudes differ by the
+    /// largest magnitude, and that magnitude. Global phases are included, so compare with
+    /// `approx_eq_up_to_global_phase` first if they should not count. Ties go to the lowest
+    /// index, so identical or empty states give `(0, 0.0)`.
+    pub fn max_amplitude_diff(&self, other: &StateVector) -> (usize, f64) {
+        assert_eq!(
+            self.amplitudes.len(),
+            other.amplitudes.len(),
+            "StateVectors must have the same dimension"
+        );
+        self.amplitudes
+            .iter()
+            .zip(&other.amplitudes)
+            .map(|(a, b)| (self.global_phase * a - other.global_phase * b).norm())
+            .enumerate()
+            .fold(
+                (0, 0.0),
+                |worst, (i, d)| if d > worst.1 { (i, d) } else { worst },
+            )
+    }
+
+    // ⟨ψ|φ⟩, including both global phases
+    pub fn inner_product(&self, other: &StateVector) -> Complex<f64> {
         assert_eq!(
             self.amplitudes.len(),
             other.amplitudes.len(),
             "StateVectors must have the same dimension"
         );
-        let inner_product: Complex<f64> = self
+        let overlap: Complex<f64> = self
             .amplitudes
             .iter()
             .zip(&other.amplitudes)
             .map(|(a, b)| a.conj() * b)
             .sum();
-        inner_product.norm_sqr()
+        self.global_phase.conj() * other.global_phase * overlap
+    }
+
+    // fidelity = |⟨ψ|φ⟩|²
+    pub fn fidelity(&self, other: &StateVector) -> f64 {
+        self.inner_product(other).norm_sqr()
     }
 }
 
+/// Unchecked conversion; prefer [`StateVector::from_amplitudes`] for external input.
+/// `num_qubits` is only meaningful when the length is a power of two.
 impl From<Vec<Complex<f64>>> for StateVector {
     fn from(vec: Vec<Complex<f64>>) -> Self {
         StateVector {
-            num_qubits: 0,
+            num_qubits: vec.len().trailing_zeros() as usize,
             amplitudes: vec,
+            global_phase: Complex::new(1.0, 0.0),
         }
     }
 }
@@ -274,6 +563,21 @@ mod tests {
         (a.re - b.re).abs() < EPSILON && (a.im - b.im).abs() < EPSILON
     }
 
+    #[test]
+    fn test_max_amplitude_diff_reports_offending_index() {
+        let mut a = StateVector::new(2);
+        a.apply_single_qubit_gate(&crate::simulator::HADAMARD, 0);
+        a.apply_single_qubit_gate(&crate::simulator::HADAMARD, 1);
+        let mut b = a.clone();
+        b.amplitudes[2] += Complex::new(0.0, 0.3);
+        b.amplitudes[1] += Complex::new(0.05, 0.0);
+
+        let (index, diff) = a.max_amplitude_diff(&b);
+        assert_eq!(index, 2);
+        assert!((diff - 0.3).abs() < EPSILON);
+        assert_eq!(a.max_amplitude_diff(&a), (0, 0.0));
+    }
+
     #[test]
     fn test_state_vector_initialization() {
         let num_qubits = 3;
@@ -286,6 +590,67 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_arbitrary_gate_cnot_matches_apply_cx() {
+        let zero = Complex::new(0.0, 0.0);
+        let one = Complex::new(1.0, 0.0);
+        // CNOT with the control as the most significant bit of the matrix index.
+        let cnot = vec![
+            vec![one, zero, zero, zero],
+            vec![zero, one, zero, zero],
+            vec![zero, zero, zero, one],
+            vec![zero, zero, one, zero],
+        ];
+
+        let mut expected = StateVector::new(3);
+        for (i, amp) in expected.amplitudes.iter_mut().enumerate() {
+            *amp = Complex::new(0.1 * (i + 1) as f64, -0.05 * i as f64);
+        }
+        let mut actual = expected.clone();
+
+        expected.apply_cx(2, 0);
+        actual.apply_arbitrary_gate(&cnot, &[0, 2]).unwrap();
+
+        for (a, b) in actual.amplitudes.iter().zip(expected.amplitudes.iter()) {
+            assert!(approx_eq(*a, *b));
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_gate_rejects_wrong_dimension() {
+        let mut state = StateVector::new(2);
+        let identity_2x2 = vec![
+            vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+            vec![Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+        ];
+        assert!(state.apply_arbitrary_gate(&identity_2x2, &[0, 1]).is_err());
+    }
+
+    #[test]
+    fn test_global_phase_becomes_relative_when_controlled() {
+        let phi = std::f64::consts::FRAC_PI_3;
+
+        // Sub-circuit on its own: only a global phase, so it's indistinguishable from |0>.
+        let mut block = StateVector::new(1);
+        block.apply_global_phase(phi);
+        let reference = StateVector::new(1);
+        assert!((block.fidelity(&reference) - 1.0).abs() < EPSILON);
+        assert!(approx_eq(
+            block.inner_product(&reference),
+            Complex::from_polar(1.0, -phi)
+        ));
+
+        // Controlled on qubit 1 prepared in |+>, the phase shows up between the branches.
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let mut state = StateVector::new(2);
+        state.amplitudes[0] = Complex::new(h, 0.0);
+        state.amplitudes[2] = Complex::new(h, 0.0);
+        state.apply_controlled_global_phase(1, block.global_phase);
+
+        let relative = state.amplitudes[2] / state.amplitudes[0];
+        assert!(approx_eq(relative, Complex::from_polar(1.0, phi)));
+    }
+
     #[test]
     fn test_measurement() {
         let pauli_x = [
@@ -302,4 +667,313 @@ mod tests {
         assert_eq!(result, 2);
         assert!(approx_eq(state.amplitudes[2], Complex::new(1.0, 0.0)));
     }
+
+    /// The original out-of-place single-qubit update, kept as a reference.
+    fn cloning_single_qubit_gate(
+        amps: &[Complex<f64>],
+        m: &[[Complex<f64>; 2]; 2],
+        target: usize,
+    ) -> Vec<Complex<f64>> {
+        let mut out = amps.to_vec();
+        let k = 1 << target;
+        for i in 0..amps.len() {
+            if (i & k) == 0 {
+                let j = i | k;
+                out[i] = m[0][0] * amps[i] + m[0][1] * amps[j];
+                out[j] = m[1][0] * amps[i] + m[1][1] * amps[j];
+            }
+        }
+        out
+    }
+
+    fn cloning_cx(amps: &[Complex<f64>], control: usize, target: usize) -> Vec<Complex<f64>> {
+        let mut out = amps.to_vec();
+        for i in 0..amps.len() {
+            if (i & (1 << control)) != 0 && (i & (1 << target)) == 0 {
+                out.swap(i, i | (1 << target));
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_in_place_gates_match_cloning_reference_on_10_qubits() {
+        let n = 10;
+        let mut state = StateVector::new(n);
+        let mut reference = state.amplitudes.clone();
+
+        for layer in 0..3 {
+            for q in 0..n {
+                let theta = 0.37 * (q + 1) as f64 + layer as f64;
+                let (c, s) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+                // RY followed by a phase so both real and imaginary parts are exercised.
+                let m = [
+                    [Complex::new(c, 0.0), Complex::new(-s, 0.0)],
+                    [Complex::new(0.0, s), Complex::new(0.0, c)],
+                ];
+                state.apply_single_qubit_gate(&m, q);
+                reference = cloning_single_qubit_gate(&reference, &m, q);
+            }
+            for q in 0..n - 1 {
+                state.apply_cx(q, q + 1);
+                reference = cloning_cx(&reference, q, q + 1);
+            }
+            state.apply_cx(n - 1, 0);
+            reference = cloning_cx(&reference, n - 1, 0);
+        }
+
+        assert_eq!(state.amplitudes, reference);
+    }
+
+    #[test]
+    fn test_cp_pi_matches_cz_on_basis_states() {
+        for basis in 0..4 {
+            let mut state = StateVector::new(2);
+            state.amplitudes[0] = Complex::new(0.0, 0.0);
+            state.amplitudes[basis] = Complex::new(1.0, 0.0);
+
+            state.apply_cp(0, 1, std::f64::consts::PI);
+
+            // CZ only flips the sign of |11>.
+            let expected = if basis == 3 { -1.0 } else { 1.0 };
+            for (i, amp) in state.amplitudes.iter().enumerate() {
+                let want = if i == basis { expected } else { 0.0 };
+                assert!(
+                    approx_eq(*amp, Complex::new(want, 0.0)),
+                    "basis {} amplitude {} was {}",
+                    basis,
+                    i,
+                    amp
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_tensor_one_with_zero_is_basis_state_two() {
+        let mut one = StateVector::new(1);
+        one.amplitudes.swap(0, 1);
+        let zero = StateVector::new(1);
+
+        let product = one.tensor(&zero);
+        assert_eq!(product.num_qubits, 2);
+        for (i, amp) in product.amplitudes.iter().enumerate() {
+            let expected = if i == 2 { 1.0 } else { 0.0 };
+            assert!(approx_eq(*amp, Complex::new(expected, 0.0)));
+        }
+    }
+
+    #[test]
+    fn test_too_many_qubits_is_an_error() {
+        assert!(matches!(
+            StateVector::try_new(40),
+            Err(SimError::TooManyQubits {
+                requested: 40,
+                max: DEFAULT_MAX_QUBITS
+            })
+        ));
+        assert!(StateVector::try_new_with_limit(3, 2).is_err());
+        assert_eq!(StateVector::try_new_with_limit(2, 2).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_reduced_density_matrix_of_bell_state_is_maximally_mixed() {
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let mut state = StateVector::new(2);
+        state.amplitudes[0] = Complex::new(h, 0.0);
+        state.amplitudes[3] = Complex::new(h, 0.0);
+
+        for keep in [0, 1] {
+            let rho = state.reduced_density_matrix(&[keep]);
+            assert!(approx_eq(rho[0][0], Complex::new(0.5, 0.0)));
+            assert!(approx_eq(rho[1][1], Complex::new(0.5, 0.0)));
+            assert!(approx_eq(rho[0][1], Complex::new(0.0, 0.0)));
+            assert!(approx_eq(rho[1][0], Complex::new(0.0, 0.0)));
+        }
+    }
+
+    #[test]
+    fn test_reduced_density_matrix_of_product_state_is_pure() {
+        // |+> on qubit 0, |1> on qubit 1
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let mut state = StateVector::new(2);
+        state.amplitudes[0] = Complex::new(0.0, 0.0);
+        state.amplitudes[2] = Complex::new(h, 0.0);
+        state.amplitudes[3] = Complex::new(h, 0.0);
+
+        let rho = state.reduced_density_matrix(&[0]);
+        for row in &rho {
+            for entry in row {
+                assert!(approx_eq(*entry, Complex::new(0.5, 0.0)));
+            }
+        }
+        // A rank-1 2x2 matrix has zero determinant (and, being a state, unit trace).
+        let det = rho[0][0] * rho[1][1] - rho[0][1] * rho[1][0];
+        assert!(approx_eq(det, Complex::new(0.0, 0.0)));
+
+        let rho1 = state.reduced_density_matrix(&[1]);
+        assert!(approx_eq(rho1[1][1], Complex::new(1.0, 0.0)));
+        assert!(approx_eq(rho1[0][0], Complex::new(0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_bloch_vector() {
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let assert_bloch = |state: &StateVector, qubit: usize, expected: (f64, f64, f64)| {
+            let (x, y, z) = state.bloch_vector(qubit);
+            assert!(
+                (x - expected.0).abs() < 1e-12
+                    && (y - expected.1).abs() < 1e-12
+                    && (z - expected.2).abs() < 1e-12,
+                "q{}: ({}, {}, {}) != {:?}",
+                qubit,
+                x,
+                y,
+                z,
+                expected
+            );
+        };
+
+        let zero = StateVector::new(1);
+        assert_bloch(&zero, 0, (0.0, 0.0, 1.0));
+
+        let mut plus = StateVector::new(1);
+        plus.amplitudes = vec![Complex::new(h, 0.0), Complex::new(h, 0.0)];
+        assert_bloch(&plus, 0, (1.0, 0.0, 0.0));
+
+        // |+i> on qubit 1, |1> on qubit 0.
+        let mut product = StateVector::new(2);
+        product.amplitudes = vec![
+            Complex::new(0.0, 0.0),
+            Complex::new(h, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, h),
+        ];
+        assert_bloch(&product, 0, (0.0, 0.0, -1.0));
+        assert_bloch(&product, 1, (0.0, 1.0, 0.0));
+
+        let mut bell = StateVector::new(2);
+        bell.amplitudes[0] = Complex::new(h, 0.0);
+        bell.amplitudes[3] = Complex::new(h, 0.0);
+        assert_bloch(&bell, 0, (0.0, 0.0, 0.0));
+        assert_bloch(&bell, 1, (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_entanglement_entropy_bell_and_product_states() {
+        let mut bell = StateVector::new(2);
+        let h = [
+            [
+                Complex::new(std::f64::consts::FRAC_1_SQRT_2, 0.0),
+                Complex::new(std::f64::consts::FRAC_1_SQRT_2, 0.0),
+            ],
+            [
+                Complex::new(std::f64::consts::FRAC_1_SQRT_2, 0.0),
+                Complex::new(-std::f64::consts::FRAC_1_SQRT_2, 0.0),
+            ],
+        ];
+        bell.apply_single_qubit_gate(&h, 0);
+        bell.apply_cx(0, 1);
+        assert!((bell.entanglement_entropy(&[0]) - 1.0).abs() < 1e-9);
+        assert!((bell.entanglement_entropy(&[1]) - 1.0).abs() < 1e-9);
+
+        let mut product = StateVector::new(3);
+        product.apply_single_qubit_gate(&h, 0);
+        product.apply_single_qubit_gate(&h, 2);
+        assert!(product.entanglement_entropy(&[0]).abs() < 1e-9);
+        assert!(product.entanglement_entropy(&[0, 1]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_approx_eq_up_to_global_phase() {
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let plus = StateVector::from(vec![Complex::new(h, 0.0), Complex::new(h, 0.0)]);
+        let rotated = StateVector::from(vec![Complex::new(0.0, h), Complex::new(0.0, h)]);
+        let minus = StateVector::from(vec![Complex::new(h, 0.0), Complex::new(-h, 0.0)]);
+
+        assert!(plus.approx_eq_up_to_global_phase(&rotated, 1e-9));
+        assert!(!plus.approx_eq_up_to_global_phase(&minus, 1e-9));
+
+        // The first amplitude is ~0 here, so the reference must come from the largest one.
+        let one = StateVector::from(vec![Complex::new(1e-15, 0.0), Complex::new(1.0, 0.0)]);
+        let phased_one = StateVector::from(vec![Complex::new(0.0, 0.0), Complex::new(0.0, -1.0)]);
+        assert!(one.approx_eq_up_to_global_phase(&phased_one, 1e-9));
+        assert!(!one.approx_eq_up_to_global_phase(&plus, 1e-9));
+    }
+
+    #[test]
+    fn test_from_amplitudes_validates_length_and_norm() {
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let three = vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ];
+        assert!(StateVector::from_amplitudes(three).is_err());
+
+        let bell = vec![
+            Complex::new(h, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, h),
+        ];
+        let state = StateVector::from_amplitudes(bell).unwrap();
+        assert_eq!(state.num_qubits, 2);
+        assert_eq!(state.len(), 4);
+
+        let unnormalized = vec![Complex::new(1.0, 0.0), Complex::new(1.0, 0.0)];
+        assert!(StateVector::from_amplitudes(unnormalized).is_err());
+    }
+
+    #[test]
+    fn test_deep_circuit_stays_normalized() {
+        let mut state = StateVector::new(5);
+        for step in 0..200 {
+            let theta = 0.1 * step as f64;
+            let (c, s) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+            let ry = [
+                [Complex::new(c, 0.0), Complex::new(-s, 0.0)],
+                [Complex::new(s, 0.0), Complex::new(c, 0.0)],
+            ];
+            state.apply_single_qubit_gate(&ry, step % 5);
+            state.apply_cx(step % 5, (step + 1) % 5);
+            state.apply_cp(step % 5, (step + 2) % 5, theta);
+        }
+        assert!(
+            state.is_normalized(1e-9),
+            "norm drifted to {}",
+            state.norm()
+        );
+    }
+
+    #[test]
+    fn test_normalize_restores_unit_norm() {
+        let mut state = StateVector::new(2);
+        state.amplitudes[0] = Complex::new(3.0, 0.0);
+        state.amplitudes[3] = Complex::new(0.0, 4.0);
+        assert!(!state.is_normalized(1e-9));
+        assert!((state.norm() - 5.0).abs() < EPSILON);
+
+        state.normalize();
+        assert!(state.is_normalized(1e-12));
+        assert!(approx_eq(state.amplitudes[0], Complex::new(0.6, 0.0)));
+        assert!(approx_eq(state.amplitudes[3], Complex::new(0.0, 0.8)));
+    }
+
+    #[test]
+    fn test_expectation_from_counts() {
+        let mut bell = StateVector::new(2);
+        bell.apply_single_qubit_gate(&crate::simulator::HADAMARD, 0);
+        bell.apply_cx(0, 1);
+        let counts = bell.sample_counts(1000);
+        assert!((expectation_from_counts(&counts, &[0, 1]) - 1.0).abs() < 1e-9);
+
+        // Only "01" (qubit 0 set) and "00": <Z0> = (1 - 3) / 4, <Z1> = 1.
+        let counts: HashMap<String, u32> = [("01".to_string(), 3), ("00".to_string(), 1)]
+            .into_iter()
+            .collect();
+        assert!((expectation_from_counts(&counts, &[0]) + 0.5).abs() < 1e-9);
+        assert!((expectation_from_counts(&counts, &[1]) - 1.0).abs() < 1e-9);
+        assert_eq!(expectation_from_counts(&HashMap::new(), &[0]), 0.0);
+    }
 }
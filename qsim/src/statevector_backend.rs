@@ -3,25 +3,74 @@ use crate::StateVector;
 use crate::api::{Pauli, SimError, SimulatorApi};
 use crate::circuit::Circuit;
 use crate::parser::Gate;
+use crate::state::DEFAULT_MAX_QUBITS;
 use num_complex::Complex;
-use rand::thread_rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::f64::consts::FRAC_1_SQRT_2;
 
 pub struct StatevectorSimulator {
     num_qubits: usize,
+    max_qubits: usize,
     state: StateVector,
+    /// Amplitudes smaller than this are zeroed after every gate; `None` disables pruning.
+    prune_threshold: Option<f64>,
+    /// Drives `measure`, `sample` and measurement gates; `sample` only has `&self`.
+    rng: RefCell<StdRng>,
 }
 
 impl StatevectorSimulator {
+    /// Panics if `num_qubits` exceeds [`DEFAULT_MAX_QUBITS`]; see [`StatevectorSimulator::try_new`].
     pub fn new(num_qubits: usize) -> Self {
-        Self {
+        Self::try_new(num_qubits).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(num_qubits: usize) -> Result<Self, SimError> {
+        Self::with_max_qubits(num_qubits, DEFAULT_MAX_QUBITS)
+    }
+
+    /// Creates a simulator that refuses registers wider than `max_qubits`, both here
+    /// and when `run` is given a wider circuit.
+    pub fn with_max_qubits(num_qubits: usize, max_qubits: usize) -> Result<Self, SimError> {
+        Ok(Self {
             num_qubits,
-            state: StateVector::new(num_qubits),
+            max_qubits,
+            state: StateVector::try_new_with_limit(num_qubits, max_qubits)?,
+            prune_threshold: None,
+            rng: RefCell::new(StdRng::from_entropy()),
+        })
+    }
+
+    /// After each gate, zeroes amplitudes whose magnitude is below `threshold` and
+    /// renormalizes. This keeps rounding residue such as cos(π/2) ≈ 6e-17 out of sparse
+    /// states at the cost of a tiny accuracy loss. Off by default.
+    pub fn with_prune_threshold(mut self, threshold: f64) -> Self {
+        self.prune_threshold = Some(threshold);
+        self
+    }
+
+    /// Seeds the RNG behind measurements and sampling so that results are reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = RefCell::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    fn prune(&mut self, threshold: f64) {
+        let mut pruned = false;
+        for amp in &mut self.state.amplitudes {
+            if *amp != Complex::new(0.0, 0.0) && amp.norm() < threshold {
+                *amp = Complex::new(0.0, 0.0);
+                pruned = true;
+            }
+        }
+        if pruned {
+            self.state.normalize();
         }
     }
 
-    fn apply_gate(&mut self, g: &Gate) {
+    fn apply_gate(&mut self, g: &Gate) -> Result<(), SimError> {
         // Constants
         let h = [
             [
@@ -91,37 +140,83 @@ impl StatevectorSimulator {
                 self.state.apply_cx(control, target)
             }
 
+            Gate::CP {
+                control,
+                target,
+                theta,
+            } => self.state.apply_cp(control, target, theta),
+
+            Gate::Unitary {
+                ref qubits,
+                ref matrix,
+            } => self.state.apply_arbitrary_gate(matrix, qubits)?,
+
+            Gate::GPhase { theta } => self.state.apply_global_phase(theta),
+
             // If you have a `Measure` gate in parsed circuits, you can ignore it here
             // (tests call measure() explicitly), or do a full-measure collapse:
             Gate::Measure => {
-                let _ = self.state.measure_all(&mut thread_rng());
+                let _ = self.state.measure_all(self.rng.get_mut());
+            }
+            Gate::MeasureQubit { qubit, .. } => {
+                if qubit >= self.num_qubits {
+                    return Err(SimError::Qubit(qubit));
+                }
+                let _ = self.state.measure_qubit_in_z(qubit, self.rng.get_mut());
             }
+            Gate::Reset { qubit } => {
+                if qubit >= self.num_qubits {
+                    return Err(SimError::Qubit(qubit));
+                }
+                self.state.reset_qubit(qubit, self.rng.get_mut());
+            }
+            Gate::Barrier { .. } => {}
+        }
+        if let Some(threshold) = self.prune_threshold {
+            self.prune(threshold);
         }
+        Ok(())
     }
 
-    fn apply_circuit(&mut self, c: &Circuit) {
+    fn apply_circuit(&mut self, c: &Circuit) -> Result<(), SimError> {
         for moment in &c.moments {
             for g in moment {
-                self.apply_gate(g);
+                self.apply_gate(g)?;
             }
         }
+        Ok(())
     }
 }
 
 impl SimulatorApi for StatevectorSimulator {
     fn reset(&mut self, n: usize) {
         self.num_qubits = n;
-        self.state = StateVector::new(n);
+        self.state =
+            StateVector::try_new_with_limit(n, self.max_qubits).unwrap_or_else(|e| panic!("{}", e));
     }
 
     fn run(&mut self, circuit: &Circuit) -> Result<(), SimError> {
+        if circuit.num_qubits > self.max_qubits {
+            return Err(SimError::TooManyQubits {
+                requested: circuit.num_qubits,
+                max: self.max_qubits,
+            });
+        }
         if self.num_qubits != circuit.num_qubits {
             self.reset(circuit.num_qubits);
         } else {
             self.state.reset();
         }
-        self.apply_circuit(circuit);
-        Ok(())
+        circuit.validate()?;
+        self.apply_circuit(circuit)
+    }
+
+    fn apply_circuit_no_reset(&mut self, circuit: &Circuit) -> Result<(), SimError> {
+        if circuit.num_qubits > self.num_qubits {
+            return Err(SimError::Qubit(circuit.num_qubits - 1));
+        }
+        circuit.validate()?;
+        self.apply_circuit(circuit)
     }
 
     fn statevector(&self) -> &StateVector {
@@ -144,7 +239,7 @@ impl SimulatorApi for StatevectorSimulator {
         }
 
         let m = outcome.unwrap_or_else(|| {
-            let idx = self.state.measure_all(&mut thread_rng());
+            let idx = self.state.measure_all(self.rng.get_mut());
             ((idx >> qubit) & 1) as u8
         });
         Ok(m)
@@ -206,11 +301,11 @@ impl SimulatorApi for StatevectorSimulator {
         let probs: Vec<f64> = self.state.amplitudes.iter().map(|a| a.norm_sqr()).collect();
         let dist = WeightedIndex::new(&probs).map_err(|e| SimError::Internal(e.to_string()))?;
 
-        let mut rng = thread_rng();
+        let mut rng = self.rng.borrow_mut();
         let mut counts = HashMap::new();
         let width = self.num_qubits;
         for _ in 0..shots {
-            let idx = dist.sample(&mut rng);
+            let idx = dist.sample(&mut *rng);
             let bitstr = format!("{:0width$b}", idx, width = width);
             *counts.entry(bitstr).or_insert(0) += 1;
         }
@@ -1,37 +1,116 @@
-use qsim::{QuantumSimulator, Gate};
+use qsim::simulator::Simulator;
+use qsim::{Gate, QuantumSimulator};
+
+/// The name under which the QCBM binary accepts [`hardware_efficient`]. A `:<layers>`
+/// suffix selects the depth, e.g. `hardware_efficient:3`; without one a single layer is used.
+pub const HARDWARE_EFFICIENT: &str = "hardware_efficient";
+
+/// The number of parameters taken by [`hardware_efficient`]: one RY angle per qubit per layer.
+pub fn hardware_efficient_num_params(num_qubits: usize, num_layers: usize) -> usize {
+    num_qubits * num_layers
+}
+
+/// Builds a `num_layers`-deep hardware-efficient ansatz. Each layer is an RY rotation on
+/// every qubit followed by a CX ladder between neighbours, and the parameters are consumed
+/// layer by layer. The returned closure is `Copy`, so it can be handed to `QcbmRunner::new`.
+pub fn hardware_efficient<S: Simulator>(num_layers: usize) -> impl Fn(&mut S, &[f64]) + Copy {
+    move |simulator: &mut S, params: &[f64]| {
+        apply_hardware_efficient_layers(simulator, params, num_layers)
+    }
+}
+
+/// Resolves an ansatz name to the number of hardware-efficient layers it asks for.
+/// Returns `None` for names that are not [`HARDWARE_EFFICIENT`] or have an invalid depth.
+pub fn parse_hardware_efficient(name: &str) -> Option<usize> {
+    match name.split_once(':') {
+        None if name == HARDWARE_EFFICIENT => Some(1),
+        Some((base, layers)) if base == HARDWARE_EFFICIENT => {
+            layers.parse().ok().filter(|&n| n > 0)
+        }
+        _ => None,
+    }
+}
 
 /// Applies a hardware-efficient ansatz to the simulator.
 ///
 /// This specific ansatz uses layers of Y-rotations and CNOTs.
 /// The number of parameters must match the requirements of the circuit.
-/// For this example, it needs 2 * num_layers parameters.
+/// For this example, it needs num_qubits * num_layers parameters with two layers.
 pub fn apply_ansatz(simulator: &mut QuantumSimulator, params: &[f64]) {
-    let num_qubits = simulator.num_qubits();
     let num_layers = 2; // A hyperparameter you can tune
+    apply_hardware_efficient_layers(simulator, params, num_layers);
+}
+
+fn apply_hardware_efficient_layers<S: Simulator>(
+    simulator: &mut S,
+    params: &[f64],
+    num_layers: usize,
+) {
+    let num_qubits = simulator.get_num_qubits();
 
     // Ensure we have the correct number of parameters.
     // Each layer has a rotation on each qubit.
     assert_eq!(
         params.len(),
-        num_qubits * num_layers,
+        hardware_efficient_num_params(num_qubits, num_layers),
         "Incorrect number of parameters for the ansatz"
     );
 
-    let mut params_iter = params.iter();
-
-    for layer in 0..num_layers {
+    for layer in params.chunks(num_qubits.max(1)) {
         // 1. Layer of single-qubit rotation gates
-        for i in 0..num_qubits {
-            let theta = params_iter.next().unwrap();
-            // You'll need to have implemented a parameterized Ry gate
-            // in your simulator.
-            simulator.apply_gate(&Gate::RY(i, *theta));
+        for (i, &theta) in layer.iter().enumerate() {
+            simulator.apply_gate(&Gate::ry(i, theta));
         }
 
         // 2. Layer of entangling gates
         // Here, we entangle each qubit with its neighbor.
-        for i in 0..(num_qubits - 1) {
-            simulator.apply_gate(&Gate::CX(i, i + 1));
+        for i in 0..num_qubits.saturating_sub(1) {
+            simulator.apply_gate(&Gate::cx(i, i + 1));
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qcbm::{AdamOptimizer, QcbmRunner};
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2};
+
+    #[test]
+    fn test_second_layer_prepares_bell_state() {
+        // First layer is the identity; the second rotates q0 to |+> and entangles.
+        let mut sim = QuantumSimulator::new(2);
+        apply_ansatz(&mut sim, &[0.0, 0.0, FRAC_PI_2, 0.0]);
+
+        let amps = &sim.state.amplitudes;
+        assert!((amps[0].re - FRAC_1_SQRT_2).abs() < 1e-9);
+        assert!((amps[3].re - FRAC_1_SQRT_2).abs() < 1e-9);
+        assert!(amps[1].norm() < 1e-9 && amps[2].norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_hardware_efficient() {
+        assert_eq!(parse_hardware_efficient("hardware_efficient"), Some(1));
+        assert_eq!(parse_hardware_efficient("hardware_efficient:3"), Some(3));
+        assert_eq!(parse_hardware_efficient("hardware_efficient:0"), None);
+        assert_eq!(parse_hardware_efficient("OPENQASM 2.0;"), None);
+    }
+
+    #[test]
+    fn test_two_layer_qcbm_learns_bell_distribution() {
+        let training_data: Vec<String> = ["00", "11"].iter().map(|s| s.to_string()).collect();
+        let num_layers = 2;
+
+        let sim = QuantumSimulator::new(2);
+        let runner = QcbmRunner::new(sim, hardware_efficient(num_layers), &training_data);
+        let mut params = vec![0.1; hardware_efficient_num_params(2, num_layers)];
+        let mut optimizer = AdamOptimizer::new(params.len(), 0.05);
+        runner.train(&mut params, &mut optimizer, 150, None);
+
+        let dist = runner.get_model_distribution(&params);
+        let p00 = dist.get("00").unwrap_or(&0.0);
+        let p11 = dist.get("11").unwrap_or(&0.0);
+        assert!(p00 + p11 > 0.85, "P(00) + P(11) = {}", p00 + p11);
+        assert!((p00 - p11).abs() < 0.3, "P(00) = {}, P(11) = {}", p00, p11);
+    }
+}
@@ -0,0 +1,95 @@
+use clap::Parser;
+use qflow_types::QcbmResult;
+use qsim::QuantumSimulator;
+use vqa_runner::ansatz::{
+    HARDWARE_EFFICIENT, hardware_efficient, hardware_efficient_num_params, parse_hardware_efficient,
+};
+use vqa_runner::convergence::ConvergenceCriterion;
+use vqa_runner::qcbm::{AdamOptimizer, QcbmRunner};
+
+/// Trains a QCBM and prints a `QcbmResult` JSON document as the final line of output.
+/// The arguments mirror the ones the operator passes to QCBM task containers.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// The ansatz requested by the task: `hardware_efficient` or `hardware_efficient:<layers>`.
+    /// Other values fall back to a single hardware-efficient layer.
+    #[arg(long)]
+    ansatz: String,
+
+    /// The training bitstrings as a JSON array, e.g. `["00","11"]`.
+    #[arg(long)]
+    training_data: String,
+
+    #[arg(long, default_value_t = 100)]
+    epochs: usize,
+
+    #[arg(long, default_value_t = 0.01)]
+    learning_rate: f64,
+
+    /// Stop early once the loss has failed to improve by more than this for `--patience`
+    /// consecutive epochs.
+    #[arg(long)]
+    tolerance: Option<f64>,
+
+    #[arg(long, default_value_t = 1)]
+    patience: usize,
+
+    /// The initial ansatz parameters as a JSON array.
+    #[arg(long)]
+    initial_params: Option<String>,
+}
+
+fn main() -> Result<(), String> {
+    let cli = Cli::parse();
+    let num_layers = parse_hardware_efficient(&cli.ansatz).unwrap_or_else(|| {
+        println!(
+            "Unrecognised ansatz '{}', using {}",
+            cli.ansatz, HARDWARE_EFFICIENT
+        );
+        1
+    });
+    println!(
+        "Using ansatz: {} ({} layers)",
+        HARDWARE_EFFICIENT, num_layers
+    );
+
+    let training_data: Vec<String> = serde_json::from_str(&cli.training_data)
+        .map_err(|e| format!("Invalid --training-data: {}", e))?;
+    let num_qubits = training_data
+        .first()
+        .map(|s| s.len())
+        .ok_or_else(|| "Training data must not be empty".to_string())?;
+    let num_params = hardware_efficient_num_params(num_qubits, num_layers);
+
+    let mut params: Vec<f64> = match &cli.initial_params {
+        Some(raw) => {
+            serde_json::from_str(raw).map_err(|e| format!("Invalid --initial-params: {}", e))?
+        }
+        None => vec![0.1; num_params],
+    };
+    if params.len() != num_params {
+        return Err(format!(
+            "Expected {} initial parameters, got {}",
+            num_params,
+            params.len()
+        ));
+    }
+
+    let sim = QuantumSimulator::new(num_qubits);
+    let runner = QcbmRunner::new(sim, hardware_efficient(num_layers), &training_data);
+    let mut optimizer = AdamOptimizer::new(params.len(), cli.learning_rate);
+    let convergence = cli
+        .tolerance
+        .map(|tol| ConvergenceCriterion::new(tol, 0.0, cli.patience));
+    let loss_history = runner.train(&mut params, &mut optimizer, cli.epochs, convergence);
+
+    let result = QcbmResult {
+        distribution: runner.get_model_distribution(&params).into_iter().collect(),
+        final_params: params,
+        loss_history,
+    };
+    let json = serde_json::to_string(&result).map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(())
+}
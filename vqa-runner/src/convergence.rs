@@ -0,0 +1,88 @@
+/// Early-stopping rule shared by the VQE and QCBM training loops.
+///
+/// A loss counts as an improvement when it beats the best loss seen so far by more than
+/// `max(abs_tol, rel_tol * |best|)`. Training should stop once `patience` consecutive
+/// losses fail to improve.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvergenceCriterion {
+    abs_tol: f64,
+    rel_tol: f64,
+    patience: usize,
+    best: Option<f64>,
+    stale_steps: usize,
+}
+
+impl ConvergenceCriterion {
+    pub fn new(abs_tol: f64, rel_tol: f64, patience: usize) -> Self {
+        ConvergenceCriterion {
+            abs_tol,
+            rel_tol,
+            patience,
+            best: None,
+            stale_steps: 0,
+        }
+    }
+
+    /// Records the loss of one step and returns whether training should stop.
+    pub fn should_stop(&mut self, loss: f64) -> bool {
+        match self.best {
+            Some(best) if loss >= best - self.abs_tol.max(self.rel_tol * best.abs()) => {
+                self.stale_steps += 1;
+            }
+            _ => {
+                self.best = Some(loss);
+                self.stale_steps = 0;
+            }
+        }
+        self.stale_steps >= self.patience
+    }
+
+    /// The lowest loss recorded so far.
+    pub fn best(&self) -> Option<f64> {
+        self.best
+    }
+
+    /// Forgets every recorded loss, e.g. before starting another training run.
+    pub fn reset(&mut self) {
+        self.best = None;
+        self.stale_steps = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stops_after_patience_non_improving_steps() {
+        let mut criterion = ConvergenceCriterion::new(1e-3, 0.0, 3);
+
+        assert!(!criterion.should_stop(1.0));
+        assert!(!criterion.should_stop(0.5));
+        // Below the absolute tolerance, so not an improvement.
+        assert!(!criterion.should_stop(0.4995));
+        assert!(!criterion.should_stop(0.6));
+        // An improvement resets the count.
+        assert!(!criterion.should_stop(0.3));
+        assert!(!criterion.should_stop(0.3));
+        assert!(!criterion.should_stop(0.3));
+        assert!(criterion.should_stop(0.3));
+        assert_eq!(criterion.best(), Some(0.3));
+
+        criterion.reset();
+        assert_eq!(criterion.best(), None);
+        assert!(!criterion.should_stop(0.3));
+    }
+
+    #[test]
+    fn test_relative_tolerance_scales_with_best_loss() {
+        let mut criterion = ConvergenceCriterion::new(0.0, 0.1, 1);
+        assert!(!criterion.should_stop(10.0));
+        // 9.5 is within 10% of 10.0.
+        assert!(criterion.should_stop(9.5));
+
+        let mut criterion = ConvergenceCriterion::new(0.0, 0.1, 1);
+        assert!(!criterion.should_stop(10.0));
+        assert!(!criterion.should_stop(8.0));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod ansatz;
+pub mod convergence;
+pub mod qcbm;
@@ -1,68 +1,182 @@
-mod qcbm;
-
+use clap::Parser;
 use hamiltonian::{Hamiltonian, PauliTerm};
+use qsim::api::Pauli;
 use qsim::simulator::Simulator;
 use qsim::{Gate, QuantumSimulator as StatevectorSimulator};
+use rand::Rng;
+use serde::Serialize;
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use vqa_runner::convergence::ConvergenceCriterion;
+use vqa_runner::qcbm::Optimizer;
+
+/// A least-recently-used map from parameter vectors to energies. Parameters are keyed on
+/// their exact bit patterns, so only a repeat of the very same point is a hit.
+struct CostCache {
+    capacity: usize,
+    entries: HashMap<Vec<u64>, (f64, u64)>,
+    clock: u64,
+    hits: usize,
+    misses: usize,
+}
+
+impl CostCache {
+    fn new(capacity: usize) -> Self {
+        CostCache {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn key(params: &[f64]) -> Vec<u64> {
+        // Adding 0.0 turns -0.0 into 0.0 so the two zeros share an entry.
+        params.iter().map(|p| (p + 0.0).to_bits()).collect()
+    }
+
+    fn get(&mut self, key: &[u64]) -> Option<f64> {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some((energy, last_used)) => {
+                *last_used = self.clock;
+                self.hits += 1;
+                Some(*energy)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: Vec<u64>, energy: f64) {
+        if self.entries.len() >= self.capacity
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(k, _)| k.clone())
+        {
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert(key, (energy, self.clock));
+    }
+}
 
 /// A VQE problem runner that is configured with a specific Hamiltonian and ansatz circuit.
-/// It is generic over any type `S` that implements the `Simulator` trait.
+/// It is generic over any type `S` that implements the `Simulator` trait. The stored
+/// simulator is only a template: each cost evaluation prepares the ansatz on a clone.
 pub struct VqeRunner<S, F>
 where
-    S: Simulator,
+    S: Simulator + Clone,
     F: Fn(&mut S, &[f64]) + Copy,
 {
-    simulator: RefCell<S>,
+    simulator: S,
     hamiltonian: Hamiltonian,
     ansatz: F,
+    convergence: Option<ConvergenceCriterion>,
+    cache: Option<RefCell<CostCache>>,
 }
 
 impl<S, F> VqeRunner<S, F>
 where
-    S: Simulator,
+    S: Simulator + Clone,
     F: Fn(&mut S, &[f64]) + Copy,
 {
     /// Creates a new VQE runner, configured with a simulator, a Hamiltonian,
     /// and the ansatz circuit to use.
     pub fn new(simulator: S, hamiltonian: Hamiltonian, ansatz: F) -> Self {
         VqeRunner {
-            simulator: RefCell::new(simulator),
+            simulator,
             hamiltonian,
             ansatz,
+            convergence: None,
+            cache: None,
         }
     }
 
-    /// Calculates the expectation value of the Hamiltonian for a given
-    /// set of parameters. This is our cost function.
-    pub fn cost_function(&self, params: &[f64]) -> f64 {
-        let mut total_energy = 0.0;
+    /// Remembers the energies of the last `capacity` distinct parameter vectors, so
+    /// repeated evaluations (e.g. of shifted points when a parameter does not move between
+    /// steps) skip the simulation. The cache lives as long as the runner.
+    pub fn with_cost_cache(mut self, capacity: usize) -> Self {
+        self.cache = (capacity > 0).then(|| RefCell::new(CostCache::new(capacity)));
+        self
+    }
 
-        for pauli_term in &self.hamiltonian.terms {
-            let mut simulator = self.simulator.borrow_mut();
-            simulator.reset();
-            (self.ansatz)(&mut simulator, params);
+    /// Cache `(hits, misses)` so far, or `None` without a cache.
+    pub fn cache_stats(&self) -> Option<(usize, usize)> {
+        self.cache.as_ref().map(|cache| {
+            let cache = cache.borrow();
+            (cache.hits, cache.misses)
+        })
+    }
 
-            // Convert the pauli term to a vector of Gates
-            let gates: Vec<Gate> = pauli_term
-                .operators
-                .iter()
-                .map(|(pauli, qubit)| match pauli {
-                    hamiltonian::Pauli::I => Gate::I { qubit: *qubit },
-                    hamiltonian::Pauli::X => Gate::X { qubit: *qubit },
-                    hamiltonian::Pauli::Y => Gate::Y { qubit: *qubit },
-                    hamiltonian::Pauli::Z => Gate::Z { qubit: *qubit },
-                })
-                .collect();
+    /// Stops every optimization run early once `criterion` is met by the energy after a
+    /// step. Each run starts from a fresh copy of the criterion. Checking costs one extra
+    /// cost-function evaluation per step.
+    pub fn with_convergence(mut self, criterion: ConvergenceCriterion) -> Self {
+        self.convergence = Some(criterion);
+        self
+    }
 
-            // The expectation is calculated on the immutable state, as per the trait definition.
-            let expectation = simulator.measure_pauli_string_expectation(gates);
-            total_energy += pauli_term.coefficient * expectation;
+    /// Whether the energy at `params` meets the run's convergence criterion, if any.
+    fn converged(&self, convergence: &mut Option<ConvergenceCriterion>, params: &[f64]) -> bool {
+        convergence
+            .as_mut()
+            .is_some_and(|criterion| criterion.should_stop(self.cost_function(params)))
+    }
+
+    /// Calculates the expectation value of the Hamiltonian for a given
+    /// set of parameters. This is our cost function.
+    pub fn cost_function(&self, params: &[f64]) -> f64 {
+        let Some(cache) = &self.cache else {
+            return self.evaluate(params);
+        };
+        let key = CostCache::key(params);
+        if let Some(energy) = cache.borrow_mut().get(&key) {
+            return energy;
         }
-        total_energy
+        let energy = self.evaluate(params);
+        cache.borrow_mut().insert(key, energy);
+        energy
+    }
+
+    /// Prepares the ansatz and measures the Hamiltonian, bypassing the cache.
+    fn evaluate(&self, params: &[f64]) -> f64 {
+        let mut simulator = self.simulator.clone();
+        simulator.reset();
+        (self.ansatz)(&mut simulator, params);
+
+        // Every term is evaluated against the same prepared state, which is left untouched.
+        self.hamiltonian
+            .terms
+            .iter()
+            .map(|pauli_term| {
+                let ops: Vec<(Pauli, usize)> = pauli_term
+                    .operators
+                    .iter()
+                    .map(|(pauli, qubit)| {
+                        let pauli = match pauli {
+                            hamiltonian::Pauli::I => Pauli::I,
+                            hamiltonian::Pauli::X => Pauli::X,
+                            hamiltonian::Pauli::Y => Pauli::Y,
+                            hamiltonian::Pauli::Z => Pauli::Z,
+                        };
+                        (pauli, *qubit)
+                    })
+                    .collect();
+                pauli_term.coefficient * simulator.expectation_pauli_string(&ops)
+            })
+            .sum()
     }
 
     /// Calculates the gradient of the cost function with respect to all parameters
-    /// using the parameter-shift rule.
+    /// using the parameter-shift rule. Every shifted parameter set is prepared once and all
+    /// Hamiltonian terms are measured against that state, so a gradient costs 2N circuit
+    /// executions for N parameters, independent of the number of terms.
     pub fn gradient(&self, params: &[f64]) -> Vec<f64> {
         let mut gradient = vec![0.0; params.len()];
         let mut temp_params = params.to_vec();
@@ -89,16 +203,138 @@ where
         learning_rate: f64,
     ) -> (f64, Vec<f64>) {
         let mut params = initial_params;
+        let mut convergence = self.convergence.clone();
 
         for _ in 0..steps {
             let grad = self.gradient(&params);
             for j in 0..params.len() {
                 params[j] -= learning_rate * grad[j];
             }
+            if self.converged(&mut convergence, &params) {
+                break;
+            }
         }
         let final_energy = self.cost_function(&params);
         (final_energy, params)
     }
+
+    /// Runs gradient descent like [`VqeRunner::run`], but keeps each parameter inside its
+    /// `(lower, upper)` bound after every step. A bound spanning exactly one full turn (2π)
+    /// is treated as an angle and wrapped into `[lower, upper)`; any other bound is clamped.
+    ///
+    /// Panics if `bounds` does not have one entry per parameter.
+    pub fn run_bounded(
+        &self,
+        initial_params: Vec<f64>,
+        steps: usize,
+        learning_rate: f64,
+        bounds: &[(f64, f64)],
+    ) -> (f64, Vec<f64>) {
+        assert_eq!(
+            initial_params.len(),
+            bounds.len(),
+            "expected one bound per parameter"
+        );
+        let mut params: Vec<f64> = initial_params
+            .iter()
+            .zip(bounds)
+            .map(|(&p, &bound)| apply_bound(p, bound))
+            .collect();
+        let mut convergence = self.convergence.clone();
+
+        for _ in 0..steps {
+            let grad = self.gradient(&params);
+            for ((p, g), &bound) in params.iter_mut().zip(&grad).zip(bounds) {
+                *p = apply_bound(*p - learning_rate * g, bound);
+            }
+            if self.converged(&mut convergence, &params) {
+                break;
+            }
+        }
+        let final_energy = self.cost_function(&params);
+        (final_energy, params)
+    }
+
+    /// Runs the VQE optimization with parameter-shift gradients, letting `optimizer`
+    /// decide how each gradient is turned into a parameter update.
+    pub fn run_with_optimizer<O: Optimizer>(
+        &self,
+        initial_params: Vec<f64>,
+        steps: usize,
+        optimizer: &mut O,
+    ) -> (f64, Vec<f64>) {
+        let mut params = initial_params;
+        let mut convergence = self.convergence.clone();
+
+        for _ in 0..steps {
+            let grad = self.gradient(&params);
+            optimizer.update(&mut params, &grad);
+            if self.converged(&mut convergence, &params) {
+                break;
+            }
+        }
+        let final_energy = self.cost_function(&params);
+        (final_energy, params)
+    }
+
+    /// Runs the VQE optimization with Simultaneous Perturbation Stochastic Approximation.
+    ///
+    /// Each step perturbs every parameter at once by ±`c_k` with random signs and estimates
+    /// the whole gradient from the two resulting energies, so a step costs 2 cost-function
+    /// evaluations regardless of the number of parameters. The gains decay with the
+    /// standard schedule `a_k = a / (k + 1)^0.602` and `c_k = c / (k + 1)^0.101`.
+    pub fn run_spsa(
+        &self,
+        initial_params: Vec<f64>,
+        steps: usize,
+        a: f64,
+        c: f64,
+    ) -> (f64, Vec<f64>) {
+        let mut params = initial_params;
+        let mut convergence = self.convergence.clone();
+        let mut rng = rand::thread_rng();
+
+        for k in 0..steps {
+            let a_k = a / ((k + 1) as f64).powf(0.602);
+            let c_k = c / ((k + 1) as f64).powf(0.101);
+            let delta: Vec<f64> = (0..params.len())
+                .map(|_| if rng.gen_bool(0.5) { 1.0 } else { -1.0 })
+                .collect();
+
+            let params_plus: Vec<f64> = params
+                .iter()
+                .zip(&delta)
+                .map(|(p, d)| p + c_k * d)
+                .collect();
+            let params_minus: Vec<f64> = params
+                .iter()
+                .zip(&delta)
+                .map(|(p, d)| p - c_k * d)
+                .collect();
+            let diff = self.cost_function(&params_plus) - self.cost_function(&params_minus);
+
+            // The estimate for parameter j is diff / (2 c_k delta_j); delta_j is ±1, so
+            // dividing by it is the same as multiplying.
+            for (p, d) in params.iter_mut().zip(&delta) {
+                *p -= a_k * diff / (2.0 * c_k) * d;
+            }
+            if self.converged(&mut convergence, &params) {
+                break;
+            }
+        }
+        let final_energy = self.cost_function(&params);
+        (final_energy, params)
+    }
+}
+
+/// Wraps `value` into a full-turn bound, or clamps it into any other bound.
+fn apply_bound(value: f64, (lower, upper): (f64, f64)) -> f64 {
+    const FULL_TURN: f64 = 2.0 * std::f64::consts::PI;
+    if ((upper - lower) - FULL_TURN).abs() < 1e-12 {
+        lower + (value - lower).rem_euclid(FULL_TURN)
+    } else {
+        value.clamp(lower, upper)
+    }
 }
 
 /// Trait defining the VQE workflow interface.
@@ -110,7 +346,7 @@ pub trait Vqe {
 
 impl<S, F> Vqe for VqeRunner<S, F>
 where
-    S: Simulator,
+    S: Simulator + Clone,
     F: Fn(&mut S, &[f64]) + Copy,
 {
     fn cost_function(&self, params: &[f64]) -> f64 {
@@ -150,22 +386,47 @@ fn two_qubit_ansatz<S: Simulator>(simulator: &mut S, params: &[f64]) {
     });
 }
 
+/// Pre-computed H2 coefficients `(I, Z0, Z1, Z0Z1, X0X1)` by internuclear distance (Å),
+/// sorted by distance. Obtained from various quantum chemistry tutorials; a more robust
+/// implementation would calculate these from integrals.
+const H2_COEFFICIENTS: [(f64, [f64; 5]); 6] = [
+    (0.74, [-0.8126, 0.1712, -0.2228, 0.1686, 0.0453]), // Equilibrium
+    (0.9, [-0.7386, 0.1656, -0.2139, 0.1659, 0.0453]),
+    (1.2, [-0.6120, 0.1507, -0.1915, 0.1568, 0.0453]),
+    (1.5, [-0.5028, 0.1343, -0.1688, 0.1468, 0.0453]),
+    (1.8, [-0.4226, 0.1203, -0.1504, 0.1384, 0.0453]),
+    (2.1, [-0.3642, 0.1088, -0.1356, 0.1317, 0.0453]),
+];
+
+/// How far (Å) beyond the tabulated range the coefficients may be linearly extrapolated.
+const H2_MAX_EXTRAPOLATION: f64 = 0.1;
+
 /// Returns the H2 molecule Hamiltonian for a given internuclear distance (in Angstroms).
-/// Coefficients are pre-computed from quantum chemistry calculations.
-fn get_h2_hamiltonian_at_distance(distance: f64) -> Hamiltonian {
-    // Coefficients obtained from various quantum chemistry tutorials.
-    // A more robust implementation would calculate these from integrals.
-    let (c_i, c_z0, c_z1, c_z0z1, c_x0x1) = match (distance * 100.0) as u32 {
-        74 => (-0.8126, 0.1712, -0.2228, 0.1686, 0.0453), // Equilibrium
-        90 => (-0.7386, 0.1656, -0.2139, 0.1659, 0.0453),
-        120 => (-0.6120, 0.1507, -0.1915, 0.1568, 0.0453),
-        150 => (-0.5028, 0.1343, -0.1688, 0.1468, 0.0453),
-        180 => (-0.4226, 0.1203, -0.1504, 0.1384, 0.0453),
-        210 => (-0.3642, 0.1088, -0.1356, 0.1317, 0.0453),
-        _ => panic!("No pre-computed Hamiltonian for distance {}", distance),
-    };
-
-    Hamiltonian::new()
+/// Each coefficient is linearly interpolated between the nearest tabulated distances, and
+/// extrapolated from the outermost segment up to `H2_MAX_EXTRAPOLATION` past either end.
+fn get_h2_hamiltonian_at_distance(distance: f64) -> Result<Hamiltonian, String> {
+    let (first, last) = (
+        H2_COEFFICIENTS[0].0,
+        H2_COEFFICIENTS[H2_COEFFICIENTS.len() - 1].0,
+    );
+    if !(first - H2_MAX_EXTRAPOLATION..=last + H2_MAX_EXTRAPOLATION).contains(&distance) {
+        return Err(format!(
+            "No H2 Hamiltonian for distance {} Å: coefficients are only known between {} and {} Å",
+            distance, first, last
+        ));
+    }
+
+    // The segment containing the distance, or the outermost one when extrapolating.
+    let segment = H2_COEFFICIENTS
+        .windows(2)
+        .position(|w| distance <= w[1].0)
+        .unwrap_or(H2_COEFFICIENTS.len() - 2);
+    let (d0, c0) = H2_COEFFICIENTS[segment];
+    let (d1, c1) = H2_COEFFICIENTS[segment + 1];
+    let t = (distance - d0) / (d1 - d0);
+    let [c_i, c_z0, c_z1, c_z0z1, c_x0x1] = std::array::from_fn(|k| c0[k] + t * (c1[k] - c0[k]));
+
+    Ok(Hamiltonian::new()
         .with_term(PauliTerm::new().with_coefficient(c_i)) // Identity term
         .with_term(
             PauliTerm::new()
@@ -188,10 +449,38 @@ fn get_h2_hamiltonian_at_distance(distance: f64) -> Hamiltonian {
                 .with_coefficient(c_x0x1)
                 .with_pauli(0, hamiltonian::Pauli::X)
                 .with_pauli(1, hamiltonian::Pauli::X),
-        )
+        ))
+}
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Also write the curve to this file: CSV if it ends in `.csv`, JSON otherwise.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// One point of the dissociation curve.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+struct DissociationPoint {
+    distance: f64,
+    energy: f64,
 }
 
-fn main() {
+fn dissociation_curve_json(points: &[DissociationPoint]) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(points)
+}
+
+fn dissociation_curve_csv(points: &[DissociationPoint]) -> String {
+    let mut csv = String::from("distance,energy\n");
+    for p in points {
+        csv.push_str(&format!("{},{}\n", p.distance, p.energy));
+    }
+    csv
+}
+
+fn main() -> Result<(), String> {
+    let cli = Cli::parse();
     println!("--- Calculating H2 Molecule Dissociation Curve ---");
 
     let distances = vec![0.74, 0.9, 1.2, 1.5, 1.8, 2.1];
@@ -199,7 +488,7 @@ fn main() {
 
     for &distance in &distances {
         println!("\n--- Running VQE for distance: {} Å ---", distance);
-        let h2_hamiltonian = get_h2_hamiltonian_at_distance(distance);
+        let h2_hamiltonian = get_h2_hamiltonian_at_distance(distance)?;
 
         let simulator = StatevectorSimulator::new(2);
         let vqe_runner = VqeRunner::new(simulator, h2_hamiltonian, two_qubit_ansatz);
@@ -209,17 +498,32 @@ fn main() {
         let learning_rate = 0.4;
 
         let (final_energy, _) = vqe_runner.run(initial_params, steps, learning_rate);
-        results.push((distance, final_energy));
+        results.push(DissociationPoint {
+            distance,
+            energy: final_energy,
+        });
     }
 
     println!("\n\n--- H2 Dissociation Curve Results ---");
     println!("---------------------------------------");
     println!("| Distance (Å) | Ground State Energy |");
     println!("|--------------|---------------------|");
-    for (distance, energy) in results {
-        println!("| {:<12.2} | {:<19.8} |", distance, energy);
+    for p in &results {
+        println!("| {:<12.2} | {:<19.8} |", p.distance, p.energy);
     }
     println!("---------------------------------------");
+
+    if let Some(path) = cli.output {
+        let contents = if path.extension().is_some_and(|ext| ext == "csv") {
+            dissociation_curve_csv(&results)
+        } else {
+            dissociation_curve_json(&results).map_err(|e| e.to_string())?
+        };
+        std::fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        println!("Wrote dissociation curve to {}", path.display());
+    }
+    Ok(())
 }
 
 // --- Test Module ---
@@ -227,6 +531,8 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use qsim::mock::MockSimulator;
+    use vqa_runner::qcbm::AdamOptimizer;
 
     /// A simple ansatz for a single qubit problem.
     fn single_qubit_ansatz<S: Simulator>(simulator: &mut S, params: &[f64]) {
@@ -251,9 +557,9 @@ mod tests {
         let steps = 100;
         let learning_rate = 0.4;
 
+        let expected_energy = vqe_runner.hamiltonian.exact_ground_state_energy(1).unwrap();
         let (final_energy, _final_params) = vqe_runner.run(initial_params, steps, learning_rate);
 
-        let expected_energy = -1.0;
         assert!(
             (final_energy - expected_energy).abs() < 1e-6,
             "Final energy {} is not close to expected energy {}",
@@ -261,4 +567,296 @@ mod tests {
             expected_energy
         );
     }
+
+    #[test]
+    fn test_vqe_with_adam_converges_faster_than_gradient_descent() {
+        let hamiltonian = Hamiltonian::new().with_term(
+            PauliTerm::new()
+                .with_coefficient(1.0)
+                .with_pauli(0, hamiltonian::Pauli::Z),
+        );
+        let simulator = StatevectorSimulator::new(1);
+        let vqe_runner = VqeRunner::new(simulator, hamiltonian, single_qubit_ansatz);
+
+        let learning_rate = 0.1;
+        let converged = |energy: f64| (energy + 1.0).abs() < 1e-3;
+        let steps_needed =
+            |run: &dyn Fn(usize) -> f64| (1..=500).find(|&steps| converged(run(steps)));
+
+        let gd_steps = steps_needed(&|steps| vqe_runner.run(vec![0.1], steps, learning_rate).0)
+            .expect("gradient descent should converge");
+        let adam_steps = steps_needed(&|steps| {
+            let mut adam = AdamOptimizer::new(1, learning_rate);
+            vqe_runner.run_with_optimizer(vec![0.1], steps, &mut adam).0
+        })
+        .expect("Adam should converge");
+
+        assert!(
+            adam_steps < gd_steps,
+            "Adam took {} steps, gradient descent {}",
+            adam_steps,
+            gd_steps
+        );
+    }
+
+    #[test]
+    fn test_spsa_matches_parameter_shift_on_h2() {
+        let runner = VqeRunner::new(
+            StatevectorSimulator::new(2),
+            get_h2_hamiltonian_at_distance(0.74).unwrap(),
+            two_qubit_ansatz,
+        );
+        let initial_params = vec![0.1, 0.2, 0.3, 0.4];
+
+        let (shift_energy, _) = runner.run(initial_params.clone(), 100, 0.4);
+        let (spsa_energy, _) = runner.run_spsa(initial_params, 300, 2.0, 0.2);
+
+        assert!(
+            (spsa_energy - shift_energy).abs() < 0.05,
+            "SPSA reached {} Ha, parameter shift {} Ha",
+            spsa_energy,
+            shift_energy
+        );
+    }
+
+    #[test]
+    fn test_run_evaluates_cost_function_expected_number_of_times() {
+        let hamiltonian = Hamiltonian::new().with_term(
+            PauliTerm::new()
+                .with_coefficient(1.0)
+                .with_pauli(0, hamiltonian::Pauli::Z),
+        );
+        let simulator = MockSimulator::new(2).with_expectation(0.5);
+        let vqe_runner = VqeRunner::new(simulator.clone(), hamiltonian, two_qubit_ansatz);
+
+        let steps = 3;
+        let initial_params = vec![0.1, 0.2, 0.3, 0.4];
+        let (final_energy, final_params) = vqe_runner.run(initial_params.clone(), steps, 0.4);
+
+        // Two shifted evaluations per parameter per step, plus the final energy. Each
+        // evaluation resets its own clone and measures the single Hamiltonian term once.
+        let evaluations = steps * 2 * initial_params.len() + 1;
+        assert_eq!(simulator.reset_count(), evaluations);
+        assert_eq!(simulator.expectation_count(), evaluations);
+        // A constant energy has zero gradient, so the parameters never move.
+        assert_eq!(final_params, initial_params);
+        assert_eq!(final_energy, 0.5);
+    }
+
+    #[test]
+    fn test_gradient_prepares_each_shifted_state_once() {
+        let hamiltonian = get_h2_hamiltonian_at_distance(0.74).unwrap();
+        let num_terms = hamiltonian.terms.len();
+        let simulator = MockSimulator::new(2);
+        let vqe_runner = VqeRunner::new(simulator.clone(), hamiltonian, two_qubit_ansatz);
+
+        vqe_runner.gradient(&[0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(simulator.reset_count(), 2 * 4);
+        assert_eq!(simulator.expectation_count(), 2 * 4 * num_terms);
+    }
+
+    #[test]
+    fn test_gradient_matches_term_by_term_parameter_shift() {
+        let hamiltonian = get_h2_hamiltonian_at_distance(0.74).unwrap();
+        let params = [0.1, 0.2, 0.3, 0.4];
+
+        // Reference: shift each parameter and re-prepare the state for every single term.
+        let term_energy = |term: &PauliTerm, params: &[f64]| {
+            let single = Hamiltonian::new().with_term(term.clone());
+            VqeRunner::new(StatevectorSimulator::new(2), single, two_qubit_ansatz)
+                .cost_function(params)
+        };
+        let shift = std::f64::consts::FRAC_PI_2;
+        let expected: Vec<f64> = (0..params.len())
+            .map(|i| {
+                let mut plus = params;
+                plus[i] += shift;
+                let mut minus = params;
+                minus[i] -= shift;
+                hamiltonian
+                    .terms
+                    .iter()
+                    .map(|term| 0.5 * (term_energy(term, &plus) - term_energy(term, &minus)))
+                    .sum()
+            })
+            .collect();
+
+        let vqe_runner =
+            VqeRunner::new(StatevectorSimulator::new(2), hamiltonian, two_qubit_ansatz);
+        for (g, e) in vqe_runner.gradient(&params).iter().zip(&expected) {
+            assert!((g - e).abs() < 1e-10, "{} != {}", g, e);
+        }
+    }
+
+    #[test]
+    fn test_cost_cache_skips_repeated_evaluations() {
+        let hamiltonian = Hamiltonian::new().with_term(
+            PauliTerm::new()
+                .with_coefficient(1.0)
+                .with_pauli(0, hamiltonian::Pauli::Z),
+        );
+        let simulator = MockSimulator::new(2).with_expectation(0.5);
+        let vqe_runner =
+            VqeRunner::new(simulator.clone(), hamiltonian, two_qubit_ansatz).with_cost_cache(64);
+
+        // The energy is flat, so the parameters never move and every step after the first
+        // revisits the same shifted points.
+        let initial_params = vec![0.1, 0.2, 0.3, 0.4];
+        vqe_runner.run(initial_params.clone(), 3, 0.4);
+        let distinct_points = 2 * initial_params.len() + 1;
+        assert_eq!(simulator.reset_count(), distinct_points);
+        assert_eq!(
+            vqe_runner.cache_stats(),
+            Some((
+                3 * 2 * initial_params.len() + 1 - distinct_points,
+                distinct_points
+            ))
+        );
+    }
+
+    #[test]
+    fn test_cost_cache_does_not_change_optimization() {
+        let runner = |cached: bool| {
+            let runner = VqeRunner::new(
+                StatevectorSimulator::new(2),
+                get_h2_hamiltonian_at_distance(0.74).unwrap(),
+                two_qubit_ansatz,
+            );
+            if cached {
+                runner.with_cost_cache(1024)
+            } else {
+                runner
+            }
+        };
+        let initial_params = vec![0.1, 0.2, 0.3, 0.4];
+
+        let uncached = runner(false).run(initial_params.clone(), 50, 0.4);
+        let cached_runner = runner(true);
+        let cached = cached_runner.run(initial_params.clone(), 50, 0.4);
+        assert_eq!(cached, uncached);
+
+        // An identical second run is answered entirely from the cache.
+        let (_, misses) = cached_runner.cache_stats().unwrap();
+        assert_eq!(cached_runner.run(initial_params, 50, 0.4), cached);
+        assert_eq!(cached_runner.cache_stats().unwrap().1, misses);
+    }
+
+    #[test]
+    fn test_cost_cache_keys_keep_large_parameters_apart() {
+        assert_ne!(CostCache::key(&[1e7]), CostCache::key(&[1e7 + 1.0]));
+        assert_ne!(CostCache::key(&[1e300]), CostCache::key(&[-1e300]));
+        assert_eq!(CostCache::key(&[-0.0]), CostCache::key(&[0.0]));
+    }
+
+    #[test]
+    fn test_run_bounded_respects_tight_bounds() {
+        let runner = VqeRunner::new(
+            StatevectorSimulator::new(2),
+            get_h2_hamiltonian_at_distance(0.74).unwrap(),
+            two_qubit_ansatz,
+        );
+        let initial_params = vec![0.1, 0.2, 0.3, 0.4];
+        let bounds = [(0.0, 0.5), (-0.25, 0.25), (0.0, 0.5), (-0.5, 0.5)];
+
+        let initial_energy = runner.cost_function(&initial_params);
+        let (free_energy, _) = runner.run(initial_params.clone(), 100, 0.4);
+        let (bounded_energy, bounded_params) =
+            runner.run_bounded(initial_params, 100, 0.4, &bounds);
+
+        for (p, (lower, upper)) in bounded_params.iter().zip(bounds) {
+            assert!(
+                (lower..=upper).contains(p),
+                "{} outside [{}, {}]",
+                p,
+                lower,
+                upper
+            );
+        }
+        assert!(bounded_energy < initial_energy);
+        assert!(bounded_energy >= free_energy - 1e-9);
+    }
+
+    #[test]
+    fn test_apply_bound_wraps_full_turn_and_clamps_otherwise() {
+        use std::f64::consts::PI;
+        assert!((apply_bound(4.0, (-PI, PI)) - (4.0 - 2.0 * PI)).abs() < 1e-12);
+        assert!((apply_bound(-7.0, (0.0, 2.0 * PI)) - (4.0 * PI - 7.0)).abs() < 1e-12);
+        assert_eq!(apply_bound(4.0, (-1.0, 1.0)), 1.0);
+        assert_eq!(apply_bound(0.5, (-1.0, 1.0)), 0.5);
+    }
+
+    #[test]
+    fn test_run_stops_once_converged() {
+        let hamiltonian = Hamiltonian::new().with_term(
+            PauliTerm::new()
+                .with_coefficient(1.0)
+                .with_pauli(0, hamiltonian::Pauli::Z),
+        );
+        let simulator = MockSimulator::new(1).with_expectation(0.5);
+        let vqe_runner = VqeRunner::new(simulator.clone(), hamiltonian, single_qubit_ansatz)
+            .with_convergence(ConvergenceCriterion::new(1e-9, 0.0, 2));
+
+        // The energy is flat, so the criterion sees one best value and then two
+        // non-improving steps. Each step costs two shifted evaluations plus the check.
+        vqe_runner.run(vec![0.1], 100, 0.4);
+        assert_eq!(simulator.reset_count(), 3 * 3 + 1);
+    }
+
+    fn coefficients(hamiltonian: &Hamiltonian) -> Vec<f64> {
+        hamiltonian.terms.iter().map(|t| t.coefficient).collect()
+    }
+
+    #[test]
+    fn test_h2_hamiltonian_interpolates_between_tabulated_distances() {
+        // Tabulated distances are reproduced exactly.
+        let equilibrium = get_h2_hamiltonian_at_distance(0.74).unwrap();
+        assert_eq!(
+            coefficients(&equilibrium),
+            vec![-0.8126, 0.1712, -0.2228, 0.1686, 0.0453]
+        );
+
+        // 1.0 Å is a third of the way from 0.9 to 1.2.
+        let interpolated = get_h2_hamiltonian_at_distance(1.0).unwrap();
+        let expected = [-0.6964, 0.16063333, -0.20643333, 0.16286667, 0.0453];
+        for (c, e) in coefficients(&interpolated).iter().zip(expected) {
+            assert!((c - e).abs() < 1e-6, "{} != {}", c, e);
+        }
+    }
+
+    #[test]
+    fn test_h2_hamiltonian_out_of_range_is_an_error() {
+        assert!(get_h2_hamiltonian_at_distance(2.15).is_ok());
+        let err = get_h2_hamiltonian_at_distance(5.0).unwrap_err();
+        assert!(err.contains("5"), "{}", err);
+        assert!(get_h2_hamiltonian_at_distance(0.3).is_err());
+    }
+
+    #[test]
+    fn test_dissociation_curve_serialization() {
+        let points = [
+            DissociationPoint {
+                distance: 0.74,
+                energy: -1.125,
+            },
+            DissociationPoint {
+                distance: 2.1,
+                energy: -0.5,
+            },
+        ];
+
+        let json: serde_json::Value =
+            serde_json::from_str(&dissociation_curve_json(&points).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "distance": 0.74, "energy": -1.125 },
+                { "distance": 2.1, "energy": -0.5 }
+            ])
+        );
+
+        assert_eq!(
+            dissociation_curve_csv(&points),
+            "distance,energy\n0.74,-1.125\n2.1,-0.5\n"
+        );
+    }
 }
@@ -2,7 +2,11 @@ use rand::Rng;
 use rand::distributions::{Distribution, WeightedIndex};
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
+use crate::convergence::ConvergenceCriterion;
+use qflow_types::TaskProgress;
 use qsim::simulator::Simulator;
 use qsim::{Gate, StateVector};
 
@@ -56,17 +60,39 @@ pub struct AdamOptimizer {
 }
 
 impl AdamOptimizer {
-    /// Creates a new AdamOptimizer.
+    /// Creates a new AdamOptimizer with the canonical defaults beta1 = 0.9, beta2 = 0.999
+    /// and epsilon = 1e-8.
+    ///
+    /// Earlier versions used beta1 = 0.92; use [`AdamOptimizer::with_hyperparams`] to
+    /// reproduce those runs.
     ///
     /// # Arguments
     /// * `num_params` - The number of parameters to optimize.
     /// * `learning_rate` - The initial learning rate (alpha).
     pub fn new(num_params: usize, learning_rate: f64) -> Self {
+        Self::with_hyperparams(num_params, learning_rate, 0.9, 0.999, 1e-8)
+    }
+
+    /// Creates a new AdamOptimizer with explicit hyperparameters.
+    ///
+    /// # Arguments
+    /// * `num_params` - The number of parameters to optimize.
+    /// * `learning_rate` - The initial learning rate (alpha).
+    /// * `beta1` - Decay rate of the first moment estimate.
+    /// * `beta2` - Decay rate of the second moment estimate.
+    /// * `epsilon` - Added to the denominator for numerical stability.
+    pub fn with_hyperparams(
+        num_params: usize,
+        learning_rate: f64,
+        beta1: f64,
+        beta2: f64,
+        epsilon: f64,
+    ) -> Self {
         Self {
             learning_rate,
-            beta1: 0.92,
-            beta2: 0.999,
-            epsilon: 1e-8,
+            beta1,
+            beta2,
+            epsilon,
             m: vec![0.0; num_params],
             v: vec![0.0; num_params],
             t: 0,
@@ -92,6 +118,72 @@ impl Optimizer for AdamOptimizer {
     }
 }
 
+/// An implementation of the RMSProp optimizer.
+pub struct RmsPropOptimizer {
+    learning_rate: f64,
+    decay: f64,
+    epsilon: f64,
+    v: Vec<f64>, // running average of squared gradients
+}
+
+impl RmsPropOptimizer {
+    /// Creates a new RmsPropOptimizer with the conventional decay rate of 0.9.
+    ///
+    /// # Arguments
+    /// * `num_params` - The number of parameters to optimize.
+    /// * `learning_rate` - The step size for each update.
+    /// * `epsilon` - Added to the denominator for numerical stability.
+    pub fn new(num_params: usize, learning_rate: f64, epsilon: f64) -> Self {
+        Self {
+            learning_rate,
+            decay: 0.9,
+            epsilon,
+            v: vec![0.0; num_params],
+        }
+    }
+}
+
+impl Optimizer for RmsPropOptimizer {
+    fn update(&mut self, params: &mut [f64], grads: &[f64]) {
+        for i in 0..params.len() {
+            self.v[i] = self.decay * self.v[i] + (1.0 - self.decay) * grads[i].powi(2);
+            params[i] -= self.learning_rate * grads[i] / (self.v[i].sqrt() + self.epsilon);
+        }
+    }
+}
+
+/// An implementation of the Adagrad optimizer.
+pub struct AdagradOptimizer {
+    learning_rate: f64,
+    epsilon: f64,
+    g: Vec<f64>, // sum of squared gradients
+}
+
+impl AdagradOptimizer {
+    /// Creates a new AdagradOptimizer.
+    ///
+    /// # Arguments
+    /// * `num_params` - The number of parameters to optimize.
+    /// * `learning_rate` - The initial step size; it shrinks as squared gradients accumulate.
+    /// * `epsilon` - Added to the denominator for numerical stability.
+    pub fn new(num_params: usize, learning_rate: f64, epsilon: f64) -> Self {
+        Self {
+            learning_rate,
+            epsilon,
+            g: vec![0.0; num_params],
+        }
+    }
+}
+
+impl Optimizer for AdagradOptimizer {
+    fn update(&mut self, params: &mut [f64], grads: &[f64]) {
+        for i in 0..params.len() {
+            self.g[i] += grads[i].powi(2);
+            params[i] -= self.learning_rate * grads[i] / (self.g[i].sqrt() + self.epsilon);
+        }
+    }
+}
+
 pub struct QcbmRunner<S, F>
 where
     S: Simulator,
@@ -101,6 +193,9 @@ where
     training_data: Vec<String>,
     ansatz: F,
     num_qubits: usize,
+    /// RBF kernel bandwidths for the MMD loss; empty means the default `sqrt(num_qubits) / 2`.
+    sigmas: Vec<f64>,
+    cancel: Option<Arc<AtomicBool>>,
 }
 
 impl<S, F> QcbmRunner<S, F>
@@ -116,6 +211,62 @@ where
             training_data: training_data.to_vec(),
             ansatz,
             num_qubits,
+            sigmas: Vec::new(),
+            cancel: None,
+        }
+    }
+
+    /// Sets the RBF kernel bandwidth of the MMD loss. `None` restores the default of
+    /// `sqrt(num_qubits) / 2`.
+    pub fn with_sigma(mut self, sigma: Option<f64>) -> Self {
+        self.sigmas = sigma.into_iter().collect();
+        self
+    }
+
+    /// Uses a multi-bandwidth MMD whose kernel is the sum of RBF kernels with the given
+    /// sigmas. Mixing scales keeps the gradient informative both far from and close to
+    /// the target distribution.
+    pub fn with_sigmas(mut self, sigmas: &[f64]) -> Self {
+        self.sigmas = sigmas.to_vec();
+        self
+    }
+
+    /// Makes training interruptible: once `flag` is set, `train` and `train_exact` stop at
+    /// the start of the next epoch and leave the best parameters seen so far in `params`.
+    pub fn with_cancellation(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(flag);
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::SeqCst))
+    }
+
+    /// Checks for cancellation at the start of `epoch`, restoring the best parameters if
+    /// training should stop.
+    fn cancel_with_best(
+        &self,
+        epoch: usize,
+        params: &mut [f64],
+        best: &Option<(f64, Vec<f64>)>,
+    ) -> bool {
+        if !self.is_cancelled() {
+            return false;
+        }
+        println!("Training cancelled after {} epochs.", epoch);
+        if let Some((_, best_params)) = best {
+            params.copy_from_slice(best_params);
+        }
+        true
+    }
+
+    fn bandwidths(&self) -> Vec<f64> {
+        if self.sigmas.is_empty() {
+            vec![(self.num_qubits as f64).sqrt() / 2.0]
+        } else {
+            self.sigmas.clone()
         }
     }
 
@@ -196,13 +347,35 @@ where
         term1 + term2 - 2.0 * term3
     }
 
+    /// Computes the MMD loss for a kernel that is a sum of RBF kernels, one per sigma.
+    /// MMD is linear in the kernel, so this is the sum of the single-sigma losses.
+    fn mmd_multi_rbf_loss(
+        target_samples: &[String],
+        model_samples: &[String],
+        sigmas: &[f64],
+    ) -> f64 {
+        sigmas
+            .iter()
+            .map(|&sigma| Self::mmd_rbf_loss(target_samples, model_samples, sigma))
+            .sum()
+    }
+
     /// Trains the QCBM using a provided optimizer and MMD loss with an analytical gradient.
-    pub fn train<O: Optimizer>(&self, params: &mut [f64], optimizer: &mut O, epochs: usize) {
+    /// Returns the MMD loss recorded at each epoch. With a `convergence` criterion, training
+    /// stops early once it is met, so the history can be shorter than `epochs`; the same
+    /// goes for cancellation (see [`QcbmRunner::with_cancellation`]).
+    pub fn train<O: Optimizer>(
+        &self,
+        params: &mut [f64],
+        optimizer: &mut O,
+        epochs: usize,
+        mut convergence: Option<ConvergenceCriterion>,
+    ) -> Vec<f64> {
         println!("Starting training with MMD loss...");
 
         const NUM_MMD_SAMPLES: usize = 128;
         let mut rng = rand::thread_rng();
-        let sigma = (self.num_qubits as f64).sqrt() / 2.0;
+        let sigmas = self.bandwidths();
         let to_vec = |s: &String| {
             s.chars()
                 .map(|c| c.to_digit(10).unwrap() as f64)
@@ -210,10 +383,19 @@ where
         };
         let kernel = |v1: &[f64], v2: &[f64]| {
             let sq_dist: f64 = v1.iter().zip(v2.iter()).map(|(a, b)| (a - b).powi(2)).sum();
-            (-sq_dist / (2.0 * sigma.powi(2))).exp()
+            sigmas
+                .iter()
+                .map(|sigma| (-sq_dist / (2.0 * sigma.powi(2))).exp())
+                .sum::<f64>()
         };
 
+        let mut loss_history = Vec::with_capacity(epochs);
+        let mut best: Option<(f64, Vec<f64>)> = None;
+
         for epoch in 0..epochs {
+            if self.cancel_with_best(epoch, params, &best) {
+                break;
+            }
             let mut gradients = vec![0.0; params.len()];
 
             let model_samples = self.get_model_samples(params, NUM_MMD_SAMPLES);
@@ -255,22 +437,172 @@ where
                 gradients[i] = grad_i;
             }
 
+            // The loss is that of the parameters before this update.
+            let current_loss =
+                Self::mmd_multi_rbf_loss(&target_samples_for_epoch, &model_samples, &sigmas);
+            if best.as_ref().is_none_or(|(loss, _)| current_loss < *loss) {
+                best = Some((current_loss, params.to_vec()));
+            }
+
+            optimizer.update(params, &gradients);
+            loss_history.push(current_loss);
+
+            if (epoch + 1) % 10 == 0 || epoch == epochs - 1 {
+                println!(
+                    "Epoch {}/{} - Loss (MMD): {:.6}",
+                    epoch + 1,
+                    epochs,
+                    current_loss
+                );
+                let progress = TaskProgress {
+                    epoch: epoch + 1,
+                    total_epochs: epochs,
+                };
+                println!("{}", progress.to_log_line());
+            }
+
+            if let Some(criterion) = convergence.as_mut()
+                && criterion.should_stop(current_loss)
+            {
+                println!(
+                    "Converged after {} epochs - Loss (MMD): {:.6}",
+                    epoch + 1,
+                    current_loss
+                );
+                break;
+            }
+        }
+
+        println!("Training finished.");
+        println!("Final Parameters: {:?}", params);
+        loss_history
+    }
+
+    /// The empirical distribution of the training data.
+    fn target_distribution(&self) -> HashMap<String, f64> {
+        let weight = 1.0 / self.training_data.len() as f64;
+        let mut distribution = HashMap::new();
+        for sample in &self.training_data {
+            *distribution.entry(sample.clone()).or_insert(0.0) += weight;
+        }
+        distribution
+    }
+
+    /// Like [`QcbmRunner::train`], but computes the MMD loss and its parameter-shift
+    /// gradient directly from the exact model and target distributions instead of drawing
+    /// samples, so the result is deterministic for a given starting point. The cost is a
+    /// kernel evaluation over every pair of basis states, i.e. 4^n per epoch.
+    pub fn train_exact<O: Optimizer>(
+        &self,
+        params: &mut [f64],
+        optimizer: &mut O,
+        epochs: usize,
+        mut convergence: Option<ConvergenceCriterion>,
+    ) -> Vec<f64> {
+        println!("Starting exact training with MMD loss...");
+
+        let sigmas = self.bandwidths();
+        let num_states = 1 << self.num_qubits;
+        let bitstrings: Vec<String> = (0..num_states)
+            .map(|z| format!("{:0width$b}", z, width = self.num_qubits))
+            .collect();
+        // K[x][z] summed over all bandwidths; the Hamming distance is the squared distance
+        // between bit vectors.
+        let kernel: Vec<Vec<f64>> = (0..num_states)
+            .map(|x: usize| {
+                (0..num_states)
+                    .map(|z: usize| {
+                        let sq_dist = (x ^ z).count_ones() as f64;
+                        sigmas
+                            .iter()
+                            .map(|sigma| (-sq_dist / (2.0 * sigma.powi(2))).exp())
+                            .sum::<f64>()
+                    })
+                    .collect()
+            })
+            .collect();
+        let target = self.target_distribution();
+        let target_probs: Vec<f64> = bitstrings
+            .iter()
+            .map(|b| *target.get(b).unwrap_or(&0.0))
+            .collect();
+        let probabilities = |dist: &HashMap<String, f64>| -> Vec<f64> {
+            bitstrings
+                .iter()
+                .map(|b| *dist.get(b).unwrap_or(&0.0))
+                .collect()
+        };
+
+        let mut loss_history = Vec::with_capacity(epochs);
+        let mut best: Option<(f64, Vec<f64>)> = None;
+
+        for epoch in 0..epochs {
+            if self.cancel_with_best(epoch, params, &best) {
+                break;
+            }
+            let model_probs = probabilities(&self.get_model_distribution(params));
+            let diff: Vec<f64> = model_probs
+                .iter()
+                .zip(&target_probs)
+                .map(|(p, q)| p - q)
+                .collect();
+            // MMD = Σ_xz d(x) K(x, z) d(z) with d = p - q, so ∂MMD/∂p(z) = 2 Σ_x d(x) K(x, z).
+            let d_mmd_dp: Vec<f64> = (0..num_states)
+                .map(|z| 2.0 * (0..num_states).map(|x| diff[x] * kernel[x][z]).sum::<f64>())
+                .collect();
+            let current_loss: f64 = (0..num_states).map(|z| 0.5 * diff[z] * d_mmd_dp[z]).sum();
+
+            let mut gradients = vec![0.0; params.len()];
+            for i in 0..params.len() {
+                let mut params_plus = params.to_vec();
+                params_plus[i] += std::f64::consts::FRAC_PI_2;
+                let p_plus = probabilities(&self.get_model_distribution(&params_plus));
+
+                let mut params_minus = params.to_vec();
+                params_minus[i] -= std::f64::consts::FRAC_PI_2;
+                let p_minus = probabilities(&self.get_model_distribution(&params_minus));
+
+                gradients[i] = (0..num_states)
+                    .map(|z| d_mmd_dp[z] * 0.5 * (p_plus[z] - p_minus[z]))
+                    .sum();
+            }
+
+            if best.as_ref().is_none_or(|(loss, _)| current_loss < *loss) {
+                best = Some((current_loss, params.to_vec()));
+            }
+
             optimizer.update(params, &gradients);
+            loss_history.push(current_loss);
 
             if (epoch + 1) % 10 == 0 || epoch == epochs - 1 {
-                let current_loss =
-                    Self::mmd_rbf_loss(&target_samples_for_epoch, &model_samples, sigma);
                 println!(
                     "Epoch {}/{} - Loss (MMD): {:.6}",
                     epoch + 1,
                     epochs,
                     current_loss
                 );
+                let progress = TaskProgress {
+                    epoch: epoch + 1,
+                    total_epochs: epochs,
+                };
+                println!("{}", progress.to_log_line());
+            }
+
+            if let Some(criterion) = convergence.as_mut()
+                && criterion.should_stop(current_loss)
+            {
+                println!(
+                    "Converged after {} epochs - Loss (MMD): {:.6}",
+                    epoch + 1,
+                    current_loss
+                );
+                break;
             }
         }
 
         println!("Training finished.");
         println!("Final Parameters: {:?}", params);
+        loss_history
     }
 }
 
@@ -312,7 +644,7 @@ mod tests {
         let qcbm_runner = QcbmRunner::new(sim, simple_ry_ansatz, &training_data);
         let mut params = vec![0.1];
         let mut optimizer = AdamOptimizer::new(params.len(), 0.02);
-        qcbm_runner.train(&mut params, &mut optimizer, 100);
+        qcbm_runner.train(&mut params, &mut optimizer, 100, None);
 
         let final_param = params[0];
         assert!(
@@ -337,7 +669,7 @@ mod tests {
         let qcbm_runner = QcbmRunner::new(sim, entangling_ansatz, &training_data);
         let mut params = vec![0.2];
         let mut optimizer = AdamOptimizer::new(params.len(), 0.01);
-        qcbm_runner.train(&mut params, &mut optimizer, 100);
+        qcbm_runner.train(&mut params, &mut optimizer, 100, None);
 
         assert!(
             params[0].cos().abs() > 0.95,
@@ -355,6 +687,39 @@ mod tests {
         assert!(*p10 < 0.1, "P('10') should be ~0");
     }
 
+    #[test]
+    fn test_exact_training_is_reproducible_on_entangled_target() {
+        let training_data = vec![
+            "00".to_string(),
+            "11".to_string(),
+            "00".to_string(),
+            "11".to_string(),
+        ];
+
+        let run = || {
+            let sim = QuantumSimulator::new(2);
+            let qcbm_runner = QcbmRunner::new(sim, entangling_ansatz, &training_data);
+            let mut params = vec![0.2];
+            let mut optimizer = AdamOptimizer::new(params.len(), 0.01);
+            let history = qcbm_runner.train_exact(&mut params, &mut optimizer, 100, None);
+            (params, history)
+        };
+        let (first_params, first_history) = run();
+        let (second_params, second_history) = run();
+
+        // No sampling, so both runs follow exactly the same trajectory.
+        assert_eq!(first_params, second_params);
+        assert_eq!(first_history, second_history);
+        assert!(
+            first_history.last().unwrap() < first_history.first().unwrap(),
+            "Exact MMD should decrease"
+        );
+        assert!(
+            first_params[0].cos().abs() > 0.95,
+            "Parameter should converge to ~0"
+        );
+    }
+
     #[test]
     fn test_qcbm_training_with_gradient_descent() {
         let target_angle = (0.75_f64).sqrt().asin() * 2.0;
@@ -370,7 +735,7 @@ mod tests {
         let mut params = vec![0.1];
         // Using the new optimizer
         let mut optimizer = GradientDescentOptimizer::new(0.1);
-        qcbm_runner.train(&mut params, &mut optimizer, 100);
+        qcbm_runner.train(&mut params, &mut optimizer, 100, None);
 
         let final_param = params[0];
         assert!(
@@ -381,4 +746,164 @@ mod tests {
         assert!((final_dist.get("0").unwrap_or(&0.0) - 0.25).abs() < 0.1);
         assert!((final_dist.get("1").unwrap_or(&0.0) - 0.75).abs() < 0.1);
     }
+
+    #[test]
+    fn test_qcbm_training_with_rmsprop() {
+        let target_angle = (0.75_f64).sqrt().asin() * 2.0;
+        let training_data = vec![
+            "1".to_string(),
+            "1".to_string(),
+            "1".to_string(),
+            "0".to_string(),
+        ];
+
+        let sim = QuantumSimulator::new(1);
+        let qcbm_runner = QcbmRunner::new(sim, simple_ry_ansatz, &training_data);
+        let mut params = vec![0.1];
+        let mut optimizer = RmsPropOptimizer::new(params.len(), 0.02, 1e-8);
+        qcbm_runner.train(&mut params, &mut optimizer, 100, None);
+
+        let final_param = params[0];
+        assert!(
+            (final_param.cos() - target_angle.cos()).abs() < 0.2,
+            "Learned parameter is not close to target with RMSProp"
+        );
+        let final_dist = qcbm_runner.get_model_distribution(&params);
+        assert!((final_dist.get("0").unwrap_or(&0.0) - 0.25).abs() < 0.1);
+        assert!((final_dist.get("1").unwrap_or(&0.0) - 0.75).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_adagrad_step_shrinks_with_accumulated_gradients() {
+        let mut optimizer = AdagradOptimizer::new(1, 0.1, 0.0);
+        let mut params = vec![0.0];
+        optimizer.update(&mut params, &[2.0]);
+        // First step: 0.1 * 2 / sqrt(4) = 0.1
+        assert!((params[0] + 0.1).abs() < 1e-12);
+        optimizer.update(&mut params, &[2.0]);
+        // Second step: 0.1 * 2 / sqrt(8)
+        assert!((params[0] + 0.1 + 0.2 / 8f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_adam_first_step_with_custom_betas() {
+        let (lr, beta1, beta2, epsilon) = (0.1, 0.8, 0.99, 1e-6);
+        let mut optimizer = AdamOptimizer::with_hyperparams(2, lr, beta1, beta2, epsilon);
+        let grads = [0.5, -2.0];
+        let mut params = vec![1.0, 1.0];
+        optimizer.update(&mut params, &grads);
+
+        for (p, g) in params.iter().zip(grads) {
+            let m_hat = (1.0 - beta1) * g / (1.0 - beta1);
+            let v_hat = (1.0 - beta2) * g * g / (1.0 - beta2);
+            let expected = 1.0 - lr * m_hat / (f64::sqrt(v_hat) + epsilon);
+            assert!((p - expected).abs() < 1e-12, "{} != {}", p, expected);
+        }
+    }
+
+    #[test]
+    fn test_training_stops_early_once_converged() {
+        // The model already reproduces the target exactly, so the loss is flat at zero.
+        let training_data = vec!["0".to_string()];
+        let sim = QuantumSimulator::new(1);
+        let qcbm_runner = QcbmRunner::new(sim, simple_ry_ansatz, &training_data);
+        let mut params = vec![0.0];
+        let mut optimizer = AdamOptimizer::new(params.len(), 0.02);
+
+        let convergence = ConvergenceCriterion::new(1e-9, 0.0, 1);
+        let history = qcbm_runner.train(&mut params, &mut optimizer, 100, Some(convergence));
+        assert!(history.len() < 100, "ran all {} epochs", history.len());
+        assert!(history.iter().all(|loss| loss.abs() < 1e-12));
+    }
+
+    /// Sets `flag` after `cancel_after` updates, standing in for a user interrupting training.
+    struct CancellingOptimizer {
+        inner: AdamOptimizer,
+        updates: usize,
+        cancel_after: usize,
+        flag: Arc<AtomicBool>,
+    }
+
+    impl Optimizer for CancellingOptimizer {
+        fn update(&mut self, params: &mut [f64], grads: &[f64]) {
+            self.inner.update(params, grads);
+            self.updates += 1;
+            if self.updates == self.cancel_after {
+                self.flag.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cancellation_stops_training_with_best_params() {
+        let training_data = vec!["00".to_string(), "11".to_string()];
+        let flag = Arc::new(AtomicBool::new(false));
+        let sim = QuantumSimulator::new(2);
+        let qcbm_runner =
+            QcbmRunner::new(sim, entangling_ansatz, &training_data).with_cancellation(flag.clone());
+        let mut optimizer = CancellingOptimizer {
+            inner: AdamOptimizer::new(1, 0.1),
+            updates: 0,
+            cancel_after: 3,
+            flag,
+        };
+
+        let mut params = vec![0.3];
+        let history = qcbm_runner.train_exact(&mut params, &mut optimizer, 100, None);
+        assert_eq!(history.len(), 3);
+        assert!(params.iter().all(|p| p.is_finite()));
+
+        // The restored parameters are those of the lowest recorded loss.
+        let mut replay = vec![0.3];
+        let mut replay_optimizer = AdamOptimizer::new(1, 0.1);
+        let best_epoch = (0..history.len())
+            .min_by(|&a, &b| history[a].total_cmp(&history[b]))
+            .unwrap();
+        let uncancelled =
+            QcbmRunner::new(QuantumSimulator::new(2), entangling_ansatz, &training_data);
+        uncancelled.train_exact(&mut replay, &mut replay_optimizer, best_epoch, None);
+        assert_eq!(params, replay);
+    }
+
+    #[test]
+    fn test_multi_sigma_mmd_is_sum_of_single_sigma_losses() {
+        type Runner = QcbmRunner<QuantumSimulator, fn(&mut QuantumSimulator, &[f64])>;
+        let target: Vec<String> = ["00", "11", "00", "11"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let model: Vec<String> = ["00", "01", "10", "11"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let narrow = Runner::mmd_rbf_loss(&target, &model, 0.5);
+        let wide = Runner::mmd_rbf_loss(&target, &model, 2.0);
+        let multi = Runner::mmd_multi_rbf_loss(&target, &model, &[0.5, 2.0]);
+
+        assert!(narrow > 0.0 && wide > 0.0);
+        assert!((narrow - wide).abs() > 1e-3, "bandwidth should matter");
+        assert!((multi - (narrow + wide)).abs() < 1e-12);
+        assert!(Runner::mmd_multi_rbf_loss(&target, &target, &[0.5, 2.0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_qcbm_training_with_multiple_sigmas() {
+        let training_data = vec![
+            "1".to_string(),
+            "1".to_string(),
+            "1".to_string(),
+            "0".to_string(),
+        ];
+
+        let sim = QuantumSimulator::new(1);
+        let qcbm_runner =
+            QcbmRunner::new(sim, simple_ry_ansatz, &training_data).with_sigmas(&[0.25, 1.0]);
+        let mut params = vec![0.1];
+        let mut optimizer = AdamOptimizer::new(params.len(), 0.02);
+        qcbm_runner.train(&mut params, &mut optimizer, 100, None);
+
+        let final_dist = qcbm_runner.get_model_distribution(&params);
+        assert!((final_dist.get("1").unwrap_or(&0.0) - 0.75).abs() < 0.1);
+    }
 }
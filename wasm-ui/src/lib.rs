@@ -1,7 +1,7 @@
 use num_complex::Complex;
 use qsim::circuit::{Circuit, circuit_to_qasm};
 use qsim::simulator::Simulator;
-use qsim::{Gate, QuantumSimulator};
+use qsim::{QuantumSimulator, try_parse_qasm};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
@@ -216,3 +216,108 @@ pub fn compile_circuit_to_qasm(circuit_json: &str) -> String {
     // Return the QASM string.
     qasm
 }
+
+/// Parses a circuit from JSON and serializes it back in the same `{ type: "H", ... }`
+/// layout, so circuits edited on the Rust side can be handed back to the UI.
+#[wasm_bindgen]
+pub fn circuit_to_json(circuit_json: &str) -> String {
+    let circuit: Circuit = match serde_json::from_str(circuit_json) {
+        Ok(c) => c,
+        Err(e) => {
+            error(&format!("Error deserializing circuit: {}", e));
+            return serde_json::json!({ "error": format!("Failed to parse circuit: {}", e) })
+                .to_string();
+        }
+    };
+
+    serde_json::to_string(&circuit).unwrap_or_else(|e| {
+        error(&format!("Error serializing circuit: {}", e));
+        serde_json::json!({ "error": format!("Failed to serialize circuit: {}", e) }).to_string()
+    })
+}
+
+/// Checks that `qasm` declares a register and that every gate acts on a qubit inside it,
+/// with the same checks the simulator runs. Returns the register size and the number of gates.
+fn check_qasm(qasm: &str) -> Result<(usize, usize), String> {
+    let (num_qubits, gates) = try_parse_qasm(qasm).map_err(|e| e.to_string())?;
+    if num_qubits == 0 {
+        return Err("No qreg declaration found".to_string());
+    }
+    let num_gates = gates.len();
+    let mut circuit = Circuit::with_qubits(num_qubits);
+    for gate in gates {
+        circuit.add_gate(gate);
+    }
+    circuit.validate().map_err(|e| e.to_string())?;
+    Ok((num_qubits, num_gates))
+}
+
+/// Validates a QASM string for the editor, returning
+/// `{ "ok": true, "num_qubits": n, "num_gates": g }` or `{ "ok": false, "error": message }`.
+#[wasm_bindgen]
+pub fn validate_qasm(qasm: &str) -> String {
+    match check_qasm(qasm) {
+        Ok((num_qubits, num_gates)) => {
+            serde_json::json!({ "ok": true, "num_qubits": num_qubits, "num_gates": num_gates })
+        }
+        Err(message) => serde_json::json!({ "ok": false, "error": message }),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_json_round_trip() {
+        let input = serde_json::json!({
+            "numQubits": 2,
+            "moments": [
+                [{ "type": "H", "qubit": 0 }, { "type": "RZ", "qubit": 1, "theta": 0.5 }],
+                [{ "type": "CX", "control": 0, "target": 1 }],
+                [{ "type": "MeasureQubit", "qubit": 1, "creg_bit": 0 }],
+                [{ "type": "Measure" }]
+            ]
+        });
+
+        let output: serde_json::Value =
+            serde_json::from_str(&circuit_to_json(&input.to_string())).unwrap();
+        assert_eq!(output, input);
+
+        let circuit: Circuit = serde_json::from_value(output).unwrap();
+        assert_eq!(circuit.gate_count(), 5);
+    }
+
+    #[test]
+    fn test_validate_qasm() {
+        let valid = "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[2];\nh q[0];\ncx q[0],q[1];\n";
+        let result: serde_json::Value = serde_json::from_str(&validate_qasm(valid)).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({ "ok": true, "num_qubits": 2, "num_gates": 2 })
+        );
+
+        let out_of_range = "qreg q[2];\ncx q[2],q[0];\n";
+        let result: serde_json::Value = serde_json::from_str(&validate_qasm(out_of_range)).unwrap();
+        assert_eq!(result["ok"], false);
+        assert!(result["error"].as_str().unwrap().contains("cx q[2],q[0]"));
+
+        // a[2] is past the end of `a`, even though global qubit 2 (b[0]) exists.
+        let aliasing = "qreg a[2];\nqreg b[1];\nx a[2];\n";
+        let result: serde_json::Value = serde_json::from_str(&validate_qasm(aliasing)).unwrap();
+        assert_eq!(result["ok"], false);
+        assert!(result["error"].as_str().unwrap().contains("x a[2]"));
+
+        let undeclared = "qreg q[2];\nh r[3];\n";
+        assert_eq!(
+            check_qasm(undeclared),
+            Err("Invalid qubit index: 3".to_string())
+        );
+
+        assert_eq!(
+            check_qasm("h q[0];\n"),
+            Err("No qreg declaration found".to_string())
+        );
+    }
+}
@@ -4,6 +4,7 @@ export default function MlSvmPage() {
   const [dataFile, setDataFile] = useState(null);
   const [targetColumn, setTargetColumn] = useState("target");
   const [testSize, setTestSize] = useState(0.3);
+  const [claimName, setClaimName] = useState("svm-results");
 
   const [results, setResults] = useState(null);
   const [error, setError] = useState("");
@@ -28,7 +29,8 @@ export default function MlSvmPage() {
     formData.append("test_size", testSize);
 
     try {
-      const response = await fetch("http://localhost:3000/api/ml/svm", {
+      const query = new URLSearchParams({ claim_name: claimName });
+      const response = await fetch(`http://localhost:3000/api/ml/svm?${query}`, {
         method: "POST",
         body: formData,
       });
@@ -95,6 +97,18 @@ export default function MlSvmPage() {
               className="bg-slate-800 border border-slate-600 rounded-md py-2 px-3 text-white w-full mt-1"
             />
           </div>
+          <div>
+            <label htmlFor="ml-claim-name" className="block text-sm">
+              Results Volume Claim
+            </label>
+            <input
+              type="text"
+              id="ml-claim-name"
+              value={claimName}
+              onChange={(e) => setClaimName(e.target.value)}
+              className="bg-slate-800 border border-slate-600 rounded-md py-2 px-3 text-white w-full mt-1"
+            />
+          </div>
           <div className="flex justify-end">
             <button
               onClick={handleRun}